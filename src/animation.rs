@@ -0,0 +1,165 @@
+// Animated GIF and WebP playback. All frames are decoded up front, composited
+// to the full canvas, and uploaded as one texture each; frames stop being
+// decoded once the animation would exceed the image texture budget. Playback
+// runs off frame delays, repainting only when the next frame is due.
+// Multi-page files (pages.rs) reuse the frames, picked instead of played.
+
+use eframe::egui;
+use image::AnimationDecoder;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Browsers stretch near-zero GIF delays to this
+const MIN_FRAME_DELAY: Duration = Duration::from_millis(20);
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+pub(crate) const SPEEDS: &[f32] = &[0.25, 0.5, 1.0, 1.5, 2.0, 4.0];
+
+/// Decoded frames of an animation, with how long each stays on screen.
+pub(crate) struct Frames {
+    pub(crate) frames: Vec<(egui::ColorImage, Duration)>,
+    /// Frames were left out to stay within the texture budget.
+    pub(crate) truncated: bool,
+    /// Names of the pages of a multi-page file, one per frame; empty for
+    /// animations. Pages are stepped through by hand, never played.
+    pub(crate) pages: Vec<String>,
+    /// Frame shown first.
+    pub(crate) first: usize,
+}
+
+/// Decode every frame of an animated GIF or WebP. Returns None for other
+/// formats and single-frame files, which decode as still images.
+pub(crate) fn decode(bytes: &[u8]) -> Result<Option<Frames>, String> {
+    let err = |e: image::ImageError| format!("Failed to open image: {}", e);
+    let frames = match image::guess_format(bytes) {
+        Ok(image::ImageFormat::Gif) => image::codecs::gif::GifDecoder::new(Cursor::new(bytes)).map_err(err)?.into_frames(),
+        Ok(image::ImageFormat::WebP) => {
+            let decoder = image::codecs::webp::WebPDecoder::new(Cursor::new(bytes)).map_err(err)?;
+            if !decoder.has_animation() { return Ok(None); }
+            decoder.into_frames()
+        }
+        _ => return Ok(None),
+    };
+    let mut out = Frames { frames: Vec::new(), truncated: false, pages: Vec::new(), first: 0 };
+    let mut total_bytes = 0usize;
+    for frame in frames {
+        let frame = frame.map_err(err)?;
+        let (numer, denom) = frame.delay().numer_denom_ms();
+        let delay = Duration::from_secs_f64(numer as f64 / denom.max(1) as f64 / 1000.0);
+        let delay = if delay < MIN_FRAME_DELAY { DEFAULT_FRAME_DELAY } else { delay };
+        let buffer = frame.into_buffer();
+        total_bytes = total_bytes.saturating_add(buffer.as_raw().len());
+        if total_bytes > crate::io::MAX_IMAGE_TEXTURE_BYTES {
+            if out.frames.is_empty() {
+                return Err(format!("Image too large: {}x{}", buffer.width(), buffer.height()));
+            }
+            out.truncated = true;
+            break;
+        }
+        let size = [buffer.width() as usize, buffer.height() as usize];
+        out.frames.push((egui::ColorImage::from_rgba_unmultiplied(size, buffer.as_raw()), delay));
+    }
+    Ok((out.frames.len() > 1).then_some(out))
+}
+
+/// Playback state of the animation on screen.
+pub(crate) struct Playback {
+    pub(crate) path: PathBuf,
+    textures: Vec<egui::TextureHandle>,
+    // CPU copies of the frames, for the pixel inspector
+    images: Vec<Arc<egui::ColorImage>>,
+    delays: Vec<Duration>,
+    pub(crate) current: usize,
+    pub(crate) playing: bool,
+    pub(crate) speed: f32,
+    pub(crate) truncated: bool,
+    pages: Vec<String>,
+    // When the current frame went up; None while paused
+    shown_at: Option<Instant>,
+}
+
+impl Playback {
+    pub(crate) fn new(ctx: &egui::Context, path: PathBuf, frames: Frames) -> Self {
+        let name = path.to_string_lossy().into_owned();
+        let mut textures = Vec::with_capacity(frames.frames.len());
+        let mut images = Vec::with_capacity(frames.frames.len());
+        let mut delays = Vec::with_capacity(frames.frames.len());
+        for (i, (image, delay)) in frames.frames.into_iter().enumerate() {
+            let image = Arc::new(image);
+            textures.push(ctx.load_texture(format!("{}#{}", name, i), image.clone(), crate::io::IMAGE_TEXTURE_OPTIONS));
+            images.push(image);
+            delays.push(delay);
+        }
+        let playing = frames.pages.is_empty();
+        Self {
+            path,
+            textures,
+            images,
+            delays,
+            current: frames.first,
+            playing,
+            speed: 1.0,
+            truncated: frames.truncated,
+            pages: frames.pages,
+            shown_at: None,
+        }
+    }
+
+    /// Page names of a multi-page file; empty for animations.
+    pub(crate) fn pages(&self) -> &[String] {
+        &self.pages
+    }
+
+    pub(crate) fn frame_count(&self) -> usize {
+        self.textures.len()
+    }
+
+    pub(crate) fn texture(&self) -> &egui::TextureHandle {
+        &self.textures[self.current]
+    }
+
+    pub(crate) fn image(&self) -> &Arc<egui::ColorImage> {
+        &self.images[self.current]
+    }
+
+    /// Delay of the current frame at normal speed.
+    pub(crate) fn delay(&self) -> Duration {
+        self.delays[self.current]
+    }
+
+    /// Advance to the frame due now and schedule a repaint for the next one.
+    pub(crate) fn tick(&mut self, ctx: &egui::Context) {
+        if !self.playing { return; }
+        let now = Instant::now();
+        let mut shown_at = *self.shown_at.get_or_insert(now);
+        // Catch up on frames missed while the window was not repainting, but
+        // start over rather than run through more than a whole loop
+        let cycle: Duration = self.delays.iter().sum::<Duration>().div_f32(self.speed);
+        if now.duration_since(shown_at) > cycle { shown_at = now; }
+        loop {
+            let due = shown_at + self.delays[self.current].div_f32(self.speed);
+            if due > now {
+                ctx.request_repaint_after(due - now);
+                break;
+            }
+            self.current = (self.current + 1) % self.textures.len();
+            shown_at = due;
+        }
+        self.shown_at = Some(shown_at);
+    }
+
+    pub(crate) fn toggle_playing(&mut self) {
+        if !self.pages.is_empty() { return; }
+        self.playing = !self.playing;
+        self.shown_at = None;
+    }
+
+    /// Show the next (or previous) frame and pause.
+    pub(crate) fn step(&mut self, forward: bool) {
+        let n = self.textures.len();
+        self.current = if forward { (self.current + 1) % n } else { (self.current + n - 1) % n };
+        self.playing = false;
+        self.shown_at = None;
+    }
+}
@@ -1,20 +1,36 @@
 use eframe::egui;
 use crate::themes::CodeTheme;
+use crate::loader::{LoadPurpose, Loaded};
 use egui::{RichText, TextureHandle};
 use std::fs;
-use rfd::FileDialog;
+use crate::filetype::FileKind;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::sync::mpsc::{channel, Receiver, TryRecvError};
 use std::thread;
+use std::time::{Duration, Instant};
 
-const MAX_FILE_SIZE_BYTES: u64 = 10_000_000; // 10MB
+pub(crate) const MAX_FILE_SIZE_BYTES: u64 = 10_000_000; // 10MB
+pub(crate) const MAX_IMAGE_ZOOM: f32 = 32.0;
 const MAX_RECENT_FILES: usize = 10;
-const BIG_TEXT_CHAR_THRESHOLD: usize = 500_000; // Disable heavy features beyond this
-pub(crate) const HIGHLIGHT_CHAR_THRESHOLD: usize = 200_000; // Disable syntax/mark highlights beyond this
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(500);
 
 pub enum Content {
-    Text(String),
+    Text(crate::document::TextDoc),
     Image(TextureHandle),
+    Hex(crate::hex::HexDoc),
+}
+
+impl Content {
+    /// The file kind this content shows, for navigating to sibling files.
+    pub(crate) fn kind(&self) -> FileKind {
+        match self {
+            Content::Text(_) => FileKind::Text,
+            Content::Image(_) => FileKind::Image,
+            Content::Hex(_) => FileKind::Binary,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -23,17 +39,62 @@ pub struct TextTab {
     pub text: String,
     pub is_lossy: bool,
     pub line_count: usize,
+    pub encoding: &'static encoding_rs::Encoding,
+    // Set for files over MAX_FILE_SIZE_BYTES; `text` stays empty then
+    pub paged: Option<std::sync::Arc<crate::paged::PagedFile>>,
+    // Find matches of `text`, kept across tab switches
+    pub matches: crate::match_index::SharedMatches,
+}
+
+/// Text files this large are memory-mapped instead of read into memory.
+pub(crate) fn needs_paging(path: &std::path::Path) -> bool {
+    fs::metadata(path).is_ok_and(|m| m.len() > MAX_FILE_SIZE_BYTES)
+}
+
+impl TextTab {
+    /// Read a text file for a tab, or page it in on demand (read-only,
+    /// indexed in the background) when it is too large to hold in memory.
+    /// Paged files are always read as UTF-8; `encoding` applies otherwise
+    /// (None detects it).
+    pub(crate) fn load(path: &std::path::Path, encoding: Option<&'static encoding_rs::Encoding>, ctx: &egui::Context) -> Result<Self, String> {
+        if needs_paging(path) {
+            let paged = crate::paged::PagedFile::open(path, ctx)?;
+            return Ok(Self { path: path.to_path_buf(), text: String::new(), is_lossy: false, line_count: 0, encoding: encoding_rs::UTF_8, paged: Some(paged), matches: Default::default() });
+        }
+        Ok(Self::decoded(path.to_path_buf(), crate::io::load_text(path, encoding)?))
+    }
+
+    pub(crate) fn decoded(path: PathBuf, decoded: crate::encoding::DecodedText) -> Self {
+        Self { path, text: decoded.text, is_lossy: decoded.is_lossy, line_count: decoded.line_count, encoding: decoded.encoding, paged: None, matches: Default::default() }
+    }
+
+    pub(crate) fn doc(&self) -> crate::document::TextDoc {
+        match &self.paged {
+            Some(paged) => crate::document::TextDoc::paged(paged.clone()),
+            None => crate::document::TextDoc::new(self.text.clone()).with_matches(self.matches.clone()),
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct GlobalSearchResult {
-    pub tab_index: usize,
+    /// None for matches in files on disk that were not open when searched.
+    pub tab_index: Option<usize>,
     pub path: PathBuf,
     pub line_index: usize,
     pub snippet: String,
+    /// Byte range of the match within `snippet`.
+    pub match_range: std::ops::Range<usize>,
     pub match_index_in_tab: usize,
 }
 
+// Two files to diff and the texts of those read so far
+struct PendingDiff {
+    left: PathBuf,
+    right: PathBuf,
+    texts: HashMap<PathBuf, String>,
+}
+
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)]
 pub struct FileViewerApp {
@@ -64,10 +125,56 @@ pub struct FileViewerApp {
     pub(crate) image_fit: bool,
     // Derived/runtime-only state for text rendering
     #[serde(skip)]
-    pub(crate) text_is_big: bool,
-    #[serde(skip)]
     pub(crate) text_line_count: usize,
     #[serde(skip)]
+    pub(crate) text_view: crate::central::TextViewState,
+    #[serde(skip)]
+    pub(crate) loader: Option<crate::loader::Loader>,
+    #[serde(skip)]
+    pub(crate) hex_view: crate::hex::HexView,
+    pub(crate) markdown_mode: crate::markdown::MarkdownMode,
+    #[serde(skip)]
+    pub(crate) markdown_preview: crate::markdown::PreviewState,
+    pub(crate) json_mode: crate::json::JsonMode,
+    #[serde(skip)]
+    pub(crate) json_tree: crate::json::JsonTreeState,
+    pub(crate) table_mode: crate::table::TableMode,
+    #[serde(skip)]
+    pub(crate) table: crate::table::TableState,
+    pub(crate) diff_layout: crate::diff::DiffLayout,
+    // Shown instead of the current content while open
+    #[serde(skip)]
+    pub(crate) diff: Option<crate::diff::DiffView>,
+    // A diff waiting for files read in the background
+    #[serde(skip)]
+    pending_diff: Option<PendingDiff>,
+    pub(crate) image_compare_mode: crate::compare::CompareMode,
+    // Like `diff`, shown instead of the current content while open
+    #[serde(skip)]
+    pub(crate) image_compare: Option<crate::compare::ImageCompare>,
+    // Frames of the image on screen when it is animated
+    #[serde(skip)]
+    pub(crate) animation: Option<crate::animation::Playback>,
+    pub(crate) show_image_info: bool,
+    #[serde(skip)]
+    pub(crate) image_info: crate::metadata::MetadataState,
+    // CPU copy of the still image on screen, for the pixel inspector
+    #[serde(skip)]
+    pub(crate) image_pixels: Option<Arc<egui::ColorImage>>,
+    // Float pixels and exposure of the image on screen when it is HDR/EXR
+    #[serde(skip)]
+    pub(crate) hdr: Option<crate::hdr::HdrView>,
+    // Document of the image on screen when it is an SVG
+    #[serde(skip)]
+    pub(crate) svg: Option<crate::svg::SvgView>,
+    pub(crate) pixel_inspector: bool,
+    pub(crate) pixel_copy_format: crate::inspector::ColorFormat,
+    #[serde(skip)]
+    pub(crate) inspector: crate::inspector::InspectorState,
+    // Pan, rotation and mirroring of the image on screen
+    #[serde(skip)]
+    pub(crate) image_view: crate::transform::ImageView,
+    #[serde(skip)]
     pub(crate) text_is_lossy: bool,
     // Tabs: keep opened text files for global search and fast switching
     #[serde(skip)]
@@ -88,9 +195,23 @@ pub struct FileViewerApp {
     pub(crate) search_count: usize,
     #[serde(skip)]
     pub(crate) search_current: usize,
+    // The match index for the query is still being built
+    #[serde(skip)]
+    pub(crate) search_counting: bool,
+    // Case, whole-word and regex switches for Find (persisted)
+    pub(crate) find_options: crate::search::FindOptions,
+    // Find query and options last compiled, with the matcher or the regex error
+    #[serde(skip)]
+    find_compiled: Option<((String, crate::search::FindOptions), Result<crate::search::Matcher, String>)>,
     // Optional direct scroll target line for precise jumps
     #[serde(skip)]
     pub(crate) scroll_target_line: Option<usize>,
+    // Column (0-based, in characters) for the caret on the target line
+    #[serde(skip)]
+    pub(crate) scroll_target_column: Option<usize>,
+    // Line and column to jump to when a tab opened in the background is first shown
+    #[serde(skip)]
+    pub(crate) pending_jumps: HashMap<PathBuf, (usize, Option<usize>)>,
     // Recent files window toggle
     #[serde(skip)]
     pub(crate) show_recent_window: bool,
@@ -100,15 +221,16 @@ pub struct FileViewerApp {
     #[serde(skip)]
     pub(crate) global_query: String,
     #[serde(skip)]
-    pub(crate) global_case_sensitive: bool,
-    #[serde(skip)]
-    pub(crate) global_whole_word: bool,
-    #[serde(skip)]
-    pub(crate) global_regex: bool,
+    pub(crate) global_options: crate::search::FindOptions,
     #[serde(skip)]
-    pub(crate) global_results: Vec<GlobalSearchResult>,
+    pub(crate) global_results: crate::search::GlobalResults,
     #[serde(skip)]
     pub(crate) global_error: Option<String>,
+    // Search a folder on disk instead of the open tabs
+    pub(crate) global_in_folder: bool,
+    pub(crate) global_folder: crate::folder_search::FolderOptions,
+    #[serde(skip)]
+    pub(crate) folder_search: Option<crate::folder_search::FolderSearch>,
     // Session restore (persisted)
     pub restore_session: bool,
     pub session_paths: Vec<PathBuf>,
@@ -120,6 +242,29 @@ pub struct FileViewerApp {
     pub(crate) file_open_rx: Option<Receiver<Option<PathBuf>>>,
     #[serde(skip)]
     pub(crate) file_open_in_flight: bool,
+    // Single-instance mode: later launches forward their files to this window
+    pub(crate) single_instance: bool,
+    #[serde(skip)]
+    pub(crate) instance_server: Option<crate::instance::InstanceServer>,
+    // File watching: reload tabs when their file changes on disk
+    #[serde(skip)]
+    pub(crate) watcher: Option<crate::watch::FileWatcher>,
+    #[serde(skip)]
+    pub(crate) no_auto_reload: HashSet<PathBuf>,
+    #[serde(skip)]
+    pub(crate) changed_on_disk: HashSet<PathBuf>,
+    #[serde(skip)]
+    pub(crate) missing_on_disk: HashSet<PathBuf>,
+    // Follow mode (tail -f) per text tab path
+    #[serde(skip)]
+    pub(crate) following: HashMap<PathBuf, crate::tail::FollowState>,
+    #[serde(skip)]
+    pub(crate) last_follow_poll: Option<Instant>,
+    // Encoding picked via "Reopen with encoding…", by file path
+    pub(crate) encoding_overrides: HashMap<PathBuf, String>,
+    // Binary files and SVGs opened as text ("Open as text", "Source") until their tab closes
+    #[serde(skip)]
+    pub(crate) forced_text: HashSet<PathBuf>,
     // Runtime
     #[serde(skip)]
     pub(crate) viewport_initialized: bool,
@@ -127,12 +272,13 @@ pub struct FileViewerApp {
 
 impl FileViewerApp {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        if let Some(storage) = cc.storage
+        // An explicit --settings file takes precedence over eframe's own storage
+        if !crate::settings::has_settings_path_override()
+            && let Some(storage) = cc.storage
             && let Some(s) = storage.get_string(eframe::APP_KEY)
             && let Ok(mut app) = serde_json::from_str::<FileViewerApp>(&s)
         {
             // ensure runtime-only fields are initialized
-            app.text_is_big = false;
             app.text_line_count = 0;
             app.text_is_lossy = false;
             app.open_text_tabs = Vec::new();
@@ -146,10 +292,8 @@ impl FileViewerApp {
             app.show_recent_window = false;
             app.show_global_search_window = false;
             app.global_query = String::new();
-            app.global_case_sensitive = false;
-            app.global_whole_word = false;
-            app.global_regex = false;
-            app.global_results = Vec::new();
+            app.global_options = Default::default();
+            app.global_results = Default::default();
             app.global_error = None;
             // Keep any previously persisted session fields; ensure runtime flags
             app.session_restored = false;
@@ -159,7 +303,6 @@ impl FileViewerApp {
             return app;
         }
         if let Some(mut app) = crate::settings::load_settings_from_disk() {
-            app.text_is_big = false;
             app.text_line_count = 0;
             app.text_is_lossy = false;
             app.open_text_tabs = Vec::new();
@@ -173,10 +316,8 @@ impl FileViewerApp {
             app.show_recent_window = false;
             app.show_global_search_window = false;
             app.global_query = String::new();
-            app.global_case_sensitive = false;
-            app.global_whole_word = false;
-            app.global_regex = false;
-            app.global_results = Vec::new();
+            app.global_options = Default::default();
+            app.global_results = Default::default();
             app.global_error = None;
             app.session_restored = false;
             app.file_open_rx = None;
@@ -191,97 +332,249 @@ impl FileViewerApp {
         crate::style::apply_theme(self, ctx);
     }
 
+    /// Apply command-line options after construction: open the named files as
+    /// tabs (first one active), then seed the jump target and Find query.
+    pub(crate) fn apply_cli(&mut self, cli: crate::cli::CliArgs, ctx: &egui::Context) {
+        if let Some(theme) = cli.theme {
+            self.code_theme = theme;
+        }
+        // Explicit files replace the restored session for this run
+        if cli.no_session || !cli.files.is_empty() {
+            self.session_restored = true;
+        }
+
+        self.open_requests(cli.files, cli.line, ctx);
+
+        if let Some(query) = cli.search {
+            self.search_query = query;
+            // Counted when the first file finishes loading
+            self.search_current = 0;
+            // Focus the Find field once a file is on screen
+            self.search_active = true;
+        }
+    }
+
+    /// Open each request as a tab and make the first one active. Every tab
+    /// jumps to its line and column when shown; the first falls back to
+    /// `default_line`. Load errors land in `error_message`.
+    pub(crate) fn open_requests(&mut self, files: Vec<crate::cli::OpenRequest>, default_line: Option<usize>, ctx: &egui::Context) {
+        for (i, req) in files.into_iter().enumerate() {
+            let line = req.line.or(default_line.filter(|_| i == 0)).map(|l| l.saturating_sub(1));
+            // The column belongs to the line written with it
+            let column = req.line.and(req.column).map(|c| c.saturating_sub(1));
+            self.request_load(req.path, LoadPurpose::Open { activate: i == 0, line, column }, ctx);
+        }
+    }
+
+    /// Start or stop listening for files forwarded by other launches so the
+    /// socket follows the `single_instance` setting.
+    pub(crate) fn sync_instance_server(&mut self, ctx: &egui::Context) {
+        if self.single_instance {
+            if self.instance_server.is_none() {
+                self.instance_server = crate::instance::InstanceServer::bind(ctx.clone());
+            }
+        } else {
+            self.instance_server = None;
+        }
+    }
+
     // io helpers moved to crate::io
 
+    /// Open `path` and switch to it once it has loaded in the background.
     pub fn load_file(&mut self, path: PathBuf, ctx: &egui::Context) {
-        self.content = None;
-        self.error_message = None;
-        self.current_path = None;
+        self.request_load(path, LoadPurpose::Open { activate: true, line: None, column: None }, ctx);
+    }
 
-        if let Ok(metadata) = fs::metadata(&path)
-            && metadata.len() > MAX_FILE_SIZE_BYTES
-        {
-            self.error_message = Some(format!(
-                "File is too large (> {:.1}MB)",
-                MAX_FILE_SIZE_BYTES as f64 / 1_000_000.0
-            ));
+    pub(crate) fn request_load(&mut self, path: PathBuf, purpose: LoadPurpose, ctx: &egui::Context) {
+        if purpose != LoadPurpose::Reload { self.error_message = None; }
+        let encoding = self.encoding_for(&path);
+        let as_text = self.forced_text.contains(&path);
+        self.loader.get_or_insert_with(|| crate::loader::Loader::new(ctx)).start(path, purpose, encoding, as_text);
+    }
+
+    /// The encoding chosen for `path` with "Reopen with encoding…", if any.
+    pub(crate) fn encoding_for(&self, path: &std::path::Path) -> Option<&'static encoding_rs::Encoding> {
+        self.encoding_overrides.get(path).and_then(|name| crate::encoding::by_name(name))
+    }
+
+    /// Re-decode an open text tab as `encoding` (None goes back to detection)
+    /// and remember the choice for the next time the file is opened. Paged
+    /// tabs are always UTF-8 and keep no choice.
+    pub(crate) fn reopen_with_encoding(&mut self, path: PathBuf, encoding: Option<&'static encoding_rs::Encoding>, ctx: &egui::Context) {
+        if self.open_text_tabs.iter().any(|t| t.path == path && t.paged.is_some()) {
+            self.error_message = Some("Reopen with encoding is not available for paged (very large) files".to_string());
             return;
         }
+        match encoding {
+            Some(enc) => { self.encoding_overrides.insert(path.clone(), enc.name().to_string()); }
+            None => { self.encoding_overrides.remove(&path); }
+        }
+        crate::settings::save_settings_to_disk(self);
+        self.request_load(path, LoadPurpose::Reload, ctx);
+    }
+
+    /// Stop an in-flight load, e.g. when its placeholder tab is closed.
+    pub(crate) fn cancel_load(&mut self, path: &PathBuf) {
+        if let Some(loader) = self.loader.as_mut() { loader.cancel(path); }
+    }
+
+    pub(crate) fn pending_loads(&self) -> &[crate::loader::PendingLoad] {
+        self.loader.as_ref().map(|l| l.pending.as_slice()).unwrap_or(&[])
+    }
 
-        let loaded = if crate::io::is_supported_image(&path) {
-            match crate::io::load_image(&path) {
-                Ok(color_image) => {
-                    let texture = ctx.load_texture(
-                        path.to_string_lossy(),
-                        color_image,
-                        egui::TextureOptions::LINEAR,
-                    );
-                    // Track in image tabs
-                    let mut exists = false;
-                    for p in &self.open_image_tabs { if p == &path { exists = true; break; } }
-                    if !exists { self.open_image_tabs.push(path.clone()); }
-                    self.active_image_tab = self.open_image_tabs.iter().position(|p| p == &path);
-                    Ok(Content::Image(texture))
+    // Apply loads that finished on the worker threads
+    fn process_loads(&mut self, ctx: &egui::Context) {
+        let Some(loader) = self.loader.as_mut() else { return; };
+        for done in loader.poll() {
+            let loaded = match done.result {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    self.error_message = Some(format!("{}: {}", done.path.display(), e));
+                    if done.purpose == LoadPurpose::Compare { self.pending_diff = None; }
+                    continue;
                 }
-                Err(e) => Err(e),
-            }
-        } else {
-            match crate::io::load_text(&path) {
-                Ok((text, lossy, lines)) => {
-                    self.text_is_big = text.len() >= BIG_TEXT_CHAR_THRESHOLD || lines >= 50_000;
-                    self.text_line_count = lines;
-                    self.text_is_lossy = lossy;
-                    // Update or insert text tab
-                    let mut tab_idx_opt = None;
-                    for (idx, t) in self.open_text_tabs.iter().enumerate() {
-                        if t.path == path { tab_idx_opt = Some(idx); break; }
+            };
+            match done.purpose {
+                LoadPurpose::Open { activate: true, line, column } => {
+                    self.show_loaded(done.path, loaded, ctx);
+                    if line.is_some() {
+                        self.scroll_target_line = line;
+                        self.scroll_target_column = column;
                     }
-                    match tab_idx_opt {
-                        Some(idx) => {
-                            self.open_text_tabs[idx] = TextTab { path: path.clone(), text: text.clone(), is_lossy: lossy, line_count: lines };
-                            self.active_text_tab = Some(idx);
-                        }
-                        None => {
-                            self.open_text_tabs.push(TextTab { path: path.clone(), text: text.clone(), is_lossy: lossy, line_count: lines });
-                            self.active_text_tab = Some(self.open_text_tabs.len() - 1);
+                }
+                LoadPurpose::Open { activate: false, line, column } => match loaded {
+                    Loaded::Text(tab) => {
+                        if let Some(line) = line { self.pending_jumps.insert(tab.path.clone(), (line, column)); }
+                        // Add text as background tab without switching
+                        if !self.open_text_tabs.iter().any(|t| t.path == tab.path) {
+                            self.open_text_tabs.push(tab);
+                            if self.active_text_tab.is_none() { self.active_text_tab = Some(self.open_text_tabs.len() - 1); }
                         }
                     }
-                    Ok(Content::Text(text))
+                    // Hex views are not kept as tabs
+                    Loaded::Hex(_) => {}
+                    _ => {
+                        if !self.open_image_tabs.contains(&done.path) { self.open_image_tabs.push(done.path); }
+                    }
+                },
+                LoadPurpose::Reload => self.apply_reload(done.path, loaded, ctx),
+                LoadPurpose::Compare => self.apply_diff_text(done.path, loaded),
+            }
+        }
+    }
+
+    fn show_loaded(&mut self, path: PathBuf, loaded: Loaded, ctx: &egui::Context) {
+        self.clear_image_state();
+        let content = match loaded {
+            Loaded::Text(tab) => {
+                self.text_line_count = tab.line_count;
+                self.text_is_lossy = tab.is_lossy;
+                let doc = tab.doc();
+                // Update or insert text tab
+                match self.open_text_tabs.iter().position(|t| t.path == path) {
+                    Some(idx) => {
+                        self.open_text_tabs[idx] = tab;
+                        self.active_text_tab = Some(idx);
+                    }
+                    None => {
+                        self.open_text_tabs.push(tab);
+                        self.active_text_tab = Some(self.open_text_tabs.len() - 1);
+                    }
                 }
-                Err(e) => Err(e),
+                self.search_current = 0;
+                Content::Text(doc)
+            }
+            Loaded::Hex(doc) => {
+                self.hex_view = Default::default();
+                Content::Hex(doc)
+            }
+            image => {
+                let Some(texture) = self.image_texture(&path, image, ctx) else { return; };
+                self.track_image_tab(&path);
+                Content::Image(texture)
             }
         };
 
-        match loaded {
-            Ok(content) => {
-                self.content = Some(content);
-                self.current_path = Some(path.clone());
-                // Deduplicate and push to recents
-                self.recent_files.retain(|p| p != &path);
-                self.recent_files.push(path);
-                if self.recent_files.len() > MAX_RECENT_FILES {
-                    let overflow = self.recent_files.len() - MAX_RECENT_FILES;
-                    self.recent_files.drain(0..overflow);
-                }
-                // Snapshot session and persist
-                self.snapshot_session();
-                crate::settings::save_settings_to_disk(self);
+        self.content = Some(content);
+        self.current_path = Some(path.clone());
+        self.diff = None;
+        self.pending_diff = None;
+        self.image_compare = None;
+        self.changed_on_disk.remove(&path);
+        self.missing_on_disk.remove(&path);
+        // Deduplicate and push to recents
+        self.recent_files.retain(|p| p != &path);
+        self.recent_files.push(path);
+        if self.recent_files.len() > MAX_RECENT_FILES {
+            let overflow = self.recent_files.len() - MAX_RECENT_FILES;
+            self.recent_files.drain(0..overflow);
+        }
+        // Snapshot session and persist
+        self.snapshot_session();
+        crate::settings::save_settings_to_disk(self);
+    }
+
+    fn track_image_tab(&mut self, path: &PathBuf) {
+        if !self.open_image_tabs.contains(path) { self.open_image_tabs.push(path.clone()); }
+        self.active_image_tab = self.open_image_tabs.iter().position(|p| p == path);
+    }
+
+    /// The animation on screen, if the current image is animated.
+    pub(crate) fn shown_animation(&mut self) -> Option<&mut crate::animation::Playback> {
+        let shown = matches!(self.content, Some(Content::Image(_)))
+            && self.animation.as_ref().is_some_and(|a| self.current_path.as_ref() == Some(&a.path));
+        if shown { self.animation.as_mut() } else { None }
+    }
+
+    /// Ask where to save the image on screen with its rotation and mirroring
+    /// baked in, in the format it was opened from.
+    pub(crate) fn save_transformed_copy(&mut self) {
+        let Some(path) = self.current_path.clone() else { return; };
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("image");
+        let name = match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) => format!("{}-transformed.{}", stem, ext),
+            None => format!("{}-transformed", stem),
+        };
+        let mut dialog = rfd::FileDialog::new().set_file_name(name);
+        if let Some(dir) = path.parent() { dialog = dialog.set_directory(dir); }
+        let Some(target) = dialog.save_file() else { return; };
+        match crate::transform::save_transformed(&path, &target, &self.image_view) {
+            Ok(()) => {
+                self.error_message = None;
+                // The file on screen now has the transform baked in and reloads as such
+                if target == path { self.image_view.reset_orientation(); }
             }
             Err(e) => self.error_message = Some(e),
         }
     }
 
+    /// The HDR image on screen, for its exposure control.
+    pub(crate) fn shown_hdr(&mut self) -> Option<&mut crate::hdr::HdrView> {
+        let shown = matches!(self.content, Some(Content::Image(_)))
+            && self.hdr.as_ref().is_some_and(|h| self.current_path.as_ref() == Some(&h.path));
+        if shown { self.hdr.as_mut() } else { None }
+    }
+
+    /// The SVG on screen, re-rasterized as the zoom changes.
+    pub(crate) fn shown_svg(&mut self) -> Option<&mut crate::svg::SvgView> {
+        let shown = matches!(self.content, Some(Content::Image(_)))
+            && self.svg.as_ref().is_some_and(|s| self.current_path.as_ref() == Some(&s.path));
+        if shown { self.svg.as_mut() } else { None }
+    }
+
+    /// Pixels of the image (or animation frame) on screen.
+    pub(crate) fn shown_pixels(&mut self) -> Option<Arc<egui::ColorImage>> {
+        if let Some(animation) = self.shown_animation() { return Some(animation.image().clone()); }
+        if matches!(self.content, Some(Content::Image(_))) { self.image_pixels.clone() } else { None }
+    }
+
     pub(crate) fn start_open_file_dialog(&mut self) {
         if self.file_open_in_flight { return; }
         self.file_open_in_flight = true;
         let (tx, rx) = channel::<Option<PathBuf>>();
         self.file_open_rx = Some(rx);
         thread::spawn(move || {
-            let picked = FileDialog::new()
-                .add_filter("All Supported", &["txt","rs","py","toml","md","json","js","html","css","png","jpg","jpeg","gif","bmp","webp"])
-                .add_filter("Images", &["png","jpg","jpeg","gif","bmp","webp"])
-                .add_filter("Text/Source", &["txt","rs","py","toml","md","json","js","html","css"])
-                .pick_file();
+            let picked = crate::filetype::file_dialog().pick_file();
             let _ = tx.send(picked);
         });
     }
@@ -290,34 +583,453 @@ impl FileViewerApp {
         if let Some(tab) = self.open_text_tabs.get(tab_index).cloned() {
             self.active_text_tab = Some(tab_index);
             self.current_path = Some(tab.path.clone());
-            self.text_is_big = tab.text.len() >= BIG_TEXT_CHAR_THRESHOLD || tab.line_count >= 50_000;
+            self.clear_image_state();
+            self.diff = None;
+            self.pending_diff = None;
+            self.image_compare = None;
             self.text_line_count = tab.line_count;
             self.text_is_lossy = tab.is_lossy;
-            self.content = Some(Content::Text(tab.text));
+            self.content = Some(Content::Text(tab.doc()));
+            if let Some((line, column)) = self.pending_jumps.remove(&tab.path) {
+                self.scroll_target_line = Some(line);
+                self.scroll_target_column = column;
+            }
             // Snapshot session on switch
             self.snapshot_session();
             crate::settings::save_settings_to_disk(self);
         }
     }
 
-    pub(crate) fn recompute_global_search(&mut self) {
+    /// Show the diff between two files. Open tabs are compared as shown,
+    /// other files are read from disk in the background first.
+    pub(crate) fn open_diff(&mut self, left: PathBuf, right: PathBuf, ctx: &egui::Context) {
+        let mut texts = HashMap::new();
+        for path in [&left, &right] {
+            match self.diff_text(path) {
+                Ok(Some(text)) => { texts.insert(path.clone(), text); }
+                Ok(None) => self.request_load(path.clone(), LoadPurpose::Compare, ctx),
+                Err(e) => {
+                    self.pending_diff = None;
+                    self.error_message = Some(e);
+                    return;
+                }
+            }
+        }
+        self.pending_diff = Some(PendingDiff { left, right, texts });
+        self.finish_diff();
+    }
+
+    // A file read for the pending diff
+    fn apply_diff_text(&mut self, path: PathBuf, loaded: Loaded) {
+        let Some(pending) = self.pending_diff.as_mut() else { return; };
+        if pending.left != path && pending.right != path { return; }
+        match loaded {
+            Loaded::Text(tab) if tab.paged.is_none() => {
+                pending.texts.insert(path, tab.text);
+                self.finish_diff();
+            }
+            _ => {
+                let name = path.file_name().and_then(|s| s.to_str()).unwrap_or("file");
+                self.error_message = Some(format!("Cannot compare {}: not a text file", name));
+                self.pending_diff = None;
+            }
+        }
+    }
+
+    // Show the pending diff once both sides are in
+    fn finish_diff(&mut self) {
+        let Some(pending) = &self.pending_diff else { return; };
+        let (Some(left_text), Some(right_text)) = (pending.texts.get(&pending.left), pending.texts.get(&pending.right)) else { return; };
+        self.diff = Some(crate::diff::DiffView::compare(pending.left.clone(), left_text, pending.right.clone(), right_text));
+        self.pending_diff = None;
+        self.image_compare = None;
+        self.error_message = None;
+    }
+
+    /// Compare the same two files again, e.g. after one was reloaded.
+    pub(crate) fn refresh_diff(&mut self, ctx: &egui::Context) {
+        if let Some(diff) = &self.diff {
+            let (left, right) = (diff.left.clone(), diff.right.clone());
+            self.open_diff(left, right, ctx);
+        }
+    }
+
+    /// Compare two files: images side by side in the image compare view,
+    /// anything else as a text diff.
+    pub(crate) fn compare_files(&mut self, left: PathBuf, right: PathBuf, ctx: &egui::Context) {
+        if crate::filetype::classify(&left) == FileKind::Image {
+            self.open_image_compare(left, right, ctx);
+        } else {
+            self.open_diff(left, right, ctx);
+        }
+    }
+
+    /// Compare two images; see `crate::compare`.
+    pub(crate) fn open_image_compare(&mut self, left: PathBuf, right: PathBuf, ctx: &egui::Context) {
+        match crate::compare::ImageCompare::open(ctx, left, right) {
+            Ok(compare) => {
+                self.image_compare = Some(compare);
+                self.diff = None;
+                self.pending_diff = None;
+                self.error_message = None;
+            }
+            Err(e) => self.error_message = Some(e),
+        }
+    }
+
+    // Text of an open tab, or None when the file has to be read from disk
+    fn diff_text(&self, path: &std::path::Path) -> Result<Option<String>, String> {
+        let name = path.file_name().and_then(|s| s.to_str()).unwrap_or("file");
+        if let Some(tab) = self.open_text_tabs.iter().find(|t| t.path == path) {
+            if tab.paged.is_some() { return Err(format!("Cannot compare {}: too large to diff", name)); }
+            return Ok(Some(tab.text.clone()));
+        }
+        if needs_paging(path) { return Err(format!("Cannot compare {}: too large to diff", name)); }
+        if crate::filetype::classify(path) != FileKind::Text { return Err(format!("Cannot compare {}: not a text file", name)); }
+        Ok(None)
+    }
+
+    fn apply_text_reload(&mut self, tab_index: usize, tab: TextTab, ctx: &egui::Context) {
+        let path = tab.path.clone();
+        self.replace_tab_text(tab_index, tab);
+        self.changed_on_disk.remove(&path);
+        self.missing_on_disk.remove(&path);
+        if self.diff.as_ref().is_some_and(|d| d.left == path || d.right == path) {
+            self.refresh_diff(ctx);
+        }
+        // Keep following from the new end of file
+        if self.following.contains_key(&path)
+            && let Ok(state) = crate::tail::FollowState::start(&path) {
+            self.following.insert(path, state);
+        }
+    }
+
+    fn is_active_text_tab(&self, tab_index: usize) -> bool {
+        self.active_text_tab == Some(tab_index) && matches!(self.content, Some(Content::Text(_)))
+    }
+
+    fn replace_tab_text(&mut self, tab_index: usize, tab: TextTab) {
+        self.open_text_tabs[tab_index] = tab;
+        if self.is_active_text_tab(tab_index) {
+            let tab = &self.open_text_tabs[tab_index];
+            self.text_line_count = tab.line_count;
+            self.text_is_lossy = tab.is_lossy;
+            self.content = Some(Content::Text(tab.doc()));
+        }
+    }
+
+    // Append newly written text, updating the line count incrementally; Find
+    // matches are rescanned from the old last line in the background
+    fn append_tab_text(&mut self, tab_index: usize, chunk: &str) {
+        let is_active = self.is_active_text_tab(tab_index);
+        let tab = &mut self.open_text_tabs[tab_index];
+        let new_lines = crate::tail::appended_line_count(&tab.text, tab.line_count, chunk);
+        tab.text.push_str(chunk);
+        tab.matches.appended();
+        tab.line_count = new_lines;
+        tab.is_lossy |= chunk.contains('\u{FFFD}');
+        if is_active {
+            self.text_line_count = tab.line_count;
+            self.text_is_lossy = tab.is_lossy;
+            if let Some(Content::Text(shown)) = self.content.as_mut() {
+                shown.push_str(chunk);
+            }
+        }
+    }
+
+    /// Turn follow mode on or off for a text tab, starting at the current end of file.
+    pub(crate) fn toggle_follow(&mut self, tab_index: usize, ctx: &egui::Context) {
+        let Some(tab) = self.open_text_tabs.get(tab_index) else { return; };
+        if tab.paged.is_some() {
+            self.error_message = Some("Follow is not available for paged (very large) files".to_string());
+            return;
+        }
+        if tab.encoding != encoding_rs::UTF_8 {
+            self.error_message = Some(format!("Follow supports UTF-8 files only (this one is {})", tab.encoding.name()));
+            return;
+        }
+        let path = tab.path.clone();
+        if self.following.remove(&path).is_none() {
+            match crate::tail::FollowState::start(&path) {
+                Ok(state) => {
+                    self.following.insert(path.clone(), state);
+                    // Catch up first; the reload restarts following from its end
+                    self.request_load(path, LoadPurpose::Reload, ctx);
+                }
+                Err(e) => self.error_message = Some(format!("Cannot follow file: {}", e)),
+            }
+        }
+    }
+
+    pub(crate) fn is_following_current(&self) -> bool {
+        self.active_text_tab
+            .and_then(|i| self.open_text_tabs.get(i))
+            .is_some_and(|t| self.following.contains_key(&t.path))
+            && matches!(self.content, Some(Content::Text(_)))
+    }
+
+    fn poll_follow(&mut self, path: &PathBuf) {
+        let Some(tab_index) = self.open_text_tabs.iter().position(|t| &t.path == path) else {
+            self.following.remove(path);
+            return;
+        };
+        // A reload in flight replaces the text and restarts following
+        if self.pending_loads().iter().any(|p| &p.path == path && p.purpose == LoadPurpose::Reload) { return; }
+        let Some(state) = self.following.get_mut(path) else { return; };
+        match state.poll(path) {
+            Ok(Some(crate::tail::TailUpdate::Appended(chunk))) => self.append_tab_text(tab_index, &chunk),
+            Ok(Some(crate::tail::TailUpdate::Reset(text))) => {
+                let is_lossy = text.contains('\u{FFFD}');
+                let line_count = text.lines().count();
+                self.replace_tab_text(tab_index, TextTab { path: path.clone(), text, is_lossy, line_count, encoding: encoding_rs::UTF_8, paged: None, matches: Default::default() });
+            }
+            Ok(None) => {}
+            // Briefly missing during rotation; the watcher marks real deletions
+            Err(_) => {}
+        }
+    }
+
+    // Timer-driven poll for followed files in case the watcher misses writes
+    fn tick_follow(&mut self, ctx: &egui::Context) {
+        if self.following.is_empty() { return; }
+        if self.last_follow_poll.is_none_or(|t| t.elapsed() >= FOLLOW_POLL_INTERVAL) {
+            self.last_follow_poll = Some(Instant::now());
+            let paths: Vec<PathBuf> = self.following.keys().cloned().collect();
+            for p in paths { self.poll_follow(&p); }
+        }
+        ctx.request_repaint_after(FOLLOW_POLL_INTERVAL);
+    }
+
+    // A background reload finished; the tab or image may have closed meanwhile
+    fn apply_reload(&mut self, path: PathBuf, loaded: Loaded, ctx: &egui::Context) {
+        match loaded {
+            Loaded::Text(tab) => {
+                if let Some(idx) = self.open_text_tabs.iter().position(|t| t.path == path) {
+                    self.apply_text_reload(idx, tab, ctx);
+                }
+            }
+            Loaded::Hex(doc) => {
+                if self.current_path.as_ref() == Some(&path) && matches!(self.content, Some(Content::Hex(_))) {
+                    self.content = Some(Content::Hex(doc));
+                }
+                self.changed_on_disk.remove(&path);
+                self.missing_on_disk.remove(&path);
+            }
+            image => {
+                if self.current_path.as_ref() == Some(&path)
+                    && matches!(self.content, Some(Content::Image(_)))
+                    && let Some(texture) = self.image_texture(&path, image, ctx) {
+                    self.content = Some(Content::Image(texture));
+                }
+                self.changed_on_disk.remove(&path);
+                self.missing_on_disk.remove(&path);
+            }
+        }
+    }
+
+    // Texture for a decoded image, setting up what goes with its kind:
+    // animation frames, HDR exposure, SVG rescaling, pixels for the inspector
+    fn image_texture(&mut self, path: &std::path::Path, loaded: Loaded, ctx: &egui::Context) -> Option<TextureHandle> {
+        self.clear_image_state();
+        let image = match loaded {
+            Loaded::Image(image) => image,
+            Loaded::Animation(frames) => {
+                let playback = crate::animation::Playback::new(ctx, path.to_path_buf(), frames);
+                let texture = playback.texture().clone();
+                self.animation = Some(playback);
+                return Some(texture);
+            }
+            Loaded::Hdr(pixels) => {
+                let hdr = crate::hdr::HdrView::new(path.to_path_buf(), pixels);
+                let image = hdr.image();
+                self.hdr = Some(hdr);
+                image
+            }
+            Loaded::Svg(doc, image, scale) => {
+                self.svg = Some(crate::svg::SvgView::new(path.to_path_buf(), doc, scale));
+                image
+            }
+            Loaded::Text(_) | Loaded::Hex(_) => return None,
+        };
+        let image = Arc::new(image);
+        let texture = ctx.load_texture(path.to_string_lossy(), image.clone(), crate::io::IMAGE_TEXTURE_OPTIONS);
+        self.image_pixels = Some(image);
+        Some(texture)
+    }
+
+    pub(crate) fn clear_image_state(&mut self) {
+        self.animation = None;
+        self.image_pixels = None;
+        self.hdr = None;
+        self.svg = None;
+    }
+
+    /// Replace the pixels of the image on screen, keeping its texture id
+    /// (HDR exposure changes, SVG rasters at a new scale).
+    pub(crate) fn replace_image(&mut self, image: egui::ColorImage) {
+        let Some(Content::Image(texture)) = &mut self.content else { return; };
+        let image = Arc::new(image);
+        texture.set(image.clone(), crate::io::IMAGE_TEXTURE_OPTIONS);
+        self.image_pixels = Some(image);
+    }
+
+    /// Reload `path` in the background wherever it is open (text tab or the image on screen).
+    pub(crate) fn reload_path(&mut self, path: &PathBuf, ctx: &egui::Context) {
+        let is_open_text = self.open_text_tabs.iter().any(|t| &t.path == path);
+        let is_shown = self.current_path.as_ref() == Some(path) && matches!(self.content, Some(Content::Image(_)) | Some(Content::Hex(_)));
+        if is_open_text || is_shown {
+            self.request_load(path.clone(), LoadPurpose::Reload, ctx);
+        } else {
+            // Image tabs load on activation, so there is nothing stale to refresh
+            self.changed_on_disk.remove(path);
+        }
+    }
+
+    pub(crate) fn toggle_auto_reload(&mut self, path: PathBuf, ctx: &egui::Context) {
+        if !self.no_auto_reload.remove(&path) {
+            self.no_auto_reload.insert(path);
+        } else if self.changed_on_disk.contains(&path) {
+            // Re-enabling catches up on changes made while it was off
+            self.reload_path(&path, ctx);
+        }
+    }
+
+    pub(crate) fn start_file_watcher(&mut self, ctx: &egui::Context) {
+        if self.watcher.is_none() {
+            self.watcher = crate::watch::FileWatcher::new(ctx.clone());
+        }
+    }
+
+    // Keep the watch list in step with the open tabs and apply disk changes
+    fn process_file_changes(&mut self, ctx: &egui::Context) {
+        let Some(watcher) = self.watcher.as_mut() else { return; };
+        watcher.sync(self.open_text_tabs.iter().map(|t| &t.path).chain(self.open_image_tabs.iter()));
+        let changes = watcher.poll_changes();
+        for change in changes {
+            match change {
+                crate::watch::FileChange::Modified(path) => {
+                    self.missing_on_disk.remove(&path);
+                    if self.following.contains_key(&path) {
+                        self.poll_follow(&path);
+                    } else if self.no_auto_reload.contains(&path) {
+                        self.changed_on_disk.insert(path);
+                    } else {
+                        self.reload_path(&path, ctx);
+                    }
+                }
+                crate::watch::FileChange::Removed(path) => {
+                    self.missing_on_disk.insert(path);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn recompute_global_search(&mut self, ctx: &egui::Context) {
         self.global_results.clear();
         self.global_error = None;
-        match crate::search::global_search(&self.open_text_tabs, &self.global_query, self.global_case_sensitive, self.global_whole_word, self.global_regex) {
-            Ok(res) => self.global_results = res,
+        self.folder_search = None;
+        let query = match crate::search::Matcher::new(&self.global_query, self.global_options) {
+            Ok(query) => query,
+            Err(e) => { self.global_error = Some(e); return; }
+        };
+        if self.global_query.is_empty() { return; }
+        if !self.global_in_folder {
+            self.global_results.extend(crate::search::global_search(&self.open_text_tabs, &query));
+            return;
+        }
+        match crate::folder_search::FolderSearch::start(ctx, &self.global_folder, query) {
+            Ok(search) => self.folder_search = Some(search),
             Err(e) => { self.global_error = Some(e); }
         }
+        crate::settings::save_settings_to_disk(self);
+    }
+
+    /// The compiled Find query, rebuilt when the query or options change.
+    /// None when the query is empty or not a valid regex (see `find_error`).
+    pub(crate) fn find_matcher(&mut self) -> Option<crate::search::Matcher> {
+        let key = (self.search_query.clone(), self.find_options);
+        if self.find_compiled.as_ref().is_none_or(|(k, _)| *k != key) {
+            let compiled = crate::search::Matcher::new(&key.0, key.1);
+            self.find_compiled = Some((key, compiled));
+        }
+        self.find_compiled.as_ref()?.1.as_ref().ok().filter(|m| !m.is_empty()).cloned()
+    }
+
+    pub(crate) fn find_error(&self) -> Option<&str> {
+        self.find_compiled.as_ref()?.1.as_ref().err().map(String::as_str)
+    }
+
+    /// Whether the text on screen is shown as a table (see `crate::table`).
+    pub(crate) fn shows_table(&self) -> bool {
+        matches!(&self.content, Some(Content::Text(doc)) if doc.paged_file().is_none())
+            && self.table_mode == crate::table::TableMode::Table
+            && self.current_path.as_deref().is_some_and(crate::table::is_table)
+    }
+
+    /// Count Find matches in the text on screen again, from the first one.
+    pub(crate) fn recount_find(&mut self, ctx: &egui::Context) {
+        self.search_current = 0;
+        self.sync_find_count(ctx);
+    }
+
+    /// Take the Find count from the match index of the text on screen,
+    /// which is built in the background when missing. Until it is ready the
+    /// count is 0 and the current match is kept, so a match picked before
+    /// counting (Global Search) is still shown once counted.
+    pub(crate) fn sync_find_count(&mut self, ctx: &egui::Context) {
+        let matcher = self.find_matcher();
+        // The table view counts the cells it highlights instead
+        if self.shows_table()
+            && let (Some(Content::Text(doc)), Some(path), Some(matcher)) = (&self.content, &self.current_path, &matcher) {
+            self.table.sync(doc, path, ctx);
+            self.search_count = self.table.match_count(matcher);
+            self.search_counting = self.table.is_parsing();
+            if !self.search_counting {
+                self.search_current = self.search_current.min(self.search_count.saturating_sub(1));
+            }
+            return;
+        }
+        let index = match (&self.content, &matcher) {
+            (Some(Content::Text(doc)), Some(matcher)) => doc.match_index(matcher, ctx),
+            _ => None,
+        };
+        self.search_counting = index.is_none() && matcher.is_some()
+            && matches!(&self.content, Some(Content::Text(doc)) if doc.paged_file().is_none());
+        self.search_count = index.map_or(0, |index| index.len());
+        if !self.search_counting {
+            self.search_current = self.search_current.min(self.search_count.saturating_sub(1));
+        }
+    }
+
+    /// Show a Global Search match: switch to its tab, or open the file from
+    /// disk, and jump to the line with the query in Find.
+    pub(crate) fn open_global_result(&mut self, res: &GlobalSearchResult, ctx: &egui::Context) {
+        // Find takes over the query and its options, so it counts the same matches
+        self.search_query = self.global_query.clone();
+        self.find_options = self.global_options;
+        // Tabs may have been closed or moved since the search ran
+        let tab = res.tab_index
+            .filter(|&i| self.open_text_tabs.get(i).is_some_and(|t| t.path == res.path))
+            .or_else(|| self.open_text_tabs.iter().position(|t| t.path == res.path));
+        let Some(tab) = tab else {
+            // Counted and scrolled to once loaded
+            self.request_load(res.path.clone(), LoadPurpose::Open { activate: true, line: Some(res.line_index), column: None }, ctx);
+            return;
+        };
+        self.switch_to_text_tab(tab);
+        self.recount_find(ctx);
+        self.search_current = if res.tab_index.is_some() { res.match_index_in_tab } else { 0 };
+        self.sync_find_count(ctx);
+        self.scroll_target_line = Some(res.line_index);
     }
 
     pub(crate) fn snapshot_session(&mut self) {
         // Build session_paths from open text tabs plus current path (for images/non-text)
         let mut paths: Vec<PathBuf> = self.open_text_tabs.iter().map(|t| t.path.clone()).collect();
         if let Some(cur) = self.current_path.clone() {
-            let is_text = crate::io::is_supported_text(&cur);
-            if !is_text {
-                if !paths.contains(&cur) {
-                    paths.push(cur);
-                }
+            let is_text = matches!(self.content, Some(Content::Text(_)));
+            if !is_text && !paths.contains(&cur) {
+                paths.push(cur);
             }
         }
         // Filter out non-existing files
@@ -352,8 +1064,31 @@ impl Default for FileViewerApp {
             show_settings_window: false,
             show_keybindings: false,
             image_fit: false,
-            text_is_big: false,
             text_line_count: 0,
+            text_view: Default::default(),
+            loader: None,
+            hex_view: Default::default(),
+            markdown_mode: Default::default(),
+            markdown_preview: Default::default(),
+            json_mode: Default::default(),
+            json_tree: Default::default(),
+            table_mode: Default::default(),
+            table: Default::default(),
+            diff_layout: Default::default(),
+            diff: None,
+            pending_diff: None,
+            image_compare_mode: Default::default(),
+            image_compare: None,
+            animation: None,
+            show_image_info: false,
+            image_info: Default::default(),
+            image_pixels: None,
+            hdr: None,
+            svg: None,
+            pixel_inspector: false,
+            pixel_copy_format: Default::default(),
+            inspector: Default::default(),
+            image_view: Default::default(),
             text_is_lossy: false,
             open_text_tabs: Vec::new(),
             active_text_tab: None,
@@ -363,21 +1098,37 @@ impl Default for FileViewerApp {
             search_active: false,
             search_count: 0,
             search_current: 0,
+            search_counting: false,
+            find_options: Default::default(),
+            find_compiled: None,
             scroll_target_line: None,
+            scroll_target_column: None,
+            pending_jumps: HashMap::new(),
             show_recent_window: false,
             show_global_search_window: false,
             global_query: String::new(),
-            global_case_sensitive: false,
-            global_whole_word: false,
-            global_regex: false,
-            global_results: Vec::new(),
+            global_options: Default::default(),
+            global_results: Default::default(),
             global_error: None,
+            global_in_folder: false,
+            global_folder: Default::default(),
+            folder_search: None,
             restore_session: false,
             session_paths: Vec::new(),
             session_active: None,
             session_restored: false,
             file_open_rx: None,
             file_open_in_flight: false,
+            single_instance: false,
+            instance_server: None,
+            watcher: None,
+            no_auto_reload: HashSet::new(),
+            changed_on_disk: HashSet::new(),
+            missing_on_disk: HashSet::new(),
+            following: HashMap::new(),
+            last_follow_poll: None,
+            encoding_overrides: HashMap::new(),
+            forced_text: HashSet::new(),
             viewport_initialized: false,
         }
     }
@@ -416,35 +1167,26 @@ impl eframe::App for FileViewerApp {
             if !dropped.is_empty() {
                 // Limit to avoid accidental floods
                 let mut opened_first: bool = file_to_load.is_some();
-                let mut extra_text_tabs: usize = 0;
                 for f in dropped.into_iter().take(20) {
                     if let Some(path) = f.path {
-                        if crate::io::is_supported_image(&path) || crate::io::is_supported_text(&path) {
-                            if !opened_first {
-                                file_to_load = Some(path);
-                                opened_first = true;
-                            } else if crate::io::is_supported_text(&path) && let Ok((text, lossy, lines)) = crate::io::load_text(&path) {
+                        // The first file always opens; binary data lands in the hex view
+                        if !opened_first {
+                            file_to_load = Some(path);
+                            opened_first = true;
+                        } else {
+                            let kind = crate::filetype::classify(&path);
+                            if kind == FileKind::Text {
                                 // Add text as background tab without switching
-                                if !self.open_text_tabs.iter().any(|t| t.path == path) {
-                                    self.open_text_tabs.push(TextTab { path: path.clone(), text, is_lossy: lossy, line_count: lines });
-                                    extra_text_tabs += 1;
-                                }
-                            } else if crate::io::is_supported_image(&path) {
+                                self.request_load(path, LoadPurpose::Open { activate: false, line: None, column: None }, ctx);
+                            } else if kind == FileKind::Image {
                                 // Track image tab without switching
                                 if !self.open_image_tabs.iter().any(|p| p == &path) {
                                     self.open_image_tabs.push(path.clone());
                                 }
                             }
-                        } else {
-                            self.error_message = Some("Unsupported file type".to_string());
                         }
                     }
                 }
-                if extra_text_tabs > 0 && self.active_text_tab.is_none() {
-                    // If no active tab yet, activate the last added
-                    let last = self.open_text_tabs.len().saturating_sub(1);
-                    if self.open_text_tabs.get(last).is_some() { self.active_text_tab = Some(last); }
-                }
             }
         }
 
@@ -468,6 +1210,26 @@ impl eframe::App for FileViewerApp {
             }
         }
 
+        // Matches streamed in by a folder search
+        if let Some(search) = &mut self.folder_search {
+            let found = search.poll();
+            self.global_results.extend(found);
+        }
+
+        // Finished background loads, then reload tabs whose files changed on disk
+        self.process_loads(ctx);
+        self.process_file_changes(ctx);
+        self.tick_follow(ctx);
+        self.sync_find_count(ctx);
+
+        // Files forwarded by another gfv launch (single-instance mode)
+        let forwarded = self.instance_server.as_ref().map(|s| s.poll()).unwrap_or_default();
+        for batch in forwarded {
+            self.open_requests(batch, None, ctx);
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+        }
+
         // Modern About dialog
         if self.show_about {
             egui::Window::new("About gfv")
@@ -563,6 +1325,27 @@ impl eframe::App for FileViewerApp {
                         ui.monospace("Left/Right or Alt+Left/Right — Prev/Next file in folder");
                         ui.monospace("< or >    — Previous/Next file in folder");
                         ui.add_space(8.0);
+                        ui.label(RichText::new("Diff").strong());
+                        ui.monospace("F7 / Shift+F7 — Next/Previous change");
+                        ui.monospace("Alt+Down / Alt+Up — Next/Previous change");
+                        ui.monospace("Escape    — Close diff");
+                        ui.add_space(8.0);
+                        ui.label(RichText::new("Image compare").strong());
+                        ui.monospace("1 / 2 / 3 / 4 — Side by side / Swipe / Onion skin / Difference");
+                        ui.monospace("Wheel     — Zoom at the pointer");
+                        ui.monospace("Drag      — Pan both images");
+                        ui.monospace("Escape    — Close compare");
+                        ui.add_space(8.0);
+                        ui.label(RichText::new("Images").strong());
+                        ui.monospace("Drag      — Pan");
+                        ui.monospace("Wheel     — Zoom at the cursor");
+                        ui.monospace("R/Shift+R — Rotate right/left");
+                        ui.monospace("H or V    — Flip horizontally/vertically");
+                        ui.add_space(8.0);
+                        ui.label(RichText::new("Animated images").strong());
+                        ui.monospace("Space     — Play/pause");
+                        ui.monospace(", or .    — Previous/Next frame");
+                        ui.add_space(8.0);
                         ui.label(RichText::new("Zoom").strong());
                         ui.monospace("Ctrl/Alt+=  — Zoom in (text/image)");
                         ui.monospace("Ctrl/Alt+-  — Zoom out (text/image)");
@@ -582,8 +1365,9 @@ impl eframe::App for FileViewerApp {
         // Tab strip for open text tabs
         crate::ui::tab_strip(ctx, self);
 
-        // Search Bar (for text files and images with navigation)
-        if matches!(self.content, Some(Content::Text(_))) || matches!(self.content, Some(Content::Image(_))) {
+        // Search Bar (for text files and images with navigation); the diff
+        // view has its own toolbar
+        if self.content.is_some() && self.diff.is_none() && self.image_compare.is_none() {
             egui::TopBottomPanel::top("searchbar").show(ctx, |ui| {
                 crate::ui::search_bar(ui, self, &mut file_to_load);
             });
@@ -599,17 +1383,13 @@ impl eframe::App for FileViewerApp {
             let active_idx = self.session_active.unwrap_or(0);
             for (idx, p) in self.session_paths.clone().into_iter().enumerate() {
                 if p.exists() {
+                    let kind = crate::filetype::classify(&p);
                     if idx == active_idx {
                         file_to_load = Some(p.clone());
                         opened_any = true;
-                    } else if crate::io::is_supported_text(&p) {
-                        if let Ok((text, lossy, lines)) = crate::io::load_text(&p) {
-                            let exists = self.open_text_tabs.iter().any(|t| t.path == p);
-                            if !exists {
-                                self.open_text_tabs.push(TextTab { path: p.clone(), text, is_lossy: lossy, line_count: lines });
-                            }
-                        }
-                    } else if crate::io::is_supported_image(&p) {
+                    } else if kind == FileKind::Text {
+                        self.request_load(p.clone(), LoadPurpose::Open { activate: false, line: None, column: None }, ctx);
+                    } else if kind == FileKind::Image {
                         // Defer actual image load to when activated
                         // Track via current_path if none yet
                         if self.current_path.is_none() && !opened_any {
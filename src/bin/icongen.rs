@@ -8,7 +8,7 @@ fn build_app_icon_rgba(size: u32) -> (Vec<u8>, u32, u32) {
         let i = to_idx(x, y);
         if i + 3 < buf.len() { buf[i] = c[0]; buf[i + 1] = c[1]; buf[i + 2] = c[2]; buf[i + 3] = c[3]; }
     }
-    let mut fill_rect = |buf: &mut [u8], x0: u32, y0: u32, w: u32, h: u32, c: [u8; 4]| {
+    let fill_rect = |buf: &mut [u8], x0: u32, y0: u32, w: u32, h: u32, c: [u8; 4]| {
         for yy in y0..(y0 + h).min(size) {
             for xx in x0..(x0 + w).min(size) {
                 put_px(buf, &to_idx, xx, yy, c);
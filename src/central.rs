@@ -1,104 +1,1440 @@
 use eframe::egui::{self, RichText, text::LayoutJob};
+use std::time::{Duration, Instant};
+use crate::highlight_syntect::{SyntectSession, SyntectState};
 
-pub(crate) fn render_central_panel(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) {
-    if let Some(err) = &app.error_message {
-        ui.colored_label(egui::Color32::RED, format!("Error: {}", err));
+// Highlighter state is checkpointed every this many lines
+const CHECKPOINT_LINES: usize = 256;
+// Time per frame spent highlighting ahead to reach the visible lines
+const HIGHLIGHT_BUDGET: Duration = Duration::from_millis(8);
+// Very long lines (minified files) are shown plain and cut off here
+const MAX_RENDERED_LINE_BYTES: usize = 64 * 1024;
+const MAX_HIGHLIGHT_LINE_BYTES: usize = 4 * 1024;
+
+#[derive(Clone)]
+enum HighlightCheckpoint {
+    Syntect(SyntectState),
+    Basic { depth: i32, in_block_comment: bool },
+}
+
+/// Per-document render cache for the virtualized text view.
+#[derive(Default)]
+pub(crate) struct TextViewState {
+    key: Option<(u64, String, bool)>,
+    // checkpoints[k] is the highlighter state before line k * CHECKPOINT_LINES
+    checkpoints: Vec<HighlightCheckpoint>,
+    // (matcher, match index) the view last scrolled to, so it only jumps on change
+    last_search_jump: Option<(crate::search::Matcher, usize)>,
+    // Line and column of the caret placed by a `path:LINE:COL` jump
+    caret: Option<(usize, usize)>,
+}
+
+// Highlighter for the visible window, resumed from the nearest checkpoint
+enum LineHighlighter {
+    Syntect(Box<SyntectSession>),
+    Basic { depth: i32, in_block_comment: bool },
+}
+
+impl LineHighlighter {
+    fn start(use_syntect: bool, ext: &str) -> Self {
+        if use_syntect {
+            Self::Syntect(Box::new(SyntectSession::start(ext, true)))
+        } else {
+            Self::Basic { depth: 0, in_block_comment: false }
+        }
     }
 
-    if let Some(content) = &app.content {
-        match content {
-            crate::app::Content::Text(text) => {
-                let mut frame = egui::Frame::group(ui.style());
-                frame.fill = app.code_theme.background();
-                frame.show(ui, |ui| {
-                    ui.style_mut().wrap_mode = Some(if app.word_wrap { egui::TextWrapMode::Wrap } else { egui::TextWrapMode::Extend });
-                    egui::ScrollArea::both().auto_shrink([false, false]).show(ui, |ui| {
-                        let text_style = egui::TextStyle::Monospace;
-                        let mut font_id = text_style.resolve(ui.style());
-                        font_id.size = (font_id.size * app.text_zoom).clamp(8.0, 48.0);
-                        let text_color = app.code_theme.foreground();
-
-                        let do_line_numbers = app.show_line_numbers && !app.text_is_big;
-                        let do_highlight = !app.text_is_big && text.len() <= crate::app::HIGHLIGHT_CHAR_THRESHOLD;
-                        if do_line_numbers || do_highlight || !app.search_query.is_empty() {
-                            let mut bracket_depth: i32 = 0;
-                            let mut in_block_comment = false;
-                            let ext = app
-                                .current_path
-                                .as_ref()
-                                .and_then(|p| p.extension().and_then(|s| s.to_str()))
-                                .unwrap_or("")
-                                .to_lowercase();
-                            let target_line_from_search = if !app.search_query.is_empty() && app.search_count > 0 {
-                                crate::search::find_target_line(text, &app.search_query, app.search_current)
-                            } else { None };
-                            let mut counter: usize = 0;
-                            let mut target_rect: Option<egui::Rect> = None;
-                            let mut syntect_session = if app.use_syntect && do_highlight {
-                                Some(crate::highlight_syntect::SyntectSession::start(&ext, true))
-                            } else { None };
-                            for (i, line) in text.lines().enumerate() {
-                                let mut line_job = LayoutJob::default();
-                                if do_line_numbers {
-                                    line_job.append(&format!("{:>4} ", i + 1), 0.0, egui::TextFormat { font_id: font_id.clone(), color: app.code_theme.comment(), ..Default::default() });
-                                }
-                                if let Some(session) = syntect_session.as_mut() {
-                                    session.append_line(&mut line_job, line, font_id.clone());
-                                } else {
-                                    let mut hctx = crate::highlight::HighlightContext {
-                                        ext: &ext,
-                                        font_id: font_id.clone(),
-                                        base_color: text_color,
-                                        do_syntax: do_highlight,
-                                        depth: &mut bracket_depth,
-                                        current_idx: app.search_current,
-                                        counter: &mut counter,
-                                        query: &app.search_query,
-                                        in_block_comment: &mut in_block_comment,
-                                        theme: app.code_theme,
-                                    };
-                                    crate::highlight::append_highlighted(&mut line_job, line, &mut hctx);
-                                }
-                                let resp = ui.label(line_job);
-                                if target_line_from_search == Some(i) || app.scroll_target_line == Some(i) { target_rect = Some(resp.rect); }
-                            }
-                            if let Some(rect) = target_rect { ui.scroll_to_rect(rect, Some(egui::Align::Center)); }
-                            app.scroll_target_line = None;
-                        } else {
-                            ui.label(RichText::new(text).monospace().size(font_id.size));
+    fn resume(cp: &HighlightCheckpoint) -> Self {
+        match cp {
+            HighlightCheckpoint::Syntect(state) => Self::Syntect(Box::new(SyntectSession::resume(state.clone(), true))),
+            HighlightCheckpoint::Basic { depth, in_block_comment } => Self::Basic { depth: *depth, in_block_comment: *in_block_comment },
+        }
+    }
+
+    fn checkpoint(&self) -> HighlightCheckpoint {
+        match self {
+            Self::Syntect(session) => HighlightCheckpoint::Syntect(session.state()),
+            Self::Basic { depth, in_block_comment } => HighlightCheckpoint::Basic { depth: *depth, in_block_comment: *in_block_comment },
+        }
+    }
+
+    fn skip_line(&mut self, line: &str, ext: &str, theme: crate::themes::CodeTheme) {
+        if line.len() > MAX_HIGHLIGHT_LINE_BYTES { return; }
+        match self {
+            Self::Syntect(session) => session.skip_line(line),
+            Self::Basic { depth, in_block_comment } => {
+                let mut scratch = LayoutJob::default();
+                let mut hctx = crate::highlight::HighlightContext {
+                    ext,
+                    font_id: egui::FontId::monospace(1.0),
+                    base_color: egui::Color32::WHITE,
+                    do_syntax: true,
+                    depth,
+                    in_block_comment,
+                    theme,
+                };
+                crate::highlight::append_highlighted(&mut scratch, line, &mut hctx);
+            }
+        }
+    }
+}
+
+// Advance the checkpoint list towards `target_cp` within the frame budget.
+// Returns false if it ran out of time first.
+fn ensure_checkpoints(view: &mut TextViewState, doc: &crate::document::TextDoc, target_cp: usize, use_syntect: bool, ext: &str, theme: crate::themes::CodeTheme) -> bool {
+    if view.checkpoints.is_empty() {
+        view.checkpoints.push(LineHighlighter::start(use_syntect, ext).checkpoint());
+    }
+    if view.checkpoints.len() > target_cp { return true; }
+    let started = Instant::now();
+    let mut hl = LineHighlighter::resume(view.checkpoints.last().expect("seeded above"));
+    let mut line = (view.checkpoints.len() - 1) * CHECKPOINT_LINES;
+    // Never checkpoint past the last line: it may still grow (follow mode)
+    while view.checkpoints.len() <= target_cp && line + CHECKPOINT_LINES < doc.line_count() {
+        for i in line..line + CHECKPOINT_LINES {
+            hl.skip_line(&doc.line(i), ext, theme);
+        }
+        line += CHECKPOINT_LINES;
+        view.checkpoints.push(hl.checkpoint());
+        if started.elapsed() > HIGHLIGHT_BUDGET { break; }
+    }
+    view.checkpoints.len() > target_cp
+}
+
+// Virtualized text view: only the lines inside the viewport are laid out.
+// Line positions come from the document's line index (and wrapped-row
+// estimates for the monospace font), so cost does not grow with file size.
+fn render_text(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, marked_line: Option<usize>) {
+    let matcher = app.find_matcher();
+    let Some(crate::app::Content::Text(doc)) = &app.content else { return; };
+    let follow = app.is_following_current();
+    let mut frame = egui::Frame::group(ui.style());
+    frame.fill = app.code_theme.background();
+    frame.show(ui, |ui| {
+        let mut font_id = egui::TextStyle::Monospace.resolve(ui.style());
+        font_id.size = (font_id.size * app.text_zoom).clamp(8.0, 48.0);
+        let text_color = app.code_theme.foreground();
+        let row_h = ui.fonts(|f| f.row_height(&font_id));
+        let char_w = ui.fonts(|f| f.glyph_width(&font_id, '0')).max(1.0);
+        let spacing = ui.spacing().item_spacing.y;
+
+        // Paged documents have no wrap estimates, so they always lay out unwrapped
+        let wrap = app.word_wrap && doc.paged_file().is_none();
+        let n = doc.line_count();
+        let number_width = n.to_string().len().max(4);
+        let prefix_chars = if app.show_line_numbers { number_width + 1 } else { 0 };
+        let avail_w = (ui.available_width() - ui.spacing().scroll.bar_width).max(char_w * 16.0);
+        let cols = if wrap { (avail_w / char_w).floor() as usize } else { usize::MAX / 2 };
+        let rows = doc.row_offsets(cols, prefix_chars);
+        let line_top = |i: usize| match &rows {
+            Some(rows) => rows[i] as f32 * row_h + i as f32 * spacing,
+            None => i as f32 * (row_h + spacing),
+        };
+        let total_h = (line_top(n) - spacing).max(0.0);
+        let content_w = if wrap { avail_w } else { (doc.max_line_chars().min(MAX_RENDERED_LINE_BYTES) + prefix_chars) as f32 * char_w };
+
+        // Paged files are not highlighted, so their first line is not read for a hint
+        let first_line = if doc.paged_file().is_some() { std::borrow::Cow::Borrowed("") } else { doc.line(0) };
+        let ext = app
+            .current_path
+            .as_ref()
+            .map(|p| crate::filetype::syntax_hint(p, &first_line))
+            .unwrap_or_default();
+        let key = (doc.id(), ext.clone(), app.use_syntect);
+        if app.text_view.key.as_ref() != Some(&key) {
+            app.text_view = TextViewState { key: Some(key), ..Default::default() };
+        }
+
+        // Jump to the current Find match when it changes, or to an explicit line
+        let match_index = matcher.as_ref().and_then(|m| doc.match_index(m, ui.ctx()));
+        let mut target_line = app.scroll_target_line.take();
+        let caret_column = app.scroll_target_column.take();
+        if let (Some(line), Some(column)) = (target_line, caret_column) {
+            app.text_view.caret = Some((line, column));
+        }
+        if let (Some(index), Some(matcher)) = (&match_index, &matcher)
+            && let Some(line) = index.line_of(app.search_current) {
+            let jump_key = (matcher.clone(), app.search_current);
+            if app.text_view.last_search_jump.as_ref() != Some(&jump_key) {
+                app.text_view.last_search_jump = Some(jump_key);
+                if line < n { target_line = target_line.or(Some(line)); }
+            }
+        } else if match_index.is_none() {
+            app.text_view.last_search_jump = None;
+        }
+
+        ui.style_mut().wrap_mode = Some(if wrap { egui::TextWrapMode::Wrap } else { egui::TextWrapMode::Extend });
+        let mut area = egui::ScrollArea::both().auto_shrink([false, false]).stick_to_bottom(follow);
+        if let Some(line) = target_line.filter(|l| *l < n) {
+            let offset = line_top(line) - ui.available_height() / 2.0 + row_h / 2.0;
+            area = area.vertical_scroll_offset(offset.max(0.0));
+        }
+        if let Some(column) = caret_column.filter(|_| !wrap) {
+            let offset = (prefix_chars + column) as f32 * char_w - avail_w / 2.0;
+            area = area.horizontal_scroll_offset(offset.max(0.0));
+        }
+        area.show_viewport(ui, |ui, viewport| {
+            ui.set_height(total_h);
+            ui.set_min_width(content_w);
+            if n == 0 { return; }
+
+            // First line whose top is at or above the viewport top
+            let (mut lo, mut hi) = (0usize, n);
+            while lo + 1 < hi {
+                let mid = (lo + hi) / 2;
+                if line_top(mid) <= viewport.min.y { lo = mid; } else { hi = mid; }
+            }
+            let first = lo;
+            let mut last = first;
+            while last < n && line_top(last) < viewport.max.y { last += 1; }
+
+            // Resume highlighting from the nearest checkpoint before `first`
+            let cp = first / CHECKPOINT_LINES;
+            let mut highlighter = if doc.paged_file().is_some() {
+                None
+            } else if ensure_checkpoints(&mut app.text_view, doc, cp, app.use_syntect, &ext, app.code_theme) {
+                let mut hl = LineHighlighter::resume(&app.text_view.checkpoints[cp]);
+                for i in cp * CHECKPOINT_LINES..first { hl.skip_line(&doc.line(i), &ext, app.code_theme); }
+                Some(hl)
+            } else {
+                // Not caught up yet: draw plain text this frame and keep going
+                ui.ctx().request_repaint();
+                None
+            };
+
+            let top = ui.max_rect().top() + line_top(first);
+            let rect = egui::Rect::from_x_y_ranges(ui.max_rect().x_range(), top..=ui.max_rect().bottom());
+            ui.scope_builder(egui::UiBuilder::new().max_rect(rect), |ui| {
+                for i in first..last {
+                    let full = doc.line(i);
+                    let line = if full.len() > MAX_RENDERED_LINE_BYTES {
+                        let mut end = MAX_RENDERED_LINE_BYTES;
+                        while !full.is_char_boundary(end) { end -= 1; }
+                        &full[..end]
+                    } else { &full[..] };
+                    let mut job = LayoutJob::default();
+                    if wrap {
+                        job.wrap.max_width = avail_w;
+                        job.wrap.break_anywhere = true;
+                    }
+                    if app.show_line_numbers {
+                        job.append(&format!("{:>width$} ", i + 1, width = number_width), 0.0, egui::TextFormat { font_id: font_id.clone(), color: app.code_theme.comment(), ..Default::default() });
+                    }
+                    let text_start = job.text.len();
+                    let highlight_line = line.len() <= MAX_HIGHLIGHT_LINE_BYTES;
+                    match highlighter.as_mut() {
+                        Some(LineHighlighter::Syntect(session)) if highlight_line => {
+                            session.append_line(&mut job, line, font_id.clone());
                         }
+                        Some(LineHighlighter::Basic { depth, in_block_comment }) if highlight_line => {
+                            let mut hctx = crate::highlight::HighlightContext {
+                                ext: &ext,
+                                font_id: font_id.clone(),
+                                base_color: text_color,
+                                do_syntax: true,
+                                depth,
+                                in_block_comment,
+                                theme: app.code_theme,
+                            };
+                            crate::highlight::append_highlighted(&mut job, line, &mut hctx);
+                        }
+                        _ => {
+                            job.append(line, 0.0, egui::TextFormat { font_id: font_id.clone(), color: text_color, ..Default::default() });
+                        }
+                    }
+                    // Matches come from the index; without one yet (or for paged
+                    // files) the visible line is scanned and none is current
+                    if let Some(index) = &match_index {
+                        let (first_idx, on_line) = index.on_line(i);
+                        let shown = on_line.iter().map(|m| m.range.clone()).filter(|r| r.end <= line.len());
+                        crate::highlight::mark_search_matches(&mut job, text_start, shown, first_idx, app.search_current, &app.code_theme);
+                    } else if let Some(matcher) = &matcher {
+                        crate::highlight::mark_search_matches(&mut job, text_start, matcher.find_iter(line), 0, usize::MAX, &app.code_theme);
+                    }
+                    if line.len() < full.len() {
+                        job.append(&format!(" … ({} more bytes)", full.len() - line.len()), 0.0, egui::TextFormat { font_id: font_id.clone(), color: app.code_theme.comment(), ..Default::default() });
+                    }
+                    let galley = ui.fonts(|f| f.layout_job(job));
+                    // Background goes in before the text so it is painted underneath
+                    let background = ui.painter().add(egui::Shape::Noop);
+                    let resp = ui.label(galley.clone());
+                    if let Some((_, column)) = app.text_view.caret.filter(|(line, _)| *line == i) {
+                        let at = prefix_chars + column.min(line.chars().count());
+                        let caret = galley.pos_from_ccursor(egui::text::CCursor::new(at)).translate(resp.rect.min.to_vec2());
+                        ui.painter().vline(caret.min.x, caret.y_range(), egui::Stroke::new(2.0, text_color));
+                    }
+                    if marked_line == Some(i) {
+                        let rect = egui::Rect::from_x_y_ranges(ui.max_rect().x_range(), resp.rect.y_range());
+                        ui.painter().set(background, egui::Shape::rect_filled(rect, 0.0, egui::Color32::from_rgba_unmultiplied(239, 68, 68, 60))); // Red
+                    }
+                }
+            });
+        });
+    });
+}
+
+// Hex dump: offset, 16 bytes in hex (split in two groups of 8) and their
+// ASCII column. Rows are painted only for the visible viewport, and a value
+// inspector for the selection sits on the right.
+fn render_hex(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) {
+    use crate::hex::BYTES_PER_ROW;
+    let Some(crate::app::Content::Hex(doc)) = &app.content else { return; };
+    let view = &mut app.hex_view;
+
+    egui::SidePanel::right("hex_inspector").resizable(false).default_width(260.0).show_inside(ui, |ui| {
+        ui.add_space(4.0);
+        ui.label(RichText::new("Inspector").strong());
+        ui.separator();
+        let Some((start, end)) = view.selection() else {
+            ui.label(RichText::new("Click a byte to inspect it").weak());
+            return;
+        };
+        ui.label(format!("Offset: 0x{:X} ({})", start, start));
+        ui.label(format!("Selected: {} byte{}", end - start + 1, if end == start { "" } else { "s" }));
+        ui.add_space(8.0);
+        egui::Grid::new("hex_values").striped(true).num_columns(3).show(ui, |ui| {
+            ui.label(RichText::new("Type").strong());
+            ui.label(RichText::new("Little endian").strong());
+            ui.label(RichText::new("Big endian").strong());
+            ui.end_row();
+            for (name, le, be) in crate::hex::interpret(&doc.read(start..start + 8)) {
+                ui.monospace(name);
+                ui.monospace(le);
+                ui.monospace(be);
+                ui.end_row();
+            }
+        });
+        ui.add_space(8.0);
+        // Cap what goes to the clipboard for huge selections
+        let copy_end = end.min(start + 64 * 1024);
+        if ui.button("📋 Copy hex").clicked() {
+            let hex: Vec<String> = doc.read(start..copy_end + 1).iter().map(|b| format!("{:02X}", b)).collect();
+            ui.ctx().copy_text(hex.join(" "));
+        }
+    });
+
+    let mut frame = egui::Frame::group(ui.style());
+    frame.fill = app.code_theme.background();
+    frame.show(ui, |ui| {
+        let mut font_id = egui::TextStyle::Monospace.resolve(ui.style());
+        font_id.size = (font_id.size * app.text_zoom).clamp(8.0, 48.0);
+        let text_color = app.code_theme.foreground();
+        let offset_color = egui::Color32::from_rgb(128, 128, 128); // Gray
+        let select_color = ui.visuals().selection.bg_fill;
+        let row_h = ui.fonts(|f| f.row_height(&font_id));
+        let char_w = ui.fonts(|f| f.glyph_width(&font_id, '0')).max(1.0);
+
+        // Column layout in characters: offset, two spaces, hex with an extra
+        // space after the eighth byte, two spaces, ASCII
+        let digits = doc.offset_digits();
+        let hex_col = |j: usize| digits + 2 + j * 3 + usize::from(j >= 8);
+        let ascii_col = hex_col(BYTES_PER_ROW - 1) + 4;
+        let line_chars = ascii_col + BYTES_PER_ROW;
+        let rows = doc.row_count();
+
+        let mut scroll = egui::ScrollArea::both().auto_shrink([false, false]);
+        if let Some(target) = view.scroll_to.take() {
+            let y = (target / BYTES_PER_ROW) as f32 * row_h - ui.available_height() / 2.0;
+            scroll = scroll.vertical_scroll_offset(y.max(0.0));
+        }
+        scroll.show_viewport(ui, |ui, viewport| {
+            let size = egui::vec2(line_chars as f32 * char_w, rows as f32 * row_h);
+            let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click_and_drag());
+
+            // Pointer position to the byte under it, in either column
+            let byte_at = |pos: egui::Pos2| -> Option<usize> {
+                let row = ((pos.y - rect.min.y) / row_h).floor();
+                let col = ((pos.x - rect.min.x) / char_w).floor();
+                if row < 0.0 || col < 0.0 { return None; }
+                let (row, col) = (row as usize, col as usize);
+                let j = if col >= ascii_col && col < line_chars {
+                    col - ascii_col
+                } else if col >= hex_col(0) && col < ascii_col - 2 {
+                    let rel = col - hex_col(0);
+                    (if rel >= 24 { rel - 1 } else { rel } / 3).min(BYTES_PER_ROW - 1)
+                } else {
+                    return None;
+                };
+                let at = row * BYTES_PER_ROW + j;
+                (at < doc.len()).then_some(at)
+            };
+            if let Some(pos) = response.interact_pointer_pos()
+                && let Some(at) = byte_at(pos) {
+                if response.drag_started() || response.clicked() {
+                    if !(ui.input(|i| i.modifiers.shift) && view.has_selection) {
+                        view.anchor = at;
+                    }
+                    view.cursor = at;
+                    view.has_selection = true;
+                } else if response.dragged() {
+                    view.cursor = at;
+                }
+            }
+
+            let first = ((viewport.min.y / row_h).floor().max(0.0) as usize).min(rows);
+            let last = ((viewport.max.y / row_h).ceil() as usize + 1).min(rows);
+            let painter = ui.painter_at(rect);
+            let selection = view.selection();
+            // One read for the rows on screen
+            let window = doc.read(first * BYTES_PER_ROW..last * BYTES_PER_ROW);
+            for row in first..last {
+                let top = rect.min.y + row as f32 * row_h;
+                let start = row * BYTES_PER_ROW;
+                let rel = (start - first * BYTES_PER_ROW).min(window.len());
+                let chunk = &window[rel..(rel + BYTES_PER_ROW).min(window.len())];
+
+                if let Some((sel_start, sel_end)) = selection {
+                    for j in 0..chunk.len() {
+                        if (sel_start..=sel_end).contains(&(start + j)) {
+                            let hex_x = rect.min.x + hex_col(j) as f32 * char_w;
+                            let ascii_x = rect.min.x + (ascii_col + j) as f32 * char_w;
+                            painter.rect_filled(egui::Rect::from_min_size(egui::pos2(hex_x, top), egui::vec2(char_w * 2.0, row_h)), 0.0, select_color);
+                            painter.rect_filled(egui::Rect::from_min_size(egui::pos2(ascii_x, top), egui::vec2(char_w, row_h)), 0.0, select_color);
+                        }
+                    }
+                }
+
+                let mut hex = String::with_capacity(line_chars);
+                for j in 0..BYTES_PER_ROW {
+                    if j == 8 { hex.push(' '); }
+                    match chunk.get(j) {
+                        Some(b) => hex.push_str(&format!("{:02X}", b)),
+                        None => hex.push_str("  "),
+                    }
+                    if j + 1 < BYTES_PER_ROW { hex.push(' '); }
+                }
+                let ascii: String = chunk.iter().map(|b| crate::hex::ascii_char(*b)).collect();
+                let mut job = LayoutJob::default();
+                let format = |color| egui::TextFormat { font_id: font_id.clone(), color, ..Default::default() };
+                job.append(&format!("{:0width$X}  ", start, width = digits), 0.0, format(offset_color));
+                job.append(&hex, 0.0, format(text_color));
+                job.append("  ", 0.0, format(text_color));
+                job.append(&ascii, 0.0, format(text_color));
+                let galley = ui.fonts(|f| f.layout_job(job));
+                painter.galley(egui::pos2(rect.min.x, top), galley, text_color);
+            }
+        });
+    });
+}
+
+// Draws the Markdown block model; clicked links are reported back so the
+// app can open them once it is no longer borrowed for drawing
+struct MarkdownPainter<'a> {
+    base: &'a std::path::Path,
+    body_size: f32,
+    code_bg: egui::Color32,
+    images: &'a mut std::collections::HashMap<std::path::PathBuf, Option<egui::TextureHandle>>,
+    clicked: Option<String>,
+    tables: usize,
+}
+
+impl MarkdownPainter<'_> {
+    fn block(&mut self, ui: &mut egui::Ui, block: &crate::markdown::Block) {
+        use crate::markdown::Block;
+        match block {
+            Block::Heading(level, inlines) => {
+                const SCALE: [f32; 6] = [2.0, 1.6, 1.35, 1.15, 1.0, 0.9];
+                ui.add_space(self.body_size * 0.6);
+                self.inlines(ui, inlines, self.body_size * SCALE[(*level).clamp(1, 6) - 1], true);
+                if *level <= 2 { ui.separator(); }
+                ui.add_space(self.body_size * 0.3);
+            }
+            Block::Paragraph(inlines) => {
+                self.inlines(ui, inlines, self.body_size, false);
+                ui.add_space(self.body_size * 0.4);
+            }
+            Block::Code { lang, text } => {
+                let mut font_id = egui::TextStyle::Monospace.resolve(ui.style());
+                font_id.size = self.body_size * 0.9;
+                let mut job = LayoutJob::default();
+                job.wrap.max_width = ui.available_width() - 16.0;
+                job.wrap.break_anywhere = true;
+                let mut session = SyntectSession::start_for_token(lang, true);
+                for line in text.split_inclusive('\n') {
+                    session.append_line(&mut job, line, font_id.clone());
+                }
+                egui::Frame::new().fill(self.code_bg).inner_margin(8.0).corner_radius(4.0).show(ui, |ui| {
+                    ui.set_width(ui.available_width());
+                    ui.label(job);
+                });
+                ui.add_space(self.body_size * 0.4);
+            }
+            Block::List { start, items } => {
+                for (n, item) in items.iter().enumerate() {
+                    let marker = match start {
+                        Some(first) => format!("{}.", first + n as u64),
+                        None => "•".to_string(),
+                    };
+                    ui.horizontal(|ui| {
+                        ui.add_sized([self.body_size * 1.6, self.body_size], egui::Label::new(RichText::new(marker).size(self.body_size)));
+                        ui.vertical(|ui| {
+                            for block in item { self.block(ui, block); }
+                        });
                     });
+                }
+                ui.add_space(self.body_size * 0.4);
+            }
+            Block::Quote(blocks) => {
+                egui::Frame::new().fill(ui.visuals().faint_bg_color).inner_margin(egui::Margin { left: 12, right: 8, top: 6, bottom: 2 }).show(ui, |ui| {
+                    ui.set_width(ui.available_width());
+                    for block in blocks { self.block(ui, block); }
                 });
+                ui.add_space(self.body_size * 0.4);
             }
-            crate::app::Content::Image(texture) => {
-                let viewport = ui.available_size();
-                egui::ScrollArea::both().show(ui, |ui| {
-                    ui.centered_and_justified(|ui| {
-                        let size = texture.size();
-                        let mut effective_zoom = app.image_zoom;
-                        if app.image_fit {
-                            let sx = if size[0] > 0 { viewport.x / size[0] as f32 } else { 1.0 };
-                            let sy = if size[1] > 0 { viewport.y / size[1] as f32 } else { 1.0 };
-                            let fit = sx.min(sy);
-                            if fit.is_finite() && fit > 0.0 {
-                                effective_zoom = fit.clamp(0.1, 6.0);
+            Block::Table { header, rows } => {
+                self.tables += 1;
+                egui::Frame::group(ui.style()).show(ui, |ui| {
+                    egui::Grid::new(("markdown_table", self.tables)).striped(true).spacing([16.0, 4.0]).show(ui, |ui| {
+                        for cell in header { self.inlines(ui, cell, self.body_size, true); }
+                        ui.end_row();
+                        for row in rows {
+                            for cell in row { self.inlines(ui, cell, self.body_size, false); }
+                            ui.end_row();
+                        }
+                    });
+                });
+                ui.add_space(self.body_size * 0.4);
+            }
+            Block::Rule => { ui.separator(); }
+        }
+    }
+
+    fn inlines(&mut self, ui: &mut egui::Ui, inlines: &[crate::markdown::Inline], size: f32, strong: bool) {
+        use crate::markdown::Inline;
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing.x = 0.0;
+            for inline in inlines {
+                match inline {
+                    Inline::Text(text, style) => {
+                        let mut rich = RichText::new(text).size(size);
+                        if strong || style.strong { rich = rich.strong(); }
+                        if style.emphasis { rich = rich.italics(); }
+                        if style.strike { rich = rich.strikethrough(); }
+                        if style.code { rich = rich.code(); }
+                        match &style.link {
+                            Some(url) => {
+                                if ui.add(egui::Link::new(rich)).on_hover_text(url).clicked() { self.clicked = Some(url.clone()); }
                             }
+                            None => { ui.label(rich); }
                         }
-                        let desired = egui::vec2(size[0] as f32 * effective_zoom, size[1] as f32 * effective_zoom);
-                        let image = egui::Image::new(texture).fit_to_exact_size(desired);
-                        let resp = ui.add(image);
-                        if resp.hovered() {
-                            let scroll = ui.input(|i| i.raw_scroll_delta.y);
-                            if scroll != 0.0 {
-                                app.image_fit = false;
-                                let factor = if scroll > 0.0 { 1.10 } else { 1.0 / 1.10 };
-                                app.image_zoom = (app.image_zoom * factor).clamp(0.1, 6.0);
+                    }
+                    Inline::Image { url, alt, link } => {
+                        match crate::markdown::cached_image(self.images, ui.ctx(), self.base, url) {
+                            Some(texture) => {
+                                let image = egui::Image::new(&texture).max_width(ui.available_width()).sense(egui::Sense::click());
+                                let resp = ui.add(image).on_hover_text(alt);
+                                if resp.clicked() && let Some(link) = link { self.clicked = Some(link.clone()); }
                             }
+                            None => { ui.label(RichText::new(format!("🖼 {}", alt)).size(size).weak()).on_hover_text(url); }
                         }
-                    });
+                    }
+                    Inline::Break => ui.end_row(),
+                }
+            }
+        });
+    }
+}
+
+// Rendered Markdown preview of the current text document
+fn render_markdown(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) {
+    let Some(crate::app::Content::Text(doc)) = &app.content else { return; };
+    app.markdown_preview.sync(doc);
+    let base = app.current_path.as_ref().and_then(|p| p.parent()).map(|p| p.to_path_buf()).unwrap_or_default();
+    let mut painter = MarkdownPainter {
+        base: &base,
+        body_size: (egui::TextStyle::Body.resolve(ui.style()).size * app.text_zoom).clamp(8.0, 48.0),
+        code_bg: app.code_theme.background(),
+        images: &mut app.markdown_preview.images,
+        clicked: None,
+        tables: 0,
+    };
+    egui::ScrollArea::vertical().id_salt("markdown_preview").auto_shrink([false, false]).show(ui, |ui| {
+        ui.set_max_width(ui.available_width().min(960.0));
+        for block in &app.markdown_preview.blocks { painter.block(ui, block); }
+    });
+    let Some(url) = painter.clicked else { return; };
+    match crate::markdown::resolve_link(&base, &url) {
+        crate::markdown::LinkTarget::External(url) => ui.ctx().open_url(egui::OpenUrl::new_tab(url)),
+        crate::markdown::LinkTarget::File(path) if path.is_file() => app.load_file(path, ui.ctx()),
+        crate::markdown::LinkTarget::File(path) => app.error_message = Some(format!("Link target not found: {}", path.display())),
+        crate::markdown::LinkTarget::Anchor => {}
+    }
+}
+
+// JSON tree; invalid documents are shown as text with the error line marked
+fn render_json(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) {
+    let Some(crate::app::Content::Text(doc)) = &app.content else { return; };
+    let json_lines = app.current_path.as_deref().is_some_and(crate::json::is_json_lines);
+    let tree = &mut app.json_tree;
+    if tree.sync(doc, json_lines) && let Some(err) = &tree.error {
+        app.scroll_target_line = Some(err.line);
+    }
+    if let Some(err) = &tree.error {
+        let line = err.line;
+        ui.colored_label(egui::Color32::from_rgb(239, 68, 68), format!("⚠️ Invalid JSON, showing text: {}", err.message)); // Red
+        render_text(ui, app, Some(line));
+        return;
+    }
+
+    let mut copy: Option<String> = None;
+    ui.horizontal(|ui| {
+        ui.label(RichText::new("Filter:").strong());
+        ui.add(egui::TextEdit::singleline(&mut tree.filter).hint_text("key or value").desired_width(200.0));
+        if ui.button("Expand all").clicked() { tree.expand_all(); }
+        if ui.button("Collapse all").clicked() { tree.collapse_all(); }
+        if let Some(row) = tree.selected.and_then(|i| tree.rows().get(i)) {
+            ui.add_space(12.0);
+            ui.monospace(&row.path);
+            if ui.small_button("📋").on_hover_text("Copy JSON path").clicked() { copy = Some(row.path.clone()); }
+        }
+    });
+    // Rebuild after the toolbar's filter and expand/collapse changes
+    tree.refresh();
+
+    let mut frame = egui::Frame::group(ui.style());
+    frame.fill = app.code_theme.background();
+    frame.show(ui, |ui| {
+        let mut font_id = egui::TextStyle::Monospace.resolve(ui.style());
+        font_id.size = (font_id.size * app.text_zoom).clamp(8.0, 48.0);
+        let row_h = ui.fonts(|f| f.row_height(&font_id));
+        let char_w = ui.fonts(|f| f.glyph_width(&font_id, '0')).max(1.0);
+        let indent = char_w * 2.0;
+        let theme = app.code_theme;
+        let format = |color| egui::TextFormat { font_id: font_id.clone(), color, ..Default::default() };
+        let (mut toggle, mut select) = (None, None);
+        egui::ScrollArea::both().auto_shrink([false, false]).show_rows(ui, row_h, tree.rows().len(), |ui, range| {
+            for i in range {
+                let row = &tree.rows()[i];
+                let mut job = LayoutJob::default();
+                job.append(if row.len.is_none() { "  " } else if row.expanded { "▼ " } else { "▶ " }, 0.0, format(theme.comment()));
+                job.append(&row.key, 0.0, format(theme.keyword()));
+                job.append(": ", 0.0, format(theme.foreground()));
+                let value_color = match row.kind {
+                    "string" => theme.string(),
+                    "number" | "boolean" | "null" => theme.number(),
+                    _ => theme.foreground(),
+                };
+                job.append(&row.summary, 0.0, format(value_color));
+                let detail = match row.len {
+                    Some(n) if row.kind == "array" => format!("  {} item{}", n, if n == 1 { "" } else { "s" }),
+                    Some(n) => format!("  {} key{}", n, if n == 1 { "" } else { "s" }),
+                    None => format!("  {}", row.kind),
+                };
+                job.append(&detail, 0.0, format(theme.comment()));
+                let galley = ui.fonts(|f| f.layout_job(job));
+
+                let width = (row.depth as f32 * indent + galley.size().x).max(ui.available_width());
+                let (rect, resp) = ui.allocate_exact_size(egui::vec2(width, row_h), egui::Sense::click());
+                if tree.selected == Some(i) {
+                    ui.painter().rect_filled(rect, 0.0, ui.visuals().selection.bg_fill);
+                }
+                let text_pos = egui::pos2(rect.min.x + row.depth as f32 * indent, rect.min.y);
+                ui.painter().galley(text_pos, galley, theme.foreground());
+
+                // The arrow toggles, a click elsewhere selects, a double click toggles too
+                let on_arrow = resp.interact_pointer_pos().is_some_and(|p| p.x < text_pos.x + char_w * 2.0);
+                if row.len.is_some() && (resp.double_clicked() || (resp.clicked() && on_arrow)) {
+                    toggle = Some(i);
+                } else if resp.clicked() {
+                    select = Some(i);
+                }
+                resp.context_menu(|ui| {
+                    if ui.button("Copy JSON path").clicked() {
+                        copy = Some(row.path.clone());
+                        ui.close_menu();
+                    }
+                    if ui.button("Copy value").clicked() {
+                        copy = tree.value(row).and_then(|v| serde_json::to_string_pretty(v).ok());
+                        ui.close_menu();
+                    }
                 });
             }
+        });
+        if select.is_some() { tree.selected = select; }
+        if let Some(i) = toggle { tree.toggle(i); }
+    });
+    if let Some(text) = copy { ui.ctx().copy_text(text); }
+}
+
+// CSV/TSV table. The header and filter rows stay at the top and the row
+// numbers at the left; only cells inside the viewport are painted.
+fn render_table(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) {
+    let matcher = app.find_matcher();
+    let Some(crate::app::Content::Text(doc)) = &app.content else { return; };
+    let Some(path) = app.current_path.clone() else { return; };
+    let table = &mut app.table;
+    table.sync(doc, &path, ui.ctx());
+    if table.is_parsing() {
+        ui.vertical_centered(|ui| {
+            ui.add_space(ui.available_height() * 0.3);
+            ui.add(egui::Spinner::new().size(32.0));
+            ui.add_space(12.0);
+            ui.label(RichText::new("Parsing table…").strong());
+        });
+        return;
+    }
+
+    let mut reparse = false;
+    let mut copy: Option<String> = None;
+    ui.horizontal(|ui| {
+        let current = crate::table::DELIMITERS.iter().find(|(d, _)| *d == table.delimiter).map(|(_, name)| *name).unwrap_or("Other");
+        egui::ComboBox::from_id_salt("table_delimiter").selected_text(format!("Delimiter: {}", current)).show_ui(ui, |ui| {
+            for &(delimiter, name) in crate::table::DELIMITERS {
+                reparse |= ui.selectable_value(&mut table.delimiter, delimiter, name).changed();
+            }
+        });
+        reparse |= ui.checkbox(&mut table.has_header, "Header row").changed();
+        table.refresh_view();
+        ui.label(RichText::new(format!("{} of {} rows, {} columns", table.view().len(), table.records.len(), table.columns())).weak());
+        if table.filters.iter().any(|f| !f.is_empty()) && ui.button("Clear filters").clicked() {
+            table.filters.iter_mut().for_each(String::clear);
+        }
+        if let Some((r, c)) = table.selected {
+            ui.add_space(12.0);
+            let value = table.cell(r, c);
+            let shown: String = value.chars().take(80).collect();
+            ui.label(RichText::new(format!("{}: {}{}", table.header[c], shown, if shown.len() < value.len() { "…" } else { "" })).monospace());
+            if ui.small_button("📋").on_hover_text("Copy cell").clicked() { copy = Some(value.to_string()); }
+        }
+    });
+    if reparse { table.reparse(doc, &path, ui.ctx()); }
+    if let Some(err) = &table.error {
+        ui.colored_label(egui::Color32::from_rgb(239, 68, 68), err); // Red
+    }
+    table.refresh_view();
+
+    // Find: highlight matching cells and bring the current match into view
+    let current_cell = matcher.as_ref().filter(|_| app.search_count > 0).and_then(|m| table.match_cell(m, app.search_current));
+    let mut target_row = None;
+    if let Some((r, _)) = current_cell
+        && let Some(matcher) = &matcher {
+        let key = (matcher.clone(), app.search_current);
+        if table.last_search_jump.as_ref() != Some(&key) {
+            table.last_search_jump = Some(key);
+            target_row = table.view().iter().position(|&v| v == r);
+        }
+    }
+
+    let theme = app.code_theme;
+    let mut frame = egui::Frame::group(ui.style());
+    frame.fill = theme.background();
+    frame.show(ui, |ui| {
+        let mut font_id = egui::TextStyle::Monospace.resolve(ui.style());
+        font_id.size = (font_id.size * app.text_zoom).clamp(8.0, 48.0);
+        let row_h = ui.fonts(|f| f.row_height(&font_id)) + 4.0;
+        let char_w = ui.fonts(|f| f.glyph_width(&font_id, '0')).max(1.0);
+        let pad = char_w * 0.5;
+        let grid = egui::Stroke::new(1.0, ui.visuals().widgets.noninteractive.bg_stroke.color);
+        let header_bg = ui.visuals().extreme_bg_color;
+
+        let columns = table.columns();
+        let rows = table.view().len();
+        let gutter_w = (table.records.len().max(1).to_string().len() + 2) as f32 * char_w;
+        let mut col_x = vec![0.0f32];
+        for w in &table.widths { col_x.push(col_x.last().copied().unwrap_or(0.0) + w * char_w); }
+        let size = egui::vec2(gutter_w + col_x[columns], (rows + 2) as f32 * row_h);
+
+        let mut area = egui::ScrollArea::both().auto_shrink([false, false]);
+        if let Some(row) = target_row {
+            area = area.vertical_scroll_offset(((row + 2) as f32 * row_h - ui.available_height() / 2.0).max(0.0));
         }
+        area.show_viewport(ui, |ui, viewport| {
+            let (rect, resp) = ui.allocate_exact_size(size, egui::Sense::click());
+            let origin = rect.min;
+            let painter = ui.painter_at(rect);
+            // Data row k sits at (k + 2) rows, below the header and filter rows
+            let first_row = ((viewport.min.y / row_h).floor().max(0.0) as usize).min(rows);
+            let last_row = ((viewport.max.y / row_h).ceil() as usize).min(rows);
+            let first_col = col_x[1..].partition_point(|&right| right <= viewport.min.x);
+            let last_col = col_x[..columns].partition_point(|&left| left < viewport.max.x - gutter_w);
+            let cell_rect = |k: usize, c: usize| egui::Rect::from_min_size(
+                egui::pos2(origin.x + gutter_w + col_x[c], origin.y + (k + 2) as f32 * row_h),
+                egui::vec2(col_x[c + 1] - col_x[c], row_h),
+            );
+            let paint_text = |rect: egui::Rect, text: &str, color: egui::Color32| {
+                // Cells show their first 500 characters on one line
+                let one_line: String = text.chars().take(500).map(|ch| if ch == '\n' { '⏎' } else { ch }).collect();
+                let clip = rect.shrink2(egui::vec2(pad, 0.0)).intersect(painter.clip_rect());
+                painter.with_clip_rect(clip).text(rect.left_center() + egui::vec2(pad, 0.0), egui::Align2::LEFT_CENTER, one_line, font_id.clone(), color);
+            };
+
+            for k in first_row..last_row {
+                let record = table.view()[k];
+                for c in first_col..last_col {
+                    let cell = cell_rect(k, c);
+                    let text = table.cell(record, c);
+                    let fill = if table.selected == Some((record, c)) {
+                        Some(ui.visuals().selection.bg_fill)
+                    } else if current_cell == Some((record, c)) {
+                        Some(theme.search_current())
+                    } else if matcher.as_ref().is_some_and(|m| m.is_match(text)) {
+                        Some(theme.search_highlight())
+                    } else {
+                        None
+                    };
+                    if let Some(fill) = fill { painter.rect_filled(cell, 0.0, fill); }
+                    paint_text(cell, text, theme.foreground());
+                    painter.vline(cell.right(), cell.y_range(), grid);
+                }
+                let y = origin.y + (k + 3) as f32 * row_h;
+                painter.hline(origin.x..=origin.x + size.x, y, grid);
+            }
+
+            // Row numbers, pinned to the left edge
+            let gutter_x = origin.x + viewport.min.x;
+            painter.rect_filled(egui::Rect::from_x_y_ranges(gutter_x..=gutter_x + gutter_w, rect.y_range()), 0.0, header_bg);
+            for k in first_row..last_row {
+                let y = origin.y + (k + 2) as f32 * row_h + row_h / 2.0;
+                painter.text(egui::pos2(gutter_x + gutter_w - pad, y), egui::Align2::RIGHT_CENTER, (table.view()[k] + 1).to_string(), font_id.clone(), theme.comment());
+            }
+
+            // Header and filter rows, pinned to the top
+            let header_y = origin.y + viewport.min.y;
+            painter.rect_filled(egui::Rect::from_x_y_ranges(rect.x_range(), header_y..=header_y + 2.0 * row_h), 0.0, header_bg);
+            for c in first_col..last_col {
+                let name_rect = egui::Rect::from_min_size(egui::pos2(origin.x + gutter_w + col_x[c], header_y), egui::vec2(col_x[c + 1] - col_x[c], row_h));
+                let arrow = match table.sort {
+                    Some((sc, true)) if sc == c => " ▲",
+                    Some((sc, false)) if sc == c => " ▼",
+                    _ => "",
+                };
+                paint_text(name_rect, &format!("{}{}", table.header[c], arrow), theme.keyword());
+                painter.vline(name_rect.right(), name_rect.top()..=name_rect.bottom() + row_h, grid);
+            }
+            painter.hline(rect.x_range(), header_y + 2.0 * row_h, grid);
+            painter.rect_filled(egui::Rect::from_min_size(egui::pos2(gutter_x, header_y), egui::vec2(gutter_w, 2.0 * row_h)), 0.0, header_bg);
+
+            // Clicks: header names sort, cells select
+            if resp.clicked() && let Some(pos) = resp.interact_pointer_pos() {
+                let x = pos.x - origin.x - gutter_w;
+                let column = col_x[1..].partition_point(|&right| right <= x);
+                if pos.y < header_y + row_h && pos.x > gutter_x + gutter_w && column < columns {
+                    table.cycle_sort(column);
+                } else if pos.y > header_y + 2.0 * row_h && pos.x > gutter_x + gutter_w && column < columns {
+                    let k = ((pos.y - origin.y) / row_h) as usize;
+                    if let Some(&record) = k.checked_sub(2).and_then(|k| table.view().get(k)) {
+                        table.selected = Some((record, column));
+                    }
+                }
+            }
+
+            // Filter boxes and resize handles go on top of the painted header
+            for c in first_col..last_col {
+                let left = origin.x + gutter_w + col_x[c];
+                let filter_rect = egui::Rect::from_min_size(egui::pos2(left, header_y + row_h), egui::vec2(col_x[c + 1] - col_x[c], row_h)).shrink(2.0);
+                ui.put(filter_rect, egui::TextEdit::singleline(&mut table.filters[c]).hint_text("filter").font(font_id.clone()).margin(egui::Margin::symmetric(2, 0)));
+                let handle = egui::Rect::from_center_size(egui::pos2(origin.x + gutter_w + col_x[c + 1], header_y + row_h / 2.0), egui::vec2(6.0, row_h));
+                let handle = ui.interact(handle, ui.id().with(("table_resize", c)), egui::Sense::drag());
+                if handle.hovered() || handle.dragged() { ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeHorizontal); }
+                if handle.dragged() { table.resize(c, handle.drag_delta().x / char_w); }
+            }
+        });
+    });
+    if let Some(text) = copy { ui.ctx().copy_text(text); }
+}
+
+// Combo box choosing one side of a diff or image compare: an open tab, or
+// any file through the file dialog. Returns the newly chosen file.
+fn diff_side_picker(ui: &mut egui::Ui, id: &str, current: &std::path::Path, tabs: &[std::path::PathBuf]) -> Option<std::path::PathBuf> {
+    let name = |p: &std::path::Path| p.file_name().and_then(|s| s.to_str()).unwrap_or("(untitled)").to_string();
+    let mut picked = None;
+    egui::ComboBox::from_id_salt(id).selected_text(RichText::new(name(current)).monospace()).width(200.0).show_ui(ui, |ui| {
+        for tab in tabs {
+            if ui.selectable_label(tab == current, RichText::new(name(tab)).monospace()).on_hover_text(tab.to_string_lossy()).clicked() {
+                picked = Some(tab.clone());
+            }
+        }
+        ui.separator();
+        if ui.button("📂 File on disk…").clicked()
+            && let Some(path) = crate::filetype::file_dialog().pick_file() {
+            picked = Some(path);
+        }
+    });
+    picked.filter(|p| p != current)
+}
+
+// Diff between two files. One scroll area drives both sides of the
+// side-by-side layout, and long lines scroll sideways together.
+fn render_diff(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) {
+    use crate::diff::{DiffLayout, DiffLine, Side};
+    let tabs: Vec<std::path::PathBuf> = app.open_text_tabs.iter().filter(|t| t.paged.is_none()).map(|t| t.path.clone()).collect();
+    let Some(diff) = app.diff.as_mut() else { return; };
+    let del_color = egui::Color32::from_rgb(239, 68, 68); // Red
+    let ins_color = egui::Color32::from_rgb(34, 197, 94); // Green
+
+    let mut compare: Option<(std::path::PathBuf, std::path::PathBuf)> = None;
+    let mut close = false;
+    let mut refresh = false;
+    let layout_before = app.diff_layout;
+    ui.horizontal(|ui| {
+        if let Some(left) = diff_side_picker(ui, "diff_left", &diff.left, &tabs) {
+            compare = Some((left, diff.right.clone()));
+        }
+        if ui.button("⇄").on_hover_text("Swap sides").clicked() {
+            compare = Some((diff.right.clone(), diff.left.clone()));
+        }
+        if let Some(right) = diff_side_picker(ui, "diff_right", &diff.right, &tabs) {
+            compare = Some((diff.left.clone(), right));
+        }
+        ui.add_space(12.0);
+        ui.selectable_value(&mut app.diff_layout, DiffLayout::SideBySide, RichText::new("◫ Side by side").strong());
+        ui.selectable_value(&mut app.diff_layout, DiffLayout::Unified, RichText::new("☰ Unified").strong());
+        ui.add_space(12.0);
+        let hunks = diff.hunk_count();
+        if hunks == 0 {
+            ui.label(RichText::new("No differences").weak());
+        } else {
+            if ui.button("⬆ Prev").on_hover_text("Previous change (Shift+F7)").clicked() {
+                diff.jump_hunk(false, app.diff_layout);
+            }
+            if ui.button("⬇ Next").on_hover_text("Next change (F7)").clicked() {
+                diff.jump_hunk(true, app.diff_layout);
+            }
+            let position = diff.current_hunk.map(|h| format!("{} of {}", h + 1, hunks)).unwrap_or_else(|| hunks.to_string());
+            ui.label(RichText::new(format!("Change {}", position)).weak());
+            ui.label(RichText::new(format!("+{}", diff.added)).color(ins_color).strong());
+            ui.label(RichText::new(format!("−{}", diff.removed)).color(del_color).strong());
+        }
+        ui.add_space(12.0);
+        if ui.button("⟳ Refresh").on_hover_text("Compare again").clicked() { refresh = true; }
+        if ui.button("✕ Close diff").on_hover_text("Escape").clicked() { close = true; }
+    });
+    if app.diff_layout != layout_before {
+        diff.relayout(app.diff_layout);
+        crate::settings::save_settings_to_disk(app);
+    }
+    let Some(diff) = app.diff.as_mut() else { return; };
+
+    let theme = app.code_theme;
+    let layout = app.diff_layout;
+    let mut frame = egui::Frame::group(ui.style());
+    frame.fill = theme.background();
+    frame.show(ui, |ui| {
+        let mut font_id = egui::TextStyle::Monospace.resolve(ui.style());
+        font_id.size = (font_id.size * app.text_zoom).clamp(8.0, 48.0);
+        let row_h = ui.fonts(|f| f.row_height(&font_id));
+        let char_w = ui.fonts(|f| f.glyph_width(&font_id, '0')).max(1.0);
+        let line_bg = |color: egui::Color32| egui::Color32::from_rgba_unmultiplied(color.r(), color.g(), color.b(), 40);
+        let word_bg = |color: egui::Color32| egui::Color32::from_rgba_unmultiplied(color.r(), color.g(), color.b(), 110);
+        let pad_bg = ui.visuals().faint_bg_color;
+        let number_color = theme.comment();
+        let text_color = theme.foreground();
+        let grid = egui::Stroke::new(1.0, ui.visuals().widgets.noninteractive.bg_stroke.color);
+        let digits = diff.rows.len().max(1).to_string().len();
+        let number_w = (digits + 1) as f32 * char_w;
+        let rows = diff.row_count(layout);
+        let current_hunk = diff.current_hunk_rows(layout);
+
+        let mut scroll = egui::ScrollArea::vertical().auto_shrink([false, false]);
+        if let Some(row) = diff.scroll_to.take() {
+            let y = row as f32 * row_h - ui.available_height() / 3.0;
+            scroll = scroll.vertical_scroll_offset(y.max(0.0));
+        }
+        scroll.show_viewport(ui, |ui, viewport| {
+            let width = ui.available_width();
+            let (rect, response) = ui.allocate_exact_size(egui::vec2(width, rows as f32 * row_h), egui::Sense::hover());
+            let painter = ui.painter_at(rect);
+
+            // Panes: numbers (two columns in unified), a +/- marker, then text
+            let (panes, numbers) = match layout {
+                DiffLayout::SideBySide => (2, 1),
+                DiffLayout::Unified => (1, 2),
+            };
+            let pane_w = width / panes as f32;
+            let text_x = numbers as f32 * number_w + 2.0 * char_w;
+            let text_w = (pane_w - text_x).max(char_w);
+            let max_offset = (diff.max_chars as f32 * char_w - text_w + char_w).max(0.0);
+            if response.hovered() {
+                diff.h_offset -= ui.input(|i| i.smooth_scroll_delta.x);
+            }
+            diff.h_offset = diff.h_offset.clamp(0.0, max_offset);
+            let h_offset = diff.h_offset;
+
+            let paint_number = |x: f32, top: f32, line: Option<&DiffLine>| {
+                if let Some(line) = line {
+                    let pos = egui::pos2(x + number_w - char_w * 0.5, top);
+                    painter.text(pos, egui::Align2::RIGHT_TOP, (line.number + 1).to_string(), font_id.clone(), number_color);
+                }
+            };
+            // Line text with changed words marked, shifted by the shared offset
+            let paint_text = |x: f32, top: f32, line: &DiffLine, changed: egui::Color32| {
+                let text = &line.text[..line.text.len().min(MAX_RENDERED_LINE_BYTES)];
+                let text = &text[..text.floor_char_boundary(text.len())];
+                let mut job = LayoutJob::default();
+                let plain = egui::TextFormat { font_id: font_id.clone(), color: text_color, ..Default::default() };
+                let marked = egui::TextFormat { background: changed, ..plain.clone() };
+                let mut at = 0;
+                for range in &line.changed {
+                    let (start, end) = (range.start.min(text.len()), range.end.min(text.len()));
+                    if start > at { job.append(&text[at..start], 0.0, plain.clone()); }
+                    if end > start { job.append(&text[start..end], 0.0, marked.clone()); }
+                    at = at.max(end);
+                }
+                if at < text.len() { job.append(&text[at..], 0.0, plain); }
+                let galley = ui.fonts(|f| f.layout_job(job));
+                let clip = egui::Rect::from_x_y_ranges(x..=x + text_w, top..=top + row_h).intersect(painter.clip_rect());
+                painter.with_clip_rect(clip).galley(egui::pos2(x - h_offset, top), galley, text_color);
+            };
+
+            let first = ((viewport.min.y / row_h).floor().max(0.0) as usize).min(rows);
+            let last = ((viewport.max.y / row_h).ceil() as usize + 1).min(rows);
+            for r in first..last {
+                let top = rect.min.y + r as f32 * row_h;
+                let row_rect = |x: f32, w: f32| egui::Rect::from_min_size(egui::pos2(x, top), egui::vec2(w, row_h));
+                match layout {
+                    DiffLayout::SideBySide => {
+                        let row = &diff.rows[r];
+                        for (k, (line, color, marker)) in [(&row.left, del_color, "−"), (&row.right, ins_color, "+")].into_iter().enumerate() {
+                            let x = rect.min.x + k as f32 * pane_w;
+                            match line {
+                                Some(line) => {
+                                    if row.change {
+                                        painter.rect_filled(row_rect(x, pane_w), 0.0, line_bg(color));
+                                        painter.text(egui::pos2(x + number_w, top), egui::Align2::LEFT_TOP, marker, font_id.clone(), color);
+                                    }
+                                    paint_number(x, top, Some(line));
+                                    paint_text(x + text_x, top, line, word_bg(color));
+                                }
+                                None => { painter.rect_filled(row_rect(x, pane_w), 0.0, pad_bg); }
+                            }
+                        }
+                    }
+                    DiffLayout::Unified => {
+                        let (index, side) = diff.unified[r];
+                        let row = &diff.rows[index];
+                        let x = rect.min.x;
+                        let (line, color, marker) = match side {
+                            Side::Both => (row.left.as_ref(), None, " "),
+                            Side::Left => (row.left.as_ref(), Some(del_color), "−"),
+                            Side::Right => (row.right.as_ref(), Some(ins_color), "+"),
+                        };
+                        let Some(line) = line else { continue; };
+                        if let Some(color) = color {
+                            painter.rect_filled(row_rect(x, pane_w), 0.0, line_bg(color));
+                            painter.text(egui::pos2(x + 2.0 * number_w, top), egui::Align2::LEFT_TOP, marker, font_id.clone(), color);
+                        }
+                        paint_number(x, top, if side == Side::Right { None } else { row.left.as_ref() });
+                        paint_number(x + number_w, top, if side == Side::Left { None } else { row.right.as_ref() });
+                        paint_text(x + text_x, top, line, word_bg(color.unwrap_or(text_color)));
+                    }
+                }
+                // Bar beside the change the hunk navigation is on
+                if current_hunk.contains(&r) {
+                    painter.rect_filled(row_rect(rect.min.x, 3.0), 0.0, ui.visuals().selection.bg_fill);
+                }
+            }
+            if layout == DiffLayout::SideBySide {
+                let mid = rect.min.x + pane_w;
+                painter.vline(mid, rect.min.y + viewport.min.y..=rect.min.y + viewport.max.y, grid);
+            }
+        });
+    });
+
+    if close { app.diff = None; }
+    if refresh { app.refresh_diff(ui.ctx()); }
+    if let Some((left, right)) = compare { app.open_diff(left, right, ui.ctx()); }
+}
+
+// Two images in one of four modes. All modes share the zoom and pan: the
+// wheel zooms at the pointer, dragging pans, double-click fits.
+fn render_image_compare(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) {
+    use crate::compare::CompareMode;
+    let tabs = app.open_image_tabs.clone();
+    let ctx = ui.ctx().clone();
+    let Some(cmp) = app.image_compare.as_mut() else { return; };
+    let mode_before = app.image_compare_mode;
+    let mut compare: Option<(std::path::PathBuf, std::path::PathBuf)> = None;
+    let mut close = false;
+    ui.horizontal(|ui| {
+        if let Some(left) = diff_side_picker(ui, "compare_left", &cmp.left, &tabs) {
+            compare = Some((left, cmp.right.clone()));
+        }
+        if ui.button("⇄").on_hover_text("Swap sides").clicked() {
+            compare = Some((cmp.right.clone(), cmp.left.clone()));
+        }
+        if let Some(right) = diff_side_picker(ui, "compare_right", &cmp.right, &tabs) {
+            compare = Some((cmp.left.clone(), right));
+        }
+        ui.add_space(12.0);
+        ui.selectable_value(&mut app.image_compare_mode, CompareMode::SideBySide, RichText::new("◫ Side by side").strong());
+        ui.selectable_value(&mut app.image_compare_mode, CompareMode::Swipe, RichText::new("↔ Swipe").strong());
+        ui.selectable_value(&mut app.image_compare_mode, CompareMode::OnionSkin, RichText::new("◐ Onion skin").strong());
+        ui.selectable_value(&mut app.image_compare_mode, CompareMode::Difference, RichText::new("≠ Difference").strong());
+        ui.add_space(12.0);
+        if ui.button("📐 Fit").clicked() { cmp.fit = true; }
+        if ui.button("100%").clicked() {
+            cmp.zoom = 1.0;
+            cmp.pan = egui::Vec2::ZERO;
+        }
+        ui.label(RichText::new(format!("{:.0}%", cmp.zoom * 100.0)).weak());
+        ui.add_space(12.0);
+        if ui.button("✕ Close compare").on_hover_text("Escape").clicked() { close = true; }
+    });
+    let mode = app.image_compare_mode;
+    ui.horizontal(|ui| {
+        match mode {
+            CompareMode::SideBySide => {}
+            CompareMode::Swipe => { ui.add(egui::Slider::new(&mut cmp.swipe, 0.0..=1.0).text("Divider").show_value(false)); }
+            CompareMode::OnionSkin => { ui.add(egui::Slider::new(&mut cmp.opacity, 0.0..=1.0).text("Right image opacity")); }
+            CompareMode::Difference => {
+                ui.add(egui::Slider::new(&mut cmp.tolerance, 0..=255).text("Tolerance"))
+                    .on_hover_text("Largest per-channel difference still counted as equal");
+                let (_, count) = cmp.difference(&ctx);
+                let extent = cmp.extent();
+                let share = count as f64 * 100.0 / (extent.x as f64 * extent.y as f64).max(1.0);
+                let color = if count == 0 { egui::Color32::from_rgb(34, 197, 94) } else { egui::Color32::from_rgb(239, 68, 68) }; // Green / Red
+                ui.colored_label(color, format!("{} differing pixel{} ({:.2}%)", count, if count == 1 { "" } else { "s" }, share));
+            }
+        }
+        let (a, b) = (cmp.size(0), cmp.size(1));
+        if a != b {
+            ui.add_space(12.0);
+            ui.colored_label(egui::Color32::from_rgb(245, 158, 11), format!("Sizes differ: {}×{} vs {}×{}", a[0], a[1], b[0], b[1])); // Orange
+        }
+    });
+    if app.image_compare_mode != mode_before { crate::settings::save_settings_to_disk(app); }
+    let Some(cmp) = app.image_compare.as_mut() else { return; };
+
+    let (rect, response) = ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, app.code_theme.background());
+    let panes = if mode == CompareMode::SideBySide {
+        let half = (rect.width() - 4.0) / 2.0;
+        vec![
+            egui::Rect::from_min_size(rect.min, egui::vec2(half, rect.height())),
+            egui::Rect::from_min_size(egui::pos2(rect.max.x - half, rect.min.y), egui::vec2(half, rect.height())),
+        ]
+    } else {
+        vec![rect]
+    };
+    if cmp.fit { cmp.fit_to(panes[0].size()); }
+
+    // Pointer: wheel zooms around it, drag pans, double-click fits
+    let pointer = response.hover_pos();
+    let pane_under = pointer.and_then(|p| panes.iter().find(|pane| pane.contains(p)));
+    if let (Some(pos), Some(pane)) = (pointer, pane_under) {
+        let scroll = ui.input(|i| i.raw_scroll_delta.y);
+        if scroll != 0.0 {
+            cmp.zoom_at(if scroll > 0.0 { 1.10 } else { 1.0 / 1.10 }, pos - pane.center());
+        }
+    }
+    let mut swipe_dragged = false;
+    if mode == CompareMode::Swipe {
+        let x = rect.min.x + cmp.swipe * rect.width();
+        let handle = egui::Rect::from_x_y_ranges(x - 6.0..=x + 6.0, rect.y_range());
+        let handle = ui.interact(handle, ui.id().with("compare_swipe"), egui::Sense::drag());
+        if handle.hovered() || handle.dragged() { ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeHorizontal); }
+        if handle.dragged() && let Some(pos) = handle.interact_pointer_pos() {
+            cmp.swipe = ((pos.x - rect.min.x) / rect.width()).clamp(0.0, 1.0);
+            swipe_dragged = true;
+        }
+    }
+    if response.dragged() && !swipe_dragged { cmp.pan += response.drag_delta(); }
+    if response.double_clicked() { cmp.fit = true; }
+
+    let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+    let draw = |clip: egui::Rect, center: egui::Pos2, side: usize, tint: egui::Color32| {
+        painter.with_clip_rect(clip).image(cmp.textures[side].id(), cmp.image_rect(side, center), uv, tint);
+    };
+    match mode {
+        CompareMode::SideBySide => {
+            for (side, pane) in panes.iter().enumerate() {
+                draw(*pane, pane.center(), side, egui::Color32::WHITE);
+                let path = if side == 0 { &cmp.left } else { &cmp.right };
+                let name = path.file_name().and_then(|s| s.to_str()).unwrap_or("image");
+                let galley = painter.layout_no_wrap(name.to_string(), egui::TextStyle::Body.resolve(ui.style()), egui::Color32::WHITE);
+                let at = pane.left_top() + egui::vec2(8.0, 6.0);
+                painter.rect_filled(egui::Rect::from_min_size(at, galley.size()).expand(4.0), 3.0, egui::Color32::from_black_alpha(160));
+                painter.galley(at, galley, egui::Color32::WHITE);
+            }
+            painter.vline(rect.center().x, rect.y_range(), ui.visuals().widgets.noninteractive.bg_stroke);
+        }
+        CompareMode::Swipe => {
+            let x = rect.min.x + cmp.swipe * rect.width();
+            draw(egui::Rect::from_x_y_ranges(rect.min.x..=x, rect.y_range()), rect.center(), 0, egui::Color32::WHITE);
+            draw(egui::Rect::from_x_y_ranges(x..=rect.max.x, rect.y_range()), rect.center(), 1, egui::Color32::WHITE);
+            painter.vline(x, rect.y_range(), egui::Stroke::new(2.0, egui::Color32::WHITE));
+            painter.circle_filled(egui::pos2(x, rect.center().y), 8.0, egui::Color32::WHITE);
+        }
+        CompareMode::OnionSkin => {
+            draw(rect, rect.center(), 0, egui::Color32::WHITE);
+            draw(rect, rect.center(), 1, egui::Color32::from_white_alpha((cmp.opacity * 255.0).round() as u8));
+        }
+        CompareMode::Difference => {
+            let extent = cmp.extent() * cmp.zoom;
+            let target = egui::Rect::from_min_size(rect.center() + cmp.pan - extent / 2.0, extent);
+            let (texture, _) = cmp.difference(&ctx);
+            painter.image(texture.id(), target, uv, egui::Color32::WHITE);
+        }
+    }
+
+    if close { app.image_compare = None; }
+    if let Some((left, right)) = compare { app.open_image_compare(left, right, &ctx); }
+}
+
+// Side panel with the image's format, EXIF and other metadata
+fn render_image_info(ui: &mut egui::Ui, info: &crate::metadata::MetadataState) {
+    ui.add_space(4.0);
+    ui.label(RichText::new("ℹ Image info").strong());
+    ui.separator();
+    if let Some(error) = &info.error {
+        ui.colored_label(egui::Color32::from_rgb(239, 68, 68), error); // Red
+        return;
+    }
+    egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        let grid = |ui: &mut egui::Ui, id: &str, rows: &[(String, String)]| {
+            egui::Grid::new(id).num_columns(2).striped(true).spacing([12.0, 4.0]).show(ui, |ui| {
+                for (label, value) in rows {
+                    ui.label(RichText::new(label).weak());
+                    // Click a value to copy it
+                    if ui.add(egui::Label::new(value).wrap().sense(egui::Sense::click())).on_hover_text("Click to copy").clicked() {
+                        ui.ctx().copy_text(value.clone());
+                    }
+                    ui.end_row();
+                }
+            });
+        };
+        for section in &info.sections {
+            egui::CollapsingHeader::new(RichText::new(section.title).strong()).default_open(true).show(ui, |ui| {
+                grid(ui, section.title, &section.rows);
+            });
+        }
+        if !info.all_exif.is_empty() {
+            egui::CollapsingHeader::new(RichText::new(format!("All EXIF fields ({})", info.all_exif.len())).strong()).default_open(false).show(ui, |ui| {
+                grid(ui, "all_exif", &info.all_exif);
+            });
+        }
+    });
+}
+
+// Pixel grid at high zoom, and a loupe with the color under the pointer that
+// is copied on click
+fn inspect_pixels(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, resp: &egui::Response, view: egui::Rect, rect: egui::Rect) {
+    use crate::inspector::{pixel_at, LOUPE_CELL, LOUPE_RADIUS, PIXEL_GRID_MIN_ZOOM};
+    let previous = app.inspector.hovered.take();
+    let Some(image) = app.shown_pixels() else { return; };
+    // Pixels are addressed as displayed, after rotation and mirroring
+    let [dw, dh] = app.image_view.display_size(image.size);
+    // Points per image pixel; an SVG raster is finer than the view zoom
+    let zoom = rect.width() / dw.max(1) as f32;
+    let source = |x: usize, y: usize| (x < dw && y < dh).then(|| app.image_view.source_pixel([x, y], image.size));
+    let shown = |x: usize, y: usize| source(x, y).and_then(|[sx, sy]| pixel_at(&image, sx, sy));
+    let visible = rect.intersect(view);
+    if zoom >= PIXEL_GRID_MIN_ZOOM && visible.is_positive() {
+        let painter = ui.painter_at(visible);
+        let stroke = egui::Stroke::new(1.0, egui::Color32::from_rgba_unmultiplied(128, 128, 128, 110)); // Translucent grey
+        let span = |from: f32, to: f32, origin: f32, len: usize| {
+            let first = ((from - origin) / zoom).floor().max(0.0) as usize;
+            let last = (((to - origin) / zoom).ceil().max(0.0) as usize).min(len);
+            first..=last
+        };
+        for x in span(visible.min.x, visible.max.x, rect.min.x, dw) {
+            painter.vline(rect.min.x + x as f32 * zoom, visible.y_range(), stroke);
+        }
+        for y in span(visible.min.y, visible.max.y, rect.min.y, dh) {
+            painter.hline(visible.x_range(), rect.min.y + y as f32 * zoom, stroke);
+        }
+    }
+
+    let hovered = resp.hover_pos().and_then(|pos| {
+        let at = (pos - rect.min) / zoom;
+        if at.x < 0.0 || at.y < 0.0 { return None; }
+        let (x, y) = (at.x as usize, at.y as usize);
+        Some(([x, y], source(x, y)?, shown(x, y)?))
+    });
+    app.inspector.hovered = hovered.map(|(_, at, color)| (at, color));
+    // The status bar was drawn before the image; show the new pixel there too
+    if app.inspector.hovered != previous { ui.ctx().request_repaint(); }
+    let Some(([x, y], [sx, sy], color)) = hovered else { return; };
+    if resp.clicked() {
+        let text = app.pixel_copy_format.format(color);
+        app.inspector.copy(ui.ctx(), text);
+    }
+
+    let format = app.pixel_copy_format;
+    let copied = app.inspector.copied().map(str::to_string);
+    egui::show_tooltip_at_pointer(ui.ctx(), ui.layer_id(), egui::Id::new("pixel_loupe"), |ui| {
+        let cells = 2 * LOUPE_RADIUS + 1;
+        let (loupe, _) = ui.allocate_exact_size(egui::Vec2::splat(cells as f32 * LOUPE_CELL), egui::Sense::hover());
+        let painter = ui.painter_at(loupe);
+        painter.rect_filled(loupe, 0.0, ui.visuals().extreme_bg_color);
+        for row in 0..cells {
+            for col in 0..cells {
+                let (Some(px), Some(py)) = ((x + col).checked_sub(LOUPE_RADIUS), (y + row).checked_sub(LOUPE_RADIUS)) else { continue; };
+                let Some(c) = shown(px, py) else { continue; };
+                let cell = egui::Rect::from_min_size(loupe.min + egui::vec2(col as f32, row as f32) * LOUPE_CELL, egui::Vec2::splat(LOUPE_CELL));
+                painter.rect_filled(cell, 0.0, c);
+            }
+        }
+        let center = egui::Rect::from_min_size(loupe.min + egui::Vec2::splat(LOUPE_RADIUS as f32 * LOUPE_CELL), egui::Vec2::splat(LOUPE_CELL));
+        painter.rect_stroke(center, 0.0, egui::Stroke::new(1.0, egui::Color32::BLACK), egui::StrokeKind::Inside);
+        painter.rect_stroke(center, 0.0, egui::Stroke::new(1.0, egui::Color32::WHITE), egui::StrokeKind::Outside);
+
+        let [r, g, b, a] = color.to_srgba_unmultiplied();
+        let (h, s, l) = crate::inspector::hsl(color);
+        ui.label(RichText::new(format!("x {}  y {}", sx, sy)).monospace().strong());
+        ui.label(RichText::new(format!("RGBA {}, {}, {}, {}", r, g, b, a)).monospace());
+        ui.label(RichText::new(crate::inspector::ColorFormat::Hex.format(color)).monospace());
+        ui.label(RichText::new(format!("HSL {:.0}°, {:.0}%, {:.0}%", h, s, l)).monospace());
+        match copied {
+            Some(text) => ui.colored_label(egui::Color32::from_rgb(34, 197, 94), format!("✔ Copied {}", text)), // Green
+            None => ui.label(RichText::new(format!("Click to copy {}", format.label())).weak()),
+        };
+    });
+}
+
+// Still or animated image; animations get playback controls above the image
+fn render_image(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) {
+    let Some(crate::app::Content::Image(texture)) = &app.content else { return; };
+    let mut texture = texture.clone();
+    if app.show_image_info && let Some(path) = app.current_path.clone() {
+        app.image_info.sync(&path, texture.id());
+        egui::SidePanel::right("image_info").resizable(true).default_width(280.0).show_inside(ui, |ui| {
+            render_image_info(ui, &app.image_info);
+        });
+    }
+    if let Some(animation) = app.shown_animation() && !animation.pages().is_empty() {
+        // Pages of a TIFF or sizes of an icon, picked by hand
+        ui.horizontal(|ui| {
+            if ui.button("⏮").on_hover_text("Previous page (,)").clicked() { animation.step(false); }
+            if ui.button("⏭").on_hover_text("Next page (.)").clicked() { animation.step(true); }
+            ui.add_space(12.0);
+            let mut current = animation.current;
+            egui::ComboBox::from_id_salt("image_page").selected_text(&animation.pages()[current]).show_ui(ui, |ui| {
+                for (i, name) in animation.pages().iter().enumerate() {
+                    ui.selectable_value(&mut current, i, name);
+                }
+            });
+            animation.current = current;
+            ui.add_space(12.0);
+            ui.label(RichText::new(format!("Page {} / {}", current + 1, animation.frame_count())).monospace());
+            if animation.truncated {
+                ui.add_space(12.0);
+                ui.colored_label(egui::Color32::from_rgb(245, 158, 11), "Only the first pages fit in memory"); // Orange
+            }
+        });
+        texture = animation.texture().clone();
+    } else if let Some(animation) = app.shown_animation() {
+        animation.tick(ui.ctx());
+        ui.horizontal(|ui| {
+            let play = if animation.playing { "⏸ Pause" } else { "▶ Play" };
+            if ui.button(play).on_hover_text("Space").clicked() { animation.toggle_playing(); }
+            if ui.button("⏮").on_hover_text("Previous frame (,)").clicked() { animation.step(false); }
+            if ui.button("⏭").on_hover_text("Next frame (.)").clicked() { animation.step(true); }
+            ui.add_space(12.0);
+            egui::ComboBox::from_id_salt("animation_speed").selected_text(format!("{}×", animation.speed)).width(64.0).show_ui(ui, |ui| {
+                for &speed in crate::animation::SPEEDS {
+                    ui.selectable_value(&mut animation.speed, speed, format!("{}×", speed));
+                }
+            });
+            ui.add_space(12.0);
+            ui.label(RichText::new(format!("Frame {} / {}", animation.current + 1, animation.frame_count())).monospace());
+            if animation.truncated {
+                ui.add_space(12.0);
+                ui.colored_label(egui::Color32::from_rgb(245, 158, 11), "Only the first frames fit in memory"); // Orange
+            }
+        });
+        texture = animation.texture().clone();
+    }
+    if let Some(hdr) = app.shown_hdr() {
+        ui.horizontal(|ui| {
+            ui.label("Exposure");
+            ui.add(egui::Slider::new(&mut hdr.exposure, crate::hdr::EXPOSURE_RANGE).step_by(0.1).suffix(" EV"));
+            if ui.add_enabled(hdr.exposure != 0.0, egui::Button::new("↺ Reset")).clicked() { hdr.exposure = 0.0; }
+        });
+        if let Some(image) = hdr.update() { app.replace_image(image); }
+    }
+    app.image_view.sync(app.current_path.as_deref());
+    // SVGs are laid out at their document size, whatever their raster's
+    let source_size = match app.shown_svg() {
+        Some(svg) => [svg.size().x.ceil() as usize, svg.size().y.ceil() as usize],
+        None => texture.size(),
+    };
+    let size = app.image_view.display_size(source_size);
+    let (view, resp) = ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+    let mut zoom = app.image_zoom;
+    if app.image_fit {
+        let sx = if size[0] > 0 { view.width() / size[0] as f32 } else { 1.0 };
+        let sy = if size[1] > 0 { view.height() / size[1] as f32 } else { 1.0 };
+        let fit = sx.min(sy);
+        if fit.is_finite() && fit > 0.0 {
+            zoom = fit.clamp(0.1, 6.0);
+        }
+    }
+    // Wheel zoom keeps the point under the cursor in place
+    if let Some(pointer) = resp.hover_pos() {
+        let scroll = ui.input(|i| i.raw_scroll_delta.y);
+        if scroll != 0.0 {
+            let factor = if scroll > 0.0 { 1.10 } else { 1.0 / 1.10 };
+            let to = (zoom * factor).clamp(0.1, crate::app::MAX_IMAGE_ZOOM);
+            app.image_view.zoom_at(zoom, to, pointer - view.center());
+            app.image_fit = false;
+            app.image_zoom = to;
+            zoom = to;
+        }
+    }
+    if resp.dragged() { app.image_view.pan += resp.drag_delta(); }
+    let image_size = egui::vec2(size[0] as f32, size[1] as f32);
+    let rect = app.image_view.layout(view, image_size, zoom);
+    // Redraw SVGs at the on-screen resolution once the zoom settles
+    let ppp = ui.ctx().pixels_per_point();
+    if let Some(svg) = app.shown_svg() && let Some(image) = svg.update(ui.ctx(), zoom * ppp) {
+        app.replace_image(image);
+    }
+    if !view.contains_rect(rect.shrink(0.5)) {
+        let cursor = if resp.dragged() { egui::CursorIcon::Grabbing } else { egui::CursorIcon::Grab };
+        resp.clone().on_hover_cursor(cursor);
+    }
+
+    // One quad with its texture corners permuted for rotation and mirroring
+    let mut mesh = egui::Mesh::with_texture(texture.id());
+    let corners = [rect.left_top(), rect.right_top(), rect.right_bottom(), rect.left_bottom()];
+    for (pos, uv) in corners.into_iter().zip(app.image_view.uv_corners()) {
+        mesh.vertices.push(egui::epaint::Vertex { pos, uv, color: egui::Color32::WHITE });
+    }
+    mesh.add_triangle(0, 1, 2);
+    mesh.add_triangle(0, 2, 3);
+    ui.painter_at(view).add(mesh);
+    if app.pixel_inspector { inspect_pixels(ui, app, &resp, view, rect); }
+}
+
+pub(crate) fn render_central_panel(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) {
+    if let Some(err) = &app.error_message {
+        ui.colored_label(egui::Color32::RED, format!("Error: {}", err));
+    }
+
+    if app.image_compare.is_some() {
+        render_image_compare(ui, app);
+    } else if app.diff.is_some() {
+        render_diff(ui, app);
+    } else if let Some(content) = &app.content {
+        match content {
+            crate::app::Content::Text(doc) if doc.paged_file().is_none() && app.current_path.as_deref().is_some_and(crate::markdown::is_markdown) => {
+                match app.markdown_mode {
+                    crate::markdown::MarkdownMode::Source => render_text(ui, app, None),
+                    crate::markdown::MarkdownMode::Preview => render_markdown(ui, app),
+                    crate::markdown::MarkdownMode::SideBySide => ui.columns(2, |cols| {
+                        render_text(&mut cols[0], app, None);
+                        render_markdown(&mut cols[1], app);
+                    }),
+                }
+            }
+            crate::app::Content::Text(doc) if doc.paged_file().is_none()
+                && app.json_mode == crate::json::JsonMode::Tree
+                && app.current_path.as_deref().is_some_and(crate::json::is_json) => render_json(ui, app),
+            crate::app::Content::Text(_) if app.shows_table() => render_table(ui, app),
+            crate::app::Content::Text(_) => render_text(ui, app, None),
+            crate::app::Content::Hex(_) => render_hex(ui, app),
+            crate::app::Content::Image(_) => render_image(ui, app),
+        }
+    } else if let Some(pending) = app.pending_loads().iter().find(|p| matches!(p.purpose, crate::loader::LoadPurpose::Open { activate: true, .. })) {
+        ui.vertical_centered(|ui| {
+            ui.add_space(ui.available_height() * 0.3);
+            ui.add(egui::Spinner::new().size(32.0));
+            ui.add_space(12.0);
+            let name = pending.path.file_name().and_then(|s| s.to_str()).unwrap_or("file");
+            ui.label(RichText::new(format!("Loading {}…", name)).strong());
+            ui.add(egui::ProgressBar::new(pending.progress).desired_width(240.0).show_percentage());
+        });
     } else if app.error_message.is_none() {
         ui.vertical_centered(|ui| {
             ui.add_space(ui.available_height() * 0.2);
@@ -0,0 +1,212 @@
+use std::ffi::OsString;
+use std::path::PathBuf;
+use crate::themes::CodeTheme;
+
+const USAGE: &str = "\
+Usage: gfv [OPTIONS] [PATH[:LINE[:COL]]]...
+
+Open text and image files in tabs. The first path becomes the active tab.
+
+Options:
+  -l, --line N         Jump to line N in the first file
+  -s, --search QUERY   Start with QUERY in the Find bar
+  -t, --theme NAME     Use the named code theme (e.g. \"dracula\", \"night-owl\")
+      --no-session     Do not restore the previous session on startup
+      --settings PATH  Read and write settings at PATH instead of the default
+  -h, --help           Print this help and exit
+  -V, --version        Print the version and exit
+";
+
+/// A file named on the command line, with an optional 1-based jump target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenRequest {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+pub struct CliArgs {
+    pub files: Vec<OpenRequest>,
+    pub line: Option<usize>,
+    pub search: Option<String>,
+    pub theme: Option<CodeTheme>,
+    pub no_session: bool,
+    pub settings: Option<PathBuf>,
+}
+
+impl CliArgs {
+    /// The files as a running instance should open them, with `--line` folded
+    /// into the first one the way `open_requests` applies it.
+    pub fn forwarded_files(&self) -> Vec<OpenRequest> {
+        let mut files = self.files.clone();
+        if let Some(first) = files.first_mut() && first.line.is_none() {
+            first.line = self.line;
+        }
+        files
+    }
+
+    /// Options that only make sense for a new window and cannot be forwarded.
+    pub fn needs_own_window(&self) -> bool {
+        self.search.is_some() || self.theme.is_some()
+    }
+}
+
+pub enum CliAction {
+    Run(CliArgs),
+    Exit(String),
+}
+
+pub(crate) fn parse_args<I>(args: I) -> Result<CliAction, String>
+where
+    I: IntoIterator<Item = OsString>,
+{
+    let mut out = CliArgs::default();
+    let mut iter = args.into_iter();
+    let mut only_paths = false;
+    while let Some(arg) = iter.next() {
+        let s = arg.to_string_lossy().into_owned();
+        if only_paths || !s.starts_with('-') || s == "-" {
+            out.files.push(parse_path_spec(arg));
+            continue;
+        }
+        // Support both "--opt value" and "--opt=value"
+        let (name, inline) = match s.split_once('=') {
+            Some((n, v)) if n.starts_with("--") => (n.to_string(), Some(v.to_string())),
+            _ => (s.clone(), None),
+        };
+        let mut value = |opt: &str| -> Result<String, String> {
+            if let Some(v) = inline.clone() { return Ok(v); }
+            iter.next()
+                .map(|v| v.to_string_lossy().into_owned())
+                .ok_or_else(|| format!("{} requires a value", opt))
+        };
+        match name.as_str() {
+            "--" => only_paths = true,
+            "-h" | "--help" => return Ok(CliAction::Exit(USAGE.to_string())),
+            "-V" | "--version" => return Ok(CliAction::Exit(format!("gfv {}", env!("CARGO_PKG_VERSION")))),
+            "-l" | "--line" => {
+                let v = value("--line")?;
+                out.line = Some(parse_line_number(&v).ok_or_else(|| format!("Invalid line number: {}", v))?);
+            }
+            "-s" | "--search" => out.search = Some(value("--search")?),
+            "-t" | "--theme" => {
+                let v = value("--theme")?;
+                out.theme = Some(CodeTheme::from_name(&v).ok_or_else(|| {
+                    let names: Vec<&str> = CodeTheme::all().iter().map(|t| t.name()).collect();
+                    format!("Unknown theme: {} (available: {})", v, names.join(", "))
+                })?);
+            }
+            "--no-session" => out.no_session = true,
+            "--settings" => out.settings = Some(PathBuf::from(value("--settings")?)),
+            _ => return Err(format!("Unknown option: {}\n\n{}", s, USAGE)),
+        }
+    }
+    Ok(CliAction::Run(out))
+}
+
+fn parse_line_number(s: &str) -> Option<usize> {
+    s.parse::<usize>().ok().filter(|n| *n > 0)
+}
+
+/// Split `path:LINE[:COL]` (as printed by compilers and grep) into its parts.
+/// A path that exists verbatim is never split, so names containing colons still open.
+fn parse_path_spec(arg: OsString) -> OpenRequest {
+    let whole = PathBuf::from(&arg);
+    let plain = OpenRequest { path: whole.clone(), line: None, column: None };
+    if whole.exists() { return plain; }
+    let Some(s) = arg.to_str() else { return plain; };
+
+    let mut parts = s.rsplitn(3, ':');
+    let last = parts.next();
+    let middle = parts.next();
+    let first = parts.next();
+    match (first, middle, last) {
+        (Some(path), Some(line), Some(col)) if !path.is_empty() => {
+            if let (Some(l), Some(c)) = (parse_line_number(line), parse_line_number(col)) {
+                return OpenRequest { path: PathBuf::from(path), line: Some(l), column: Some(c) };
+            }
+            // Only the trailing component is numeric: treat it as the line
+            if let Some(l) = parse_line_number(col) {
+                return OpenRequest { path: PathBuf::from(format!("{}:{}", path, line)), line: Some(l), column: None };
+            }
+            plain
+        }
+        (None, Some(path), Some(line)) if !path.is_empty() => match parse_line_number(line) {
+            Some(l) => OpenRequest { path: PathBuf::from(path), line: Some(l), column: None },
+            None => plain,
+        },
+        _ => plain,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(arg: &str) -> OpenRequest {
+        parse_path_spec(OsString::from(arg))
+    }
+
+    fn request(path: &str, line: Option<usize>, column: Option<usize>) -> OpenRequest {
+        OpenRequest { path: PathBuf::from(path), line, column }
+    }
+
+    fn run(args: &[&str]) -> Result<CliArgs, String> {
+        match parse_args(args.iter().map(OsString::from))? {
+            CliAction::Run(args) => Ok(args),
+            CliAction::Exit(msg) => Err(format!("exited: {}", msg)),
+        }
+    }
+
+    #[test]
+    fn line_and_column_suffixes() {
+        assert_eq!(spec("no_such_file.rs:12"), request("no_such_file.rs", Some(12), None));
+        assert_eq!(spec("no_such_file.rs:12:3"), request("no_such_file.rs", Some(12), Some(3)));
+        // Only the last component is a number: the rest is the path
+        assert_eq!(spec("a:b:12"), request("a:b", Some(12), None));
+        // Line and column are 1-based
+        assert_eq!(spec("no_such_file.rs:0"), request("no_such_file.rs:0", None, None));
+        assert_eq!(spec("no_such_file.rs:12:0"), request("no_such_file.rs:12:0", None, None));
+        assert_eq!(spec("no_such_file.rs:x"), request("no_such_file.rs:x", None, None));
+        assert_eq!(spec(":12"), request(":12", None, None));
+        assert_eq!(spec("no_such_file.rs"), request("no_such_file.rs", None, None));
+    }
+
+    #[test]
+    fn existing_path_with_colons_is_not_split() {
+        let path = std::env::temp_dir().join(format!("gfv-cli-test-{}:12:3", std::process::id()));
+        std::fs::write(&path, "").unwrap();
+        let parsed = parse_path_spec(path.clone().into_os_string());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(parsed, OpenRequest { path, line: None, column: None });
+    }
+
+    #[test]
+    fn options_with_values() {
+        let args = run(&["--line", "40", "-s", "needle", "--theme=dracula", "a.rs:3"]).unwrap();
+        assert_eq!(args.line, Some(40));
+        assert_eq!(args.search.as_deref(), Some("needle"));
+        assert!(args.theme.is_some());
+        assert_eq!(args.files, [request("a.rs", Some(3), None)]);
+        // Only the first '=' separates the value
+        assert_eq!(run(&["--search=a=b"]).unwrap().search.as_deref(), Some("a=b"));
+        assert_eq!(run(&["-l", "7"]).unwrap().line, Some(7));
+    }
+
+    #[test]
+    fn bad_options() {
+        assert_eq!(run(&["--line"]).err().as_deref(), Some("--line requires a value"));
+        assert_eq!(run(&["--line", "0"]).err().as_deref(), Some("Invalid line number: 0"));
+        assert!(run(&["--theme", "nope"]).unwrap_err().starts_with("Unknown theme: nope"));
+        assert!(run(&["--bogus"]).unwrap_err().starts_with("Unknown option: --bogus"));
+        assert!(run(&["--help"]).unwrap_err().starts_with("exited: Usage:"));
+    }
+
+    #[test]
+    fn double_dash_ends_options() {
+        let args = run(&["-", "--", "--line", "-x:2"]).unwrap();
+        assert_eq!(args.line, None);
+        assert_eq!(args.files, [request("-", None, None), request("--line", None, None), request("-x", Some(2), None)]);
+    }
+}
@@ -0,0 +1,151 @@
+// Image compare: two images decoded side by side for reviewing screenshot
+// changes. Every mode shares one zoom and pan, and both images are aligned
+// at their top-left corner, the same alignment the difference image uses.
+// Rendering lives in central.rs.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const DIFF_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 0, 128); // Magenta
+
+/// How the two images are shown.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum CompareMode {
+    #[default]
+    SideBySide,
+    Swipe,
+    OnionSkin,
+    Difference,
+}
+
+pub(crate) struct ImageCompare {
+    pub(crate) left: PathBuf,
+    pub(crate) right: PathBuf,
+    images: [egui::ColorImage; 2],
+    pub(crate) textures: [egui::TextureHandle; 2],
+    pub(crate) zoom: f32,
+    /// Offset of the images from the centre of the view, in points.
+    pub(crate) pan: egui::Vec2,
+    /// Fit both images into the view on the next frame.
+    pub(crate) fit: bool,
+    /// Swipe divider position, 0 (all right image) to 1 (all left image).
+    pub(crate) swipe: f32,
+    /// Opacity of the right image over the left in onion skin mode.
+    pub(crate) opacity: f32,
+    /// Largest per-channel difference still counted as equal.
+    pub(crate) tolerance: u8,
+    // Difference image, the number of differing pixels, and the tolerance it was made for
+    difference: Option<(egui::TextureHandle, usize, u8)>,
+}
+
+impl ImageCompare {
+    pub(crate) fn open(ctx: &egui::Context, left: PathBuf, right: PathBuf) -> Result<Self, String> {
+        let images = [load(&left)?, load(&right)?];
+        let textures = [
+            ctx.load_texture(format!("compare:{}", left.display()), images[0].clone(), crate::io::IMAGE_TEXTURE_OPTIONS),
+            ctx.load_texture(format!("compare:{}", right.display()), images[1].clone(), crate::io::IMAGE_TEXTURE_OPTIONS),
+        ];
+        Ok(Self {
+            left,
+            right,
+            images,
+            textures,
+            zoom: 1.0,
+            pan: egui::Vec2::ZERO,
+            fit: true,
+            swipe: 0.5,
+            opacity: 0.5,
+            tolerance: 0,
+            difference: None,
+        })
+    }
+
+    pub(crate) fn size(&self, side: usize) -> [usize; 2] {
+        self.images[side].size
+    }
+
+    /// Size of the area both images are laid out in, in image pixels.
+    pub(crate) fn extent(&self) -> egui::Vec2 {
+        let [a, b] = [self.size(0), self.size(1)];
+        egui::vec2(a[0].max(b[0]) as f32, a[1].max(b[1]) as f32)
+    }
+
+    /// Zoom so the images fill `view`, centred.
+    pub(crate) fn fit_to(&mut self, view: egui::Vec2) {
+        let extent = self.extent();
+        let zoom = (view.x / extent.x).min(view.y / extent.y);
+        if zoom.is_finite() && zoom > 0.0 { self.zoom = zoom.clamp(0.05, 32.0); }
+        self.pan = egui::Vec2::ZERO;
+        self.fit = false;
+    }
+
+    /// Zoom by `factor` keeping the image point under `anchor` (relative to
+    /// the view centre) in place.
+    pub(crate) fn zoom_at(&mut self, factor: f32, anchor: egui::Vec2) {
+        let zoom = (self.zoom * factor).clamp(0.05, 32.0);
+        self.pan = anchor - (anchor - self.pan) * (zoom / self.zoom);
+        self.zoom = zoom;
+    }
+
+    /// Screen rectangle of image `side` in a view centred on `center`.
+    pub(crate) fn image_rect(&self, side: usize, center: egui::Pos2) -> egui::Rect {
+        let origin = center + self.pan - self.extent() * self.zoom / 2.0;
+        let [w, h] = self.size(side);
+        egui::Rect::from_min_size(origin, egui::vec2(w as f32, h as f32) * self.zoom)
+    }
+
+    /// Difference texture and differing pixel count, recomputed when the tolerance changed.
+    pub(crate) fn difference(&mut self, ctx: &egui::Context) -> (&egui::TextureHandle, usize) {
+        if self.difference.as_ref().is_none_or(|(_, _, tolerance)| *tolerance != self.tolerance) {
+            let (image, count) = difference_image(&self.images[0], &self.images[1], self.tolerance);
+            let texture = ctx.load_texture("compare:difference", image, crate::io::IMAGE_TEXTURE_OPTIONS);
+            self.difference = Some((texture, count, self.tolerance));
+        }
+        let (texture, count, _) = self.difference.as_ref().expect("difference was just computed");
+        (texture, *count)
+    }
+}
+
+fn load(path: &Path) -> Result<egui::ColorImage, String> {
+    let name = path.file_name().and_then(|s| s.to_str()).unwrap_or("image");
+    let size = std::fs::metadata(path).map_err(|e| format!("Cannot compare {}: {}", name, e))?.len();
+    if size > crate::app::MAX_FILE_SIZE_BYTES {
+        return Err(format!("Cannot compare {}: file too large", name));
+    }
+    let bytes = std::fs::read(path).map_err(|e| format!("Cannot compare {}: {}", name, e))?;
+    crate::io::decode_image(path, &bytes)
+}
+
+// Differing pixels in magenta over a faded grey copy of the left image.
+// Where only one image has pixels (different sizes) they count as differing.
+fn difference_image(a: &egui::ColorImage, b: &egui::ColorImage, tolerance: u8) -> (egui::ColorImage, usize) {
+    let [w, h] = [a.size[0].max(b.size[0]), a.size[1].max(b.size[1])];
+    let pixel = |image: &egui::ColorImage, x: usize, y: usize| {
+        (x < image.size[0] && y < image.size[1]).then(|| image.pixels[y * image.size[0] + x])
+    };
+    let mut out = egui::ColorImage::new([w, h], egui::Color32::TRANSPARENT);
+    let mut count = 0;
+    for y in 0..h {
+        for x in 0..w {
+            let same = match (pixel(a, x, y), pixel(b, x, y)) {
+                (Some(p), Some(q)) => {
+                    let (p, q) = (p.to_srgba_unmultiplied(), q.to_srgba_unmultiplied());
+                    p.iter().zip(q).all(|(c, d)| c.abs_diff(d) <= tolerance).then_some(p)
+                }
+                _ => None,
+            };
+            out.pixels[y * w + x] = match same {
+                Some([r, g, b, _]) => {
+                    let luma = (r as u32 * 3 + g as u32 * 6 + b as u32) / 10;
+                    egui::Color32::from_gray((luma / 4 + 24) as u8)
+                }
+                None => {
+                    count += 1;
+                    DIFF_COLOR
+                }
+            };
+        }
+    }
+    (out, count)
+}
@@ -0,0 +1,231 @@
+// Diff view: a line diff (similar, patience) between two texts, with changed
+// words highlighted inside each pair of replaced lines. Rows are built once
+// for the side-by-side layout; the unified layout indexes into them, so
+// switching layouts is free. Rendering lives in central.rs.
+
+use serde::{Deserialize, Serialize};
+use similar::{Algorithm, DiffTag};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+// Past this the line diff settles for a coarser result instead of hanging the UI
+const DIFF_TIMEOUT: Duration = Duration::from_secs(2);
+// Word highlighting is skipped for longer lines
+const MAX_WORD_DIFF_CHARS: usize = 2000;
+
+/// How a diff is laid out.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum DiffLayout {
+    #[default]
+    SideBySide,
+    Unified,
+}
+
+pub(crate) struct DiffLine {
+    /// 0-based line in its file.
+    pub(crate) number: usize,
+    pub(crate) text: String,
+    /// Byte ranges that differ from the paired line on the other side.
+    pub(crate) changed: Vec<Range<usize>>,
+}
+
+/// One side-by-side row. A missing side is padding opposite an insertion or
+/// deletion; `change` is false only for lines equal on both sides.
+pub(crate) struct Row {
+    pub(crate) left: Option<DiffLine>,
+    pub(crate) right: Option<DiffLine>,
+    pub(crate) change: bool,
+}
+
+/// Which part of a side-by-side row a unified row shows.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Side {
+    Both,
+    Left,
+    Right,
+}
+
+pub(crate) struct DiffView {
+    pub(crate) left: PathBuf,
+    pub(crate) right: PathBuf,
+    pub(crate) rows: Vec<Row>,
+    /// Unified layout: deleted lines of a hunk, then its inserted lines.
+    pub(crate) unified: Vec<(usize, Side)>,
+    // First row of each hunk, per layout
+    hunks: Vec<usize>,
+    unified_hunks: Vec<usize>,
+    pub(crate) current_hunk: Option<usize>,
+    /// Row (in the current layout) to bring into view on the next frame.
+    pub(crate) scroll_to: Option<usize>,
+    pub(crate) added: usize,
+    pub(crate) removed: usize,
+    /// Longest line in characters, for horizontal scrolling.
+    pub(crate) max_chars: usize,
+    /// Shared horizontal scroll offset of both sides, in points.
+    pub(crate) h_offset: f32,
+}
+
+impl DiffView {
+    pub(crate) fn compare(left: PathBuf, left_text: &str, right: PathBuf, right_text: &str) -> Self {
+        let old: Vec<&str> = left_text.lines().collect();
+        let new: Vec<&str> = right_text.lines().collect();
+        let deadline = Instant::now() + DIFF_TIMEOUT;
+        let ops = similar::capture_diff_slices_deadline(Algorithm::Patience, &old, &new, Some(deadline));
+
+        let line = |lines: &[&str], number: usize| DiffLine { number, text: lines[number].to_string(), changed: Vec::new() };
+        let mut rows = Vec::new();
+        let mut hunks = Vec::new();
+        // Consecutive non-equal ops form one hunk
+        let mut pending: (Range<usize>, Range<usize>) = (0..0, 0..0);
+        let flush = |rows: &mut Vec<Row>, hunks: &mut Vec<usize>, pending: &mut (Range<usize>, Range<usize>)| {
+            let (dels, ins) = std::mem::replace(pending, (0..0, 0..0));
+            if dels.is_empty() && ins.is_empty() { return; }
+            hunks.push(rows.len());
+            for k in 0..dels.len().max(ins.len()) {
+                let mut left = (k < dels.len()).then(|| line(&old, dels.start + k));
+                let mut right = (k < ins.len()).then(|| line(&new, ins.start + k));
+                if let (Some(l), Some(r)) = (&mut left, &mut right) {
+                    (l.changed, r.changed) = word_changes(&l.text, &r.text, deadline);
+                }
+                rows.push(Row { left, right, change: true });
+            }
+        };
+        for op in &ops {
+            let (tag, old_range, new_range) = op.as_tag_tuple();
+            if tag == DiffTag::Equal {
+                flush(&mut rows, &mut hunks, &mut pending);
+                for (o, n) in old_range.zip(new_range) {
+                    rows.push(Row { left: Some(line(&old, o)), right: Some(line(&new, n)), change: false });
+                }
+            } else {
+                if pending.0.is_empty() && pending.1.is_empty() {
+                    pending = (old_range.start..old_range.start, new_range.start..new_range.start);
+                }
+                pending.0.end = old_range.end;
+                pending.1.end = new_range.end;
+            }
+        }
+        flush(&mut rows, &mut hunks, &mut pending);
+
+        let mut unified = Vec::with_capacity(rows.len());
+        let mut unified_hunks = Vec::with_capacity(hunks.len());
+        let mut i = 0;
+        while i < rows.len() {
+            if !rows[i].change {
+                unified.push((i, Side::Both));
+                i += 1;
+                continue;
+            }
+            let end = rows[i..].iter().position(|r| !r.change).map_or(rows.len(), |n| i + n);
+            unified_hunks.push(unified.len());
+            unified.extend((i..end).filter(|&r| rows[r].left.is_some()).map(|r| (r, Side::Left)));
+            unified.extend((i..end).filter(|&r| rows[r].right.is_some()).map(|r| (r, Side::Right)));
+            i = end;
+        }
+
+        let removed = rows.iter().filter(|r| r.change && r.left.is_some()).count();
+        let added = rows.iter().filter(|r| r.change && r.right.is_some()).count();
+        let max_chars = old.iter().chain(&new).map(|l| l.chars().count()).max().unwrap_or(0);
+        Self {
+            left,
+            right,
+            rows,
+            unified,
+            hunks,
+            unified_hunks,
+            current_hunk: None,
+            scroll_to: None,
+            added,
+            removed,
+            max_chars,
+            h_offset: 0.0,
+        }
+    }
+
+    pub(crate) fn hunk_count(&self) -> usize {
+        self.hunks.len()
+    }
+
+    pub(crate) fn row_count(&self, layout: DiffLayout) -> usize {
+        match layout {
+            DiffLayout::SideBySide => self.rows.len(),
+            DiffLayout::Unified => self.unified.len(),
+        }
+    }
+
+    /// Move to the next (or previous) hunk, wrapping around, and scroll to it.
+    pub(crate) fn jump_hunk(&mut self, forward: bool, layout: DiffLayout) {
+        let n = self.hunks.len();
+        if n == 0 { return; }
+        let next = match self.current_hunk {
+            Some(h) if forward => (h + 1) % n,
+            Some(h) => (h + n - 1) % n,
+            None if forward => 0,
+            None => n - 1,
+        };
+        self.current_hunk = Some(next);
+        self.scroll_to = Some(self.hunk_start(next, layout));
+    }
+
+    /// Keep the current hunk in view after the layout changed.
+    pub(crate) fn relayout(&mut self, layout: DiffLayout) {
+        self.scroll_to = self.current_hunk.map(|h| self.hunk_start(h, layout));
+    }
+
+    fn hunk_start(&self, hunk: usize, layout: DiffLayout) -> usize {
+        match layout {
+            DiffLayout::SideBySide => self.hunks[hunk],
+            DiffLayout::Unified => self.unified_hunks[hunk],
+        }
+    }
+
+    /// Rows of the current hunk in the given layout, for highlighting it.
+    pub(crate) fn current_hunk_rows(&self, layout: DiffLayout) -> Range<usize> {
+        let Some(h) = self.current_hunk else { return 0..0; };
+        let start = self.hunk_start(h, layout);
+        let len = match layout {
+            DiffLayout::SideBySide => self.rows[start..].iter().take_while(|r| r.change).count(),
+            DiffLayout::Unified => self.unified[start..].iter().take_while(|(_, side)| *side != Side::Both).count(),
+        };
+        start..start + len
+    }
+}
+
+// Split into words, runs of whitespace and single other characters, so
+// punctuation changes do not swallow the neighbouring word
+fn tokens(text: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    let class = |c: char| if c.is_alphanumeric() || c == '_' { 0 } else if c.is_whitespace() { 1 } else { 2 };
+    let mut prev: Option<u8> = None;
+    for (i, c) in text.char_indices() {
+        let k = class(c);
+        if i > start && (prev != Some(k) || k == 2) {
+            out.push(&text[start..i]);
+            start = i;
+        }
+        prev = Some(k);
+    }
+    if start < text.len() { out.push(&text[start..]); }
+    out
+}
+
+// Byte ranges of the tokens that differ between a deleted and an inserted line
+fn word_changes(old: &str, new: &str, deadline: Instant) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
+    if old.len() > MAX_WORD_DIFF_CHARS || new.len() > MAX_WORD_DIFF_CHARS { return (Vec::new(), Vec::new()); }
+    let (a, b) = (tokens(old), tokens(new));
+    let offsets = |toks: &[&str]| -> Vec<usize> {
+        toks.iter().scan(0, |at, t| { let start = *at; *at += t.len(); Some(start) }).chain(std::iter::once(toks.iter().map(|t| t.len()).sum())).collect()
+    };
+    let (a_at, b_at) = (offsets(&a), offsets(&b));
+    let mut old_changed: Vec<Range<usize>> = Vec::new();
+    let mut new_changed: Vec<Range<usize>> = Vec::new();
+    for op in similar::capture_diff_slices_deadline(Algorithm::Myers, &a, &b, Some(deadline)) {
+        let (tag, o, n) = op.as_tag_tuple();
+        if tag == DiffTag::Equal { continue; }
+        if !o.is_empty() { old_changed.push(a_at[o.start]..a_at[o.end]); }
+        if !n.is_empty() { new_changed.push(b_at[n.start]..b_at[n.end]); }
+    }
+    (old_changed, new_changed)
+}
@@ -0,0 +1,163 @@
+// Text content with a precomputed line index, so the viewer can fetch any line
+// (and map scroll offsets to lines) without rescanning the whole string.
+// Files too large to hold in memory are backed by a `PagedFile` instead.
+
+use crate::match_index::{MatchIndex, SharedMatches};
+use crate::paged::PagedFile;
+use std::borrow::Cow;
+use std::cell::{Ref, RefCell};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NEXT_DOC_ID: AtomicU64 = AtomicU64::new(1);
+
+pub struct TextDoc {
+    // Shared with Find indexing jobs, which read it without a copy; an
+    // append while one runs copies it once
+    text: Arc<String>,
+    // Byte offset where each line starts (`str::lines` semantics)
+    line_starts: Vec<usize>,
+    // Characters per line, for wrapped-row estimates with a monospace font
+    line_chars: Vec<u32>,
+    max_line_chars: usize,
+    // Paged backing read from disk; the fields above stay empty when set
+    paged: Option<Arc<PagedFile>>,
+    id: u64,
+    // Cumulative visual rows per line for the last wrap width asked for
+    wrap_rows: RefCell<Option<(usize, usize, Vec<usize>)>>,
+    // Find matches, shared with the tab the document was made from
+    matches: SharedMatches,
+}
+
+impl TextDoc {
+    pub(crate) fn new(text: String) -> Self {
+        let mut doc = Self {
+            text: Arc::default(),
+            line_starts: Vec::new(),
+            line_chars: Vec::new(),
+            max_line_chars: 0,
+            paged: None,
+            id: NEXT_DOC_ID.fetch_add(1, Ordering::Relaxed),
+            wrap_rows: RefCell::new(None),
+            matches: SharedMatches::default(),
+        };
+        doc.push_str(&text);
+        doc
+    }
+
+    /// A read-only view of a file paged in from disk. Line counts follow its
+    /// background indexer; wrap estimates and match counting are not offered.
+    pub(crate) fn paged(file: Arc<PagedFile>) -> Self {
+        let mut doc = Self::new(String::new());
+        doc.paged = Some(file);
+        doc
+    }
+
+    pub(crate) fn paged_file(&self) -> Option<&Arc<PagedFile>> {
+        self.paged.as_ref()
+    }
+
+    /// Share the Find match index of the tab this document shows.
+    pub(crate) fn with_matches(mut self, matches: SharedMatches) -> Self {
+        self.matches = matches;
+        self
+    }
+
+    /// Stable identity for caches keyed on this document; survives appends.
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub(crate) fn line_count(&self) -> usize {
+        match &self.paged {
+            Some(file) => file.line_count(),
+            None => self.line_starts.len(),
+        }
+    }
+
+    pub(crate) fn max_line_chars(&self) -> usize {
+        match &self.paged {
+            Some(file) => file.max_line_bytes(),
+            None => self.max_line_chars,
+        }
+    }
+
+    /// Line `i` without its terminator, like the `i`th item of `str::lines`.
+    pub(crate) fn line(&self, i: usize) -> Cow<'_, str> {
+        match &self.paged {
+            Some(file) => file.line(i),
+            None => Cow::Borrowed(self.owned_line(i)),
+        }
+    }
+
+    fn owned_line(&self, i: usize) -> &str {
+        let start = self.line_starts[i];
+        let end = self.line_starts.get(i + 1).map(|n| n - 1).unwrap_or(self.text.len());
+        let line = &self.text[start..end];
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        line.strip_suffix('\r').unwrap_or(line)
+    }
+
+    /// Append text, extending the index from the old last line onwards.
+    pub(crate) fn push_str(&mut self, chunk: &str) {
+        if chunk.is_empty() || self.paged.is_some() { return; }
+        let old_len = self.text.len();
+        // The old last line may continue into the chunk; re-measure it
+        let reindex_from = if old_len == 0 || self.text.ends_with('\n') {
+            self.line_count()
+        } else {
+            self.line_count() - 1
+        };
+        Arc::make_mut(&mut self.text).push_str(chunk);
+        if old_len == 0 {
+            self.line_starts.push(0);
+        } else if self.text.as_bytes()[old_len - 1] == b'\n' {
+            self.line_starts.push(old_len);
+        }
+        let bytes = self.text.as_bytes();
+        for (i, b) in bytes.iter().enumerate().skip(old_len) {
+            if *b == b'\n' && i + 1 < bytes.len() {
+                self.line_starts.push(i + 1);
+            }
+        }
+        self.line_chars.truncate(reindex_from);
+        for i in reindex_from..self.line_count() {
+            let chars = self.owned_line(i).chars().count();
+            self.max_line_chars = self.max_line_chars.max(chars);
+            self.line_chars.push(chars.min(u32::MAX as usize) as u32);
+        }
+        self.wrap_rows.replace(None);
+    }
+
+    /// Cumulative visual row counts (length `line_count + 1`) when every row
+    /// holds `cols` characters and each line is prefixed by `prefix_chars`.
+    /// None for paged documents, where every line takes one row.
+    pub(crate) fn row_offsets(&self, cols: usize, prefix_chars: usize) -> Option<Ref<'_, Vec<usize>>> {
+        if self.paged.is_some() { return None; }
+        let stale = !matches!(&*self.wrap_rows.borrow(), Some((c, p, _)) if *c == cols && *p == prefix_chars);
+        if stale {
+            let cols = cols.max(1);
+            let mut rows = Vec::with_capacity(self.line_count() + 1);
+            let mut total = 0usize;
+            rows.push(0);
+            for chars in &self.line_chars {
+                total += (*chars as usize + prefix_chars).div_ceil(cols).max(1);
+                rows.push(total);
+            }
+            self.wrap_rows.replace(Some((cols, prefix_chars, rows)));
+        }
+        Some(Ref::map(self.wrap_rows.borrow(), |c| &c.as_ref().expect("row offsets computed above").2))
+    }
+
+    /// The whole text, shared without a copy. Empty for paged documents.
+    pub(crate) fn shared_text(&self) -> Arc<String> {
+        self.text.clone()
+    }
+
+    /// Find matches of the whole text, or None while they are indexed in
+    /// the background. Paged documents are not indexed.
+    pub(crate) fn match_index(&self, matcher: &crate::search::Matcher, ctx: &eframe::egui::Context) -> Option<Arc<MatchIndex>> {
+        if self.paged.is_some() { return None; }
+        self.matches.get(matcher, &self.text, ctx)
+    }
+}
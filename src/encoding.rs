@@ -0,0 +1,131 @@
+// Text decoding: BOM sniffing, BOM-less UTF-16 detection and a statistical
+// guess (chardetng) for legacy single- and multi-byte charsets. Everything is
+// decoded to UTF-8 once at load time; the rest of the viewer only sees `str`.
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+/// Encodings offered in "Reopen with encoding…", most common first.
+pub(crate) const CHOICES: &[&Encoding] = &[
+    UTF_8,
+    UTF_16LE,
+    UTF_16BE,
+    encoding_rs::WINDOWS_1252,
+    encoding_rs::ISO_8859_15,
+    encoding_rs::WINDOWS_1250,
+    encoding_rs::ISO_8859_2,
+    encoding_rs::WINDOWS_1251,
+    encoding_rs::KOI8_R,
+    encoding_rs::WINDOWS_1253,
+    encoding_rs::WINDOWS_1254,
+    encoding_rs::WINDOWS_1255,
+    encoding_rs::WINDOWS_1256,
+    encoding_rs::WINDOWS_874,
+    encoding_rs::SHIFT_JIS,
+    encoding_rs::EUC_JP,
+    encoding_rs::ISO_2022_JP,
+    encoding_rs::GBK,
+    encoding_rs::GB18030,
+    encoding_rs::BIG5,
+    encoding_rs::EUC_KR,
+];
+
+pub(crate) struct DecodedText {
+    pub(crate) text: String,
+    pub(crate) encoding: &'static Encoding,
+    pub(crate) is_lossy: bool,
+    pub(crate) line_count: usize,
+}
+
+/// Look up an encoding by the label stored in settings.
+pub(crate) fn by_name(name: &str) -> Option<&'static Encoding> {
+    Encoding::for_label(name.as_bytes())
+}
+
+/// Decode `bytes` as `forced` if given, otherwise as whatever the data looks
+/// like. A BOM matching the chosen encoding is dropped either way.
+pub(crate) fn decode(bytes: &[u8], forced: Option<&'static Encoding>) -> DecodedText {
+    let encoding = forced.unwrap_or_else(|| detect(bytes));
+    let (text, had_errors) = encoding.decode_with_bom_removal(bytes);
+    let text = text.into_owned();
+    let line_count = text.lines().count();
+    DecodedText { text, encoding, is_lossy: had_errors, line_count }
+}
+
+pub(crate) fn detect(bytes: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+    // Before the UTF-8 check: ASCII-range UTF-16 is valid UTF-8 full of NULs
+    if let Some(encoding) = sniff_utf16(bytes) {
+        return encoding;
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return UTF_8;
+    }
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(bytes, true);
+    detector.guess(None, true)
+}
+
+// BOM-less UTF-16 text that is mostly ASCII has a zero in every other byte;
+// which half holds the zeros tells the byte order
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sample = &bytes[..bytes.len().min(4096) & !1];
+    if sample.len() < 4 { return None; }
+    let pairs = sample.len() / 2;
+    let even_zeros = sample.iter().step_by(2).filter(|b| **b == 0).count();
+    let odd_zeros = sample.iter().skip(1).step_by(2).filter(|b| **b == 0).count();
+    if odd_zeros * 10 >= pairs * 7 && even_zeros * 10 <= pairs {
+        Some(UTF_16LE)
+    } else if even_zeros * 10 >= pairs * 7 && odd_zeros * 10 <= pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+        text.encode_utf16().flat_map(|u| if big_endian { u.to_be_bytes() } else { u.to_le_bytes() }).collect()
+    }
+
+    #[test]
+    fn bomless_utf16() {
+        let text = "fn main() {\n    println!(\"héllo\");\n}\n";
+        assert_eq!(detect(&utf16(text, false)), UTF_16LE);
+        assert_eq!(detect(&utf16(text, true)), UTF_16BE);
+        assert_eq!(decode(&utf16(text, true), None).text, text);
+        // Too short to tell, and binary with scattered NULs is not UTF-16
+        assert_eq!(sniff_utf16(b"a\0"), None);
+        assert_eq!(sniff_utf16(b"\x7fELF\x02\x01\x01\0\0\0\0\0\0\0\0\0\x03\0\x3e\0"), None);
+    }
+
+    #[test]
+    fn bom_wins() {
+        let mut bytes = b"\xef\xbb\xbf".to_vec();
+        bytes.extend(utf16("ab", false));
+        assert_eq!(detect(&bytes), UTF_8);
+        let decoded = decode(b"\xff\xfea\0b\0", None);
+        assert_eq!((decoded.encoding, decoded.text.as_str()), (UTF_16LE, "ab"));
+    }
+
+    #[test]
+    fn latin1_text() {
+        let bytes = b"Caf\xe9 cr\xe8me br\xfbl\xe9e, na\xefve fa\xe7ade and a r\xe9sum\xe9 for the \xe9l\xe8ve.\n";
+        let decoded = decode(bytes, None);
+        assert_eq!(decoded.encoding, encoding_rs::WINDOWS_1252);
+        assert!(decoded.text.starts_with("Café crème brûlée"));
+        assert!(!decoded.is_lossy);
+        assert_eq!(detect("Café crème".as_bytes()), UTF_8);
+    }
+
+    #[test]
+    fn forced_encoding() {
+        let decoded = decode(b"\xe9t\xe9\n\xe9t\xe9", Some(encoding_rs::ISO_8859_15));
+        assert_eq!((decoded.text.as_str(), decoded.line_count), ("été\nété", 2));
+        assert!(decode(b"\xe9t\xe9", Some(UTF_8)).is_lossy);
+    }
+}
@@ -0,0 +1,298 @@
+// File kind detection from content rather than extension: magic bytes for
+// images and common binary formats, shebangs, UTF-8 validity and a control
+// character heuristic, with known file names (Makefile, Dockerfile, …) and
+// extensions as hints. One classification drives loading, the file dialog
+// filters and prev/next sibling navigation.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+// How much of a file is looked at to classify it
+pub(crate) const SNIFF_BYTES: usize = 8192;
+
+/// Extensions offered by the "Text/Source" dialog filter. Detection does not
+/// depend on them; any file that reads as text opens as text.
+pub(crate) const TEXT_EXTENSIONS: &[&str] = &[
+    "txt", "md", "markdown", "rst", "log", "csv", "tsv", "json", "jsonl", "ndjson", "toml", "yaml", "yml",
+    "ini", "cfg", "conf", "xml", "html", "htm", "css", "scss", "sass", "less", "js", "mjs", "cjs", "ts",
+    "tsx", "jsx", "vue", "svelte", "rs", "py", "pyi", "rb", "php", "pl", "lua", "go", "java", "kt", "kts",
+    "scala", "swift", "c", "h", "cc", "cpp", "cxx", "hpp", "hh", "cs", "m", "mm", "r", "jl", "dart", "zig",
+    "hs", "ex", "exs", "erl", "clj", "sh", "bash", "zsh", "fish", "ps1", "bat", "cmd", "sql", "graphql",
+    "proto", "cmake", "gradle", "tex", "diff", "patch", "env", "properties", "lock",
+];
+
+pub(crate) const IMAGE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "bmp", "webp", "tif", "tiff", "ico", "cur", "tga", "qoi", "pbm", "pgm", "ppm",
+    "pnm", "pam", "hdr", "exr", "svg", "svgz",
+];
+
+// Signatures of binary formats that can start with printable bytes
+const BINARY_MAGIC: &[&[u8]] = &[
+    b"\x7fELF",
+    b"MZ",
+    b"PK\x03\x04",
+    b"%PDF-",
+    b"\x1f\x8b",
+    b"7z\xbc\xaf\x27\x1c",
+    b"Rar!\x1a\x07",
+    b"SQLite format 3\0",
+    b"\0asm",
+    b"\xca\xfe\xba\xbe",
+    b"\xcf\xfa\xed\xfe",
+    b"OggS",
+    b"fLaC",
+    b"ID3",
+];
+
+// File names without a telling extension, and the syntax to highlight them as
+const KNOWN_NAMES: &[(&str, &str)] = &[
+    ("makefile", "makefile"),
+    ("gnumakefile", "makefile"),
+    ("dockerfile", "sh"),
+    ("containerfile", "sh"),
+    ("jenkinsfile", "groovy"),
+    ("gemfile", "rb"),
+    ("rakefile", "rb"),
+    ("vagrantfile", "rb"),
+    ("podfile", "rb"),
+    ("cmakelists.txt", "cmake"),
+    ("cargo.lock", "toml"),
+    (".bashrc", "sh"),
+    (".bash_profile", "sh"),
+    (".zshrc", "sh"),
+    (".profile", "sh"),
+    (".gitconfig", "ini"),
+    (".editorconfig", "ini"),
+];
+
+// Interpreters named in a shebang, and the syntax they imply
+const INTERPRETERS: &[(&str, &str)] = &[
+    ("python", "py"),
+    ("bash", "sh"),
+    ("sh", "sh"),
+    ("zsh", "sh"),
+    ("dash", "sh"),
+    ("ksh", "sh"),
+    ("node", "js"),
+    ("deno", "ts"),
+    ("perl", "pl"),
+    ("ruby", "rb"),
+    ("php", "php"),
+    ("lua", "lua"),
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum FileKind {
+    Text,
+    Image,
+    /// Anything else; shown in the hex view.
+    Binary,
+}
+
+/// Classify `path` from its first SNIFF_BYTES. Unreadable files are guessed
+/// from their name so the load reports the actual error.
+pub(crate) fn classify(path: &Path) -> FileKind {
+    match read_head(path) {
+        Ok(head) => classify_bytes(path, &head),
+        Err(_) => kind_from_name(path),
+    }
+}
+
+/// Classify from the start of a file's data (`head`, at most SNIFF_BYTES of it).
+pub(crate) fn classify_bytes(path: &Path, head: &[u8]) -> FileKind {
+    if is_image_data(path, head) { return FileKind::Image; }
+    if head.is_empty() { return kind_from_name(path); }
+    if BINARY_MAGIC.iter().any(|magic| head.starts_with(magic)) { return FileKind::Binary; }
+    if head.starts_with(b"#!") { return FileKind::Text; }
+    if looks_binary(head) { FileKind::Binary } else { FileKind::Text }
+}
+
+pub(crate) fn read_head(path: &Path) -> std::io::Result<Vec<u8>> {
+    let mut head = Vec::with_capacity(SNIFF_BYTES);
+    File::open(path)?.take(SNIFF_BYTES as u64).read_to_end(&mut head)?;
+    Ok(head)
+}
+
+// Image formats the viewer can decode, recognised by signature. TGA has
+// none and goes by extension; SVG is XML with an <svg> root element.
+fn is_image_data(path: &Path, head: &[u8]) -> bool {
+    use image::ImageFormat as F;
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+    match image::guess_format(head) {
+        Ok(F::Png | F::Jpeg | F::Gif | F::Bmp | F::WebP | F::Tiff | F::Ico | F::Qoi | F::Hdr | F::OpenExr) => true,
+        // Only two bytes of magic ("P1".."P7"), so also look for the width
+        // after them to tell a PNM header from text starting with "P3 "
+        Ok(F::Pnm) => {
+            let rest = head.get(2..).unwrap_or_default();
+            rest.first().is_some_and(u8::is_ascii_whitespace)
+                && rest.iter().find(|b| !b.is_ascii_whitespace()).is_some_and(|b| b.is_ascii_digit() || *b == b'#')
+        }
+        _ if head.starts_with(b"\0\0\x02\0") => ext == "cur",
+        _ if ext == "tga" => looks_binary(head),
+        _ => is_svg(path, head),
+    }
+}
+
+/// Whether a file is an SVG image, plain or gzipped (.svgz).
+pub(crate) fn is_svg(path: &Path, head: &[u8]) -> bool {
+    let svgz = path.extension().and_then(|s| s.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("svgz"));
+    if svgz { head.starts_with(b"\x1f\x8b") } else { is_svg_data(head) }
+}
+
+/// Whether `head` is the start of an SVG document: markup whose root element
+/// is <svg>, after any XML declaration, comments and doctype.
+fn is_svg_data(head: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(head);
+    let mut rest = text.trim_start_matches('\u{feff}');
+    loop {
+        rest = rest.trim_start();
+        let skip = if rest.starts_with("<?") {
+            rest.find("?>").map(|i| i + 2)
+        } else if rest.starts_with("<!--") {
+            rest.find("-->").map(|i| i + 3)
+        } else if rest.starts_with("<!") {
+            rest.find('>').map(|i| i + 1)
+        } else {
+            return rest.strip_prefix("<svg").and_then(|r| r.chars().next()).is_some_and(|c| c.is_whitespace() || c == '>' || c == '/');
+        };
+        match skip {
+            Some(i) => rest = &rest[i..],
+            None => return false,
+        }
+    }
+}
+
+fn kind_from_name(path: &Path) -> FileKind {
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+    if IMAGE_EXTENSIONS.contains(&ext.as_str()) { FileKind::Image } else { FileKind::Text }
+}
+
+/// Valid UTF-8 without NULs is text. Otherwise NUL bytes or a high share of
+/// control characters mean "not text", unless the data looks like UTF-16
+/// (which is full of NULs) or decodes as a legacy charset.
+pub(crate) fn looks_binary(bytes: &[u8]) -> bool {
+    let sample = &bytes[..bytes.len().min(SNIFF_BYTES)];
+    if sample.is_empty() { return false; }
+    let has_nul = sample.contains(&0);
+    // A multi-byte character cut off by the sample end still counts as valid
+    let valid_utf8 = match std::str::from_utf8(sample) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    };
+    if valid_utf8 && !has_nul { return false; }
+    let encoding = crate::encoding::detect(sample);
+    if encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE { return false; }
+    let control = sample.iter().filter(|b| **b < 0x20 && !matches!(**b, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b)).count();
+    has_nul || control * 10 > sample.len()
+}
+
+/// Name to pick a highlighting syntax by: the extension, a known file name's
+/// syntax, or for extension-less scripts the shebang's interpreter.
+pub(crate) fn syntax_hint(path: &Path, first_line: &str) -> String {
+    let name = path.file_name().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+    if let Some((_, syntax)) = KNOWN_NAMES.iter().find(|(known, _)| *known == name) {
+        return syntax.to_string();
+    }
+    if let Some(ext) = path.extension().and_then(|s| s.to_str()) {
+        return ext.to_lowercase();
+    }
+    shebang_syntax(first_line).unwrap_or("").to_string()
+}
+
+// `#!/usr/bin/env -S python3 -u` → "py"
+fn shebang_syntax(line: &str) -> Option<&'static str> {
+    let mut words = line.strip_prefix("#!")?.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|w| !w.starts_with('-'))?;
+    }
+    let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    INTERPRETERS.iter().find(|(name, _)| *name == program).map(|(_, syntax)| *syntax)
+}
+
+/// The next (or previous) file of `kind` next to `path`, wrapping around.
+/// Siblings are classified one at a time, starting next to the current file.
+pub(crate) fn neighbor(path: &Path, kind: FileKind, forward: bool) -> Option<PathBuf> {
+    let parent = path.parent()?;
+    let mut files: Vec<PathBuf> = std::fs::read_dir(parent).ok()?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .collect();
+    files.sort();
+    let current_name = path.file_name()?;
+    let idx = files.iter().position(|p| p.file_name() == Some(current_name))?;
+    let n = files.len();
+    (1..n)
+        .map(|step| if forward { (idx + step) % n } else { (idx + n - step) % n })
+        .map(|i| &files[i])
+        .find(|p| classify(p) == kind)
+        .cloned()
+}
+
+/// Native file picker with the viewer's filters. "All Files" comes first
+/// since detection does not need a known extension.
+pub(crate) fn file_dialog() -> rfd::FileDialog {
+    let supported: Vec<&str> = TEXT_EXTENSIONS.iter().chain(IMAGE_EXTENSIONS).copied().collect();
+    rfd::FileDialog::new()
+        .add_filter("All Files", &["*"])
+        .add_filter("All Supported", &supported)
+        .add_filter("Images", IMAGE_EXTENSIONS)
+        .add_filter("Text/Source", TEXT_EXTENSIONS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(name: &str, head: &[u8]) -> FileKind {
+        classify_bytes(Path::new(name), head)
+    }
+
+    #[test]
+    fn latin1_text_versus_binary() {
+        assert_eq!(kind("notes", b"Caf\xe9 cr\xe8me, na\xefve fa\xe7ade\r\n\xc0 bient\xf4t\r\n"), FileKind::Text);
+        assert_eq!(kind("notes.txt", "Grüße\n".as_bytes()), FileKind::Text);
+        assert_eq!(kind("data", b"\x01\x02\x03\x04\x05\x06\x07\x08\xe9\xff\x10\x11"), FileKind::Binary);
+        assert_eq!(kind("data.txt", b"text\0with a NUL"), FileKind::Binary);
+        assert_eq!(kind("a.out", b"\x7fELF\x02\x01\x01"), FileKind::Binary);
+        // BOM-less UTF-16 is full of NULs but still text
+        let utf16: Vec<u8> = "hello, world\n".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        assert!(!looks_binary(&utf16));
+        assert_eq!(kind("empty.png", b""), FileKind::Image);
+        assert_eq!(kind("empty", b""), FileKind::Text);
+    }
+
+    #[test]
+    fn pnm_header_versus_text() {
+        assert_eq!(kind("image", b"P3\n2 2\n255\n255 0 0 0 255 0\n"), FileKind::Image);
+        assert_eq!(kind("image", b"P6 # comment\n2 2 255\n"), FileKind::Image);
+        assert_eq!(kind("notes", b"P3 is the third point\n"), FileKind::Text);
+        assert_eq!(kind("notes", b"P1-P7 formats\n"), FileKind::Text);
+    }
+
+    #[test]
+    fn svg_after_prolog() {
+        assert!(is_svg_data(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"));
+        assert!(is_svg_data(b"\xef\xbb\xbf<?xml version=\"1.0\"?>\n<!-- made by hand -->\n<!DOCTYPE svg>\n<svg>"));
+        assert!(!is_svg_data(b"<?xml version=\"1.0\"?>\n<svgfont/>"));
+        assert!(!is_svg_data(b"<?xml version=\"1.0\"?>\n<html><svg/></html>"));
+        assert!(!is_svg_data(b"<!-- unterminated <svg>"));
+        assert_eq!(kind("drawing.xml", b"<?xml version=\"1.0\"?><!-- x --><svg viewBox=\"0 0 1 1\"/>"), FileKind::Image);
+        assert_eq!(kind("page.xml", b"<?xml version=\"1.0\"?><page/>"), FileKind::Text);
+    }
+
+    #[test]
+    fn shebangs() {
+        assert_eq!(shebang_syntax("#!/usr/bin/env -S python3 -u"), Some("py"));
+        assert_eq!(shebang_syntax("#!/usr/bin/env python3.12"), Some("py"));
+        assert_eq!(shebang_syntax("#! /bin/bash -e"), Some("sh"));
+        assert_eq!(shebang_syntax("#!/usr/local/bin/node"), Some("js"));
+        assert_eq!(shebang_syntax("#!/usr/bin/env"), None);
+        assert_eq!(shebang_syntax("# not a shebang"), None);
+        assert_eq!(syntax_hint(Path::new("build"), "#!/usr/bin/env -S python3"), "py");
+        assert_eq!(syntax_hint(Path::new("tool.rb"), "#!/usr/bin/env python3"), "rb");
+        assert_eq!(syntax_hint(Path::new("Makefile"), ""), "makefile");
+        assert_eq!(kind("run", b"#!/bin/sh\n\x01\x02\x03"), FileKind::Text);
+    }
+}
@@ -0,0 +1,158 @@
+// Global Search over a folder on disk. A background thread walks it with the
+// `ignore` crate (the walker behind ripgrep), so .gitignore and hidden-file
+// rules apply as they do on the command line, and sends each file's matches
+// over a channel as soon as that file is searched. The walk is over when the
+// channel disconnects.
+
+use crate::app::GlobalSearchResult;
+use crate::filetype::FileKind;
+use crate::search::Matcher;
+use eframe::egui;
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::{WalkBuilder, WalkState};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+/// The walk stops once this many matches have been found.
+pub(crate) const MAX_RESULTS: usize = 50_000;
+
+/// Where a folder search looks and what it skips (persisted).
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct FolderOptions {
+    pub(crate) root: Option<PathBuf>,
+    /// Comma-separated globs; when any are given only matching files are searched.
+    pub(crate) include: String,
+    /// Comma-separated globs of files and folders to leave out.
+    pub(crate) exclude: String,
+    pub(crate) respect_gitignore: bool,
+    pub(crate) include_hidden: bool,
+}
+
+impl Default for FolderOptions {
+    fn default() -> Self {
+        Self {
+            root: None,
+            include: String::new(),
+            exclude: String::new(),
+            respect_gitignore: true,
+            include_hidden: false,
+        }
+    }
+}
+
+/// A folder search in progress (or finished, for its totals).
+pub(crate) struct FolderSearch {
+    rx: Receiver<Vec<GlobalSearchResult>>,
+    cancel: Arc<AtomicBool>,
+    scanned: Arc<AtomicUsize>,
+    found: usize,
+    pub(crate) done: bool,
+    /// Stopped early at MAX_RESULTS.
+    pub(crate) truncated: bool,
+}
+
+impl FolderSearch {
+    pub(crate) fn start(ctx: &egui::Context, options: &FolderOptions, query: Matcher) -> Result<Self, String> {
+        let root = options.root.clone().ok_or("Choose a folder to search")?;
+        if !root.is_dir() { return Err(format!("Not a folder: {}", root.display())); }
+        let walker = WalkBuilder::new(&root)
+            .hidden(!options.include_hidden)
+            .git_ignore(options.respect_gitignore)
+            .git_global(options.respect_gitignore)
+            .git_exclude(options.respect_gitignore)
+            .ignore(options.respect_gitignore)
+            // Honor a .gitignore even in a folder that is not a checkout
+            .require_git(false)
+            .overrides(globs(&root, options)?)
+            .build_parallel();
+
+        let (tx, rx) = channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let scanned = Arc::new(AtomicUsize::new(0));
+        let (stop, count, ctx) = (cancel.clone(), scanned.clone(), ctx.clone());
+        thread::spawn(move || {
+            walker.run(|| {
+                let (tx, stop, count, query, ctx) = (tx.clone(), stop.clone(), count.clone(), query.clone(), ctx.clone());
+                Box::new(move |entry| {
+                    if stop.load(Ordering::Relaxed) { return WalkState::Quit; }
+                    let Ok(entry) = entry else { return WalkState::Continue; };
+                    if !entry.file_type().is_some_and(|t| t.is_file()) { return WalkState::Continue; }
+                    count.fetch_add(1, Ordering::Relaxed);
+                    let found = search_file(entry.path(), &query);
+                    if !found.is_empty() {
+                        if tx.send(found).is_err() { return WalkState::Quit; }
+                        ctx.request_repaint();
+                    }
+                    WalkState::Continue
+                })
+            });
+            // Disconnect before the last repaint so it sees the walk as done
+            drop(tx);
+            ctx.request_repaint();
+        });
+        Ok(Self { rx, cancel, scanned, found: 0, done: false, truncated: false })
+    }
+
+    /// Matches sent since the last call.
+    pub(crate) fn poll(&mut self) -> Vec<GlobalSearchResult> {
+        let mut results = Vec::new();
+        while !self.done {
+            match self.rx.try_recv() {
+                Ok(batch) => results.extend(batch),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => self.done = true,
+            }
+        }
+        if self.found + results.len() >= MAX_RESULTS {
+            results.truncate(MAX_RESULTS - self.found);
+            if !self.done {
+                self.truncated = true;
+                self.stop();
+            }
+        }
+        self.found += results.len();
+        results
+    }
+
+    pub(crate) fn files_scanned(&self) -> usize {
+        self.scanned.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn stop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+        self.done = true;
+    }
+}
+
+impl Drop for FolderSearch {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+// Include globs as a whitelist, exclude globs as "!" entries
+fn globs(root: &Path, options: &FolderOptions) -> Result<Override, String> {
+    let split = |list: &str| list.split(',').map(str::trim).filter(|g| !g.is_empty()).map(str::to_string).collect::<Vec<_>>();
+    let mut builder = OverrideBuilder::new(root);
+    let include = split(&options.include);
+    let exclude = split(&options.exclude).into_iter().map(|g| format!("!{}", g));
+    for glob in include.into_iter().chain(exclude) {
+        builder.add(&glob).map_err(|e| format!("Invalid glob: {}", e))?;
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+// Matches in one file; binary, image and oversized (paged) files are skipped
+fn search_file(path: &Path, query: &Matcher) -> Vec<GlobalSearchResult> {
+    if crate::app::needs_paging(path) { return Vec::new(); }
+    let is_text = crate::filetype::read_head(path).is_ok_and(|head| crate::filetype::classify_bytes(path, &head) == FileKind::Text);
+    if !is_text { return Vec::new(); }
+    match crate::io::load_text(path, None) {
+        Ok(decoded) => query.results(path, None, &decoded.text),
+        Err(_) => Vec::new(),
+    }
+}
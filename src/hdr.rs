@@ -0,0 +1,65 @@
+// High dynamic range images (Radiance HDR, OpenEXR). The float pixels are
+// kept so the exposure can be changed after loading; display is the linear
+// values scaled by 2^exposure, clipped, and encoded as 8-bit sRGB.
+
+use eframe::egui;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+pub(crate) const EXPOSURE_RANGE: std::ops::RangeInclusive<f32> = -10.0..=10.0;
+// Resolution of the linear to sRGB lookup table
+const LUT_SIZE: usize = 1 << 16;
+
+pub(crate) fn is_hdr(bytes: &[u8]) -> bool {
+    matches!(image::guess_format(bytes), Ok(image::ImageFormat::Hdr | image::ImageFormat::OpenExr))
+}
+
+/// Tone map linear float pixels for display.
+pub(crate) fn tone_map(pixels: &image::Rgba32FImage, exposure: f32) -> egui::ColorImage {
+    static SRGB: OnceLock<Vec<u8>> = OnceLock::new();
+    let lut = SRGB.get_or_init(|| {
+        (0..LUT_SIZE)
+            .map(|i| {
+                let v = i as f32 / (LUT_SIZE - 1) as f32;
+                let s = if v <= 0.003_130_8 { 12.92 * v } else { 1.055 * v.powf(1.0 / 2.4) - 0.055 };
+                (s * 255.0).round() as u8
+            })
+            .collect()
+    });
+    let gain = exposure.exp2();
+    let encode = |v: f32| lut[((v * gain).clamp(0.0, 1.0) * (LUT_SIZE - 1) as f32) as usize];
+    let (w, h) = pixels.dimensions();
+    let pixels = pixels.pixels().map(|p| {
+        let [r, g, b, a] = p.0;
+        egui::Color32::from_rgba_unmultiplied(encode(r), encode(g), encode(b), (a.clamp(0.0, 1.0) * 255.0).round() as u8)
+    });
+    egui::ColorImage { size: [w as usize, h as usize], pixels: pixels.collect() }
+}
+
+/// The HDR image on screen with its exposure.
+pub(crate) struct HdrView {
+    pub(crate) path: PathBuf,
+    pixels: image::Rgba32FImage,
+    /// Stops above (or below) the stored values.
+    pub(crate) exposure: f32,
+    // Exposure of the texture on screen
+    shown: f32,
+}
+
+impl HdrView {
+    pub(crate) fn new(path: PathBuf, pixels: image::Rgba32FImage) -> Self {
+        Self { path, pixels, exposure: 0.0, shown: 0.0 }
+    }
+
+    pub(crate) fn image(&self) -> egui::ColorImage {
+        tone_map(&self.pixels, self.exposure)
+    }
+
+    /// The image tone mapped again when the exposure changed since it was
+    /// last shown.
+    pub(crate) fn update(&mut self) -> Option<egui::ColorImage> {
+        if self.exposure == self.shown { return None; }
+        self.shown = self.exposure;
+        Some(self.image())
+    }
+}
@@ -0,0 +1,216 @@
+// Hex dump view for binary and otherwise unsupported files: the bytes (read
+// into memory, or read on demand past MAX_FILE_SIZE_BYTES), the view's
+// selection/search state, and helpers for patterns, offsets and the value
+// inspector. Rendering lives in central.rs like the other content kinds.
+
+use memchr::memmem;
+use std::borrow::Cow;
+use std::fs::File;
+use std::ops::Range;
+use std::path::Path;
+
+pub(crate) const BYTES_PER_ROW: usize = 16;
+// Bytes read at a time when searching a file on disk
+const FIND_CHUNK_BYTES: usize = 8 * 1024 * 1024;
+
+enum Bytes {
+    Owned(Vec<u8>),
+    // Read with positioned reads, as paged text is; `len` is the size at open
+    OnDisk { file: File, len: usize },
+}
+
+pub struct HexDoc {
+    bytes: Bytes,
+}
+
+impl HexDoc {
+    pub(crate) fn new(bytes: Vec<u8>) -> Self {
+        Self { bytes: Bytes::Owned(bytes) }
+    }
+
+    /// Keep `path` open and read only the bytes shown or searched (for very
+    /// large files).
+    pub(crate) fn open(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+        let len = file.metadata().map_err(|e| format!("Failed to open file: {}", e))?.len() as usize;
+        Ok(Self { bytes: Bytes::OnDisk { file, len } })
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match &self.bytes {
+            Bytes::Owned(v) => v.len(),
+            Bytes::OnDisk { len, .. } => *len,
+        }
+    }
+
+    /// The bytes in `range`, cut short at the end of the file (or where a
+    /// file on disk has since been truncated).
+    pub(crate) fn read(&self, range: Range<usize>) -> Cow<'_, [u8]> {
+        let range = range.start.min(self.len())..range.end.min(self.len());
+        match &self.bytes {
+            Bytes::Owned(v) => Cow::Borrowed(&v[range]),
+            Bytes::OnDisk { file, .. } => {
+                let mut buf = vec![0u8; range.len()];
+                let n = crate::io::read_at(file, range.start as u64, &mut buf);
+                buf.truncate(n);
+                Cow::Owned(buf)
+            }
+        }
+    }
+
+    pub(crate) fn row_count(&self) -> usize {
+        self.len().div_ceil(BYTES_PER_ROW)
+    }
+
+    /// Hex digits needed for the largest offset (at least 8).
+    pub(crate) fn offset_digits(&self) -> usize {
+        let len = self.len().max(1) as u64;
+        ((64 - len.leading_zeros() as usize).div_ceil(4)).max(8)
+    }
+}
+
+/// Selection, search and navigation state for the hex view.
+#[derive(Default)]
+pub(crate) struct HexView {
+    // Selected byte range is anchor..=cursor in either order
+    pub(crate) anchor: usize,
+    pub(crate) cursor: usize,
+    pub(crate) has_selection: bool,
+    pub(crate) pattern: String,
+    pub(crate) goto: String,
+    pub(crate) scroll_to: Option<usize>,
+    pub(crate) message: Option<String>,
+}
+
+impl HexView {
+    pub(crate) fn selection(&self) -> Option<(usize, usize)> {
+        self.has_selection.then(|| (self.anchor.min(self.cursor), self.anchor.max(self.cursor)))
+    }
+
+    pub(crate) fn select(&mut self, start: usize, end_inclusive: usize) {
+        self.anchor = start;
+        self.cursor = end_inclusive;
+        self.has_selection = true;
+        self.scroll_to = Some(start);
+    }
+
+    /// Jump to the next (or previous) match of the pattern after (before)
+    /// the selection, wrapping around the file.
+    pub(crate) fn find(&mut self, doc: &HexDoc, forward: bool) {
+        let Some(needle) = parse_pattern(&self.pattern) else {
+            self.message = Some("Enter hex bytes (DE AD BE EF) or text".to_string());
+            return;
+        };
+        let found = if forward {
+            let from = self.selection().map(|(s, _)| s + 1).unwrap_or(0).min(doc.len());
+            find_from(doc, &needle, from).or_else(|| find_from(doc, &needle, 0))
+        } else {
+            let before = self.selection().map(|(s, _)| s).unwrap_or(doc.len());
+            rfind_before(doc, &needle, before).or_else(|| rfind_before(doc, &needle, doc.len()))
+        };
+        match found {
+            Some(at) => {
+                self.select(at, at + needle.len() - 1);
+                self.message = None;
+            }
+            None => self.message = Some("Pattern not found".to_string()),
+        }
+    }
+
+    pub(crate) fn go_to(&mut self, doc: &HexDoc) {
+        match parse_offset(&self.goto) {
+            Some(offset) if offset < doc.len() => {
+                self.select(offset, offset);
+                self.message = None;
+            }
+            Some(_) => self.message = Some("Offset is past the end of the file".to_string()),
+            None => self.message = Some("Enter an offset like 0x1F40 or 8000".to_string()),
+        }
+    }
+}
+
+// First match starting at or after `from`. Chunks overlap by the needle
+// length so a match across a chunk edge is still found.
+fn find_from(doc: &HexDoc, needle: &[u8], from: usize) -> Option<usize> {
+    let finder = memmem::Finder::new(needle);
+    let mut start = from;
+    while start < doc.len() {
+        let end = (start + FIND_CHUNK_BYTES.max(needle.len())).min(doc.len());
+        if let Some(i) = finder.find(&doc.read(start..end)) { return Some(start + i); }
+        if end == doc.len() { break; }
+        start = end + 1 - needle.len();
+    }
+    None
+}
+
+// Last match ending at or before `before`, searching back chunk by chunk
+fn rfind_before(doc: &HexDoc, needle: &[u8], before: usize) -> Option<usize> {
+    let finder = memmem::FinderRev::new(needle);
+    let mut end = before.min(doc.len());
+    while end >= needle.len() {
+        let start = end.saturating_sub(FIND_CHUNK_BYTES.max(needle.len()));
+        if let Some(i) = finder.rfind(doc.read(start..end)) { return Some(start + i); }
+        if start == 0 { break; }
+        end = start + needle.len() - 1;
+    }
+    None
+}
+
+/// Hex byte pairs (spaces allowed, e.g. `de ad be ef`), otherwise the text's
+/// UTF-8 bytes; quotes force text (`"cafe"`). None for an empty pattern.
+pub(crate) fn parse_pattern(input: &str) -> Option<Vec<u8>> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() { return None; }
+    if let Some(text) = trimmed.strip_prefix('"').and_then(|t| t.strip_suffix('"')).filter(|t| !t.is_empty()) {
+        return Some(text.as_bytes().to_vec());
+    }
+    let digits: String = trimmed.chars().filter(|c| !c.is_whitespace()).collect();
+    let is_hex = digits.len().is_multiple_of(2) && digits.chars().all(|c| c.is_ascii_hexdigit());
+    if is_hex {
+        let bytes = (0..digits.len()).step_by(2).map(|i| u8::from_str_radix(&digits[i..i + 2], 16)).collect::<Result<Vec<u8>, _>>();
+        if let Ok(bytes) = bytes { return Some(bytes); }
+    }
+    Some(input.as_bytes().to_vec())
+}
+
+/// `0x`-prefixed (or `h`-suffixed) hex, otherwise decimal.
+pub(crate) fn parse_offset(input: &str) -> Option<usize> {
+    let s = input.trim().replace('_', "");
+    if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).or_else(|| s.strip_suffix('h')) {
+        return usize::from_str_radix(hex, 16).ok();
+    }
+    s.parse().ok()
+}
+
+/// The bytes at the start of the selection read as integers and floats of
+/// each width, in both byte orders. Widths longer than the data are skipped.
+pub(crate) fn interpret(bytes: &[u8]) -> Vec<(&'static str, String, String)> {
+    fn arr<const N: usize>(b: &[u8]) -> Option<[u8; N]> {
+        b.get(..N).and_then(|s| s.try_into().ok())
+    }
+    let mut rows = Vec::new();
+    if let Some(b) = arr::<1>(bytes) {
+        rows.push(("u8", b[0].to_string(), b[0].to_string()));
+        rows.push(("i8", (b[0] as i8).to_string(), (b[0] as i8).to_string()));
+    }
+    if let Some(b) = arr::<2>(bytes) {
+        rows.push(("u16", u16::from_le_bytes(b).to_string(), u16::from_be_bytes(b).to_string()));
+        rows.push(("i16", i16::from_le_bytes(b).to_string(), i16::from_be_bytes(b).to_string()));
+    }
+    if let Some(b) = arr::<4>(bytes) {
+        rows.push(("u32", u32::from_le_bytes(b).to_string(), u32::from_be_bytes(b).to_string()));
+        rows.push(("i32", i32::from_le_bytes(b).to_string(), i32::from_be_bytes(b).to_string()));
+        rows.push(("f32", f32::from_le_bytes(b).to_string(), f32::from_be_bytes(b).to_string()));
+    }
+    if let Some(b) = arr::<8>(bytes) {
+        rows.push(("u64", u64::from_le_bytes(b).to_string(), u64::from_be_bytes(b).to_string()));
+        rows.push(("i64", i64::from_le_bytes(b).to_string(), i64::from_be_bytes(b).to_string()));
+        rows.push(("f64", f64::from_le_bytes(b).to_string(), f64::from_be_bytes(b).to_string()));
+    }
+    rows
+}
+
+/// Printable ASCII as itself, everything else as a dot.
+pub(crate) fn ascii_char(b: u8) -> char {
+    if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' }
+}
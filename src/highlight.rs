@@ -7,9 +7,6 @@ pub struct HighlightContext<'a> {
     pub base_color: Color32,
     pub do_syntax: bool,
     pub depth: &'a mut i32,
-    pub current_idx: usize,
-    pub counter: &'a mut usize,
-    pub query: &'a str,
     pub in_block_comment: &'a mut bool,
     pub theme: CodeTheme,
 }
@@ -23,43 +20,13 @@ fn comment_text_format(font_id: &FontId, theme: &CodeTheme) -> egui::TextFormat
     }
 }
 
-pub(crate) fn append_with_search(
+pub(crate) fn append_colored(
     job: &mut LayoutJob,
     text: &str,
     color: Color32,
-    ctx: &mut HighlightContext,
+    ctx: &HighlightContext,
 ) {
-    if ctx.query.is_empty() {
-        job.append(text, 0.0, egui::TextFormat { font_id: ctx.font_id.clone(), color, ..Default::default() });
-        return;
-    }
-    let lc_query = ctx.query.to_ascii_lowercase();
-    let mut rest = text;
-    loop {
-        let lc_rest = rest.to_ascii_lowercase();
-        if let Some(found_rel) = lc_rest.find(&lc_query) {
-            let prefix = &rest[..found_rel];
-            if !prefix.is_empty() {
-                job.append(prefix, 0.0, egui::TextFormat { font_id: ctx.font_id.clone(), color, ..Default::default() });
-            }
-            let matched = &rest[found_rel..found_rel + lc_query.len()];
-            let mut fmt = egui::TextFormat { font_id: ctx.font_id.clone(), color, ..Default::default() };
-            if *ctx.counter == ctx.current_idx {
-                fmt.background = ctx.theme.search_current();
-            } else {
-                fmt.background = ctx.theme.search_highlight();
-            }
-            job.append(matched, 0.0, fmt);
-            *ctx.counter += 1;
-            rest = &rest[found_rel + lc_query.len()..];
-            if rest.is_empty() { break; }
-        } else {
-            if !rest.is_empty() {
-                job.append(rest, 0.0, egui::TextFormat { font_id: ctx.font_id.clone(), color, ..Default::default() });
-            }
-            break;
-        }
-    }
+    job.append(text, 0.0, egui::TextFormat { font_id: ctx.font_id.clone(), color, ..Default::default() });
 }
 
 pub(crate) fn token_highlight(
@@ -68,7 +35,7 @@ pub(crate) fn token_highlight(
     ctx: &mut HighlightContext,
 ) {
     if !ctx.do_syntax {
-        append_with_search(job, text, ctx.base_color, ctx);
+        append_colored(job, text, ctx.base_color, ctx);
         return;
     }
     let kw_color = ctx.theme.keyword();
@@ -101,7 +68,7 @@ pub(crate) fn token_highlight(
                 } else {
                     (ctx.base_color, false)
                 };
-                append_with_search(job, &buf, color, ctx);
+                append_colored(job, &buf, color, ctx);
                 buf.clear();
             }
             let color = match ch {
@@ -118,7 +85,7 @@ pub(crate) fn token_highlight(
                 _ => None,
             };
             let delim = ch.to_string();
-            append_with_search(job, &delim, color.unwrap_or(ctx.base_color), ctx);
+            append_colored(job, &delim, color.unwrap_or(ctx.base_color), ctx);
         }
     }
     if !buf.is_empty() {
@@ -133,7 +100,7 @@ pub(crate) fn token_highlight(
         } else {
             (ctx.base_color, false)
         };
-        append_with_search(job, &buf, color, ctx);
+        append_colored(job, &buf, color, ctx);
     }
 }
 
@@ -248,7 +215,7 @@ pub(crate) fn append_highlighted(
                     s.push(c2);
                     if c2 == '"' { break; }
                 }
-                append_with_search(job, &s, ctx.theme.string(), ctx);
+                append_colored(job, &s, ctx.theme.string(), ctx);
             } else {
                 buf.push(ch);
             }
@@ -261,3 +228,49 @@ pub(crate) fn append_highlighted(
         token_highlight(job, &buf, ctx);
     }
 }
+
+/// Give the Find matches of the line at `job.text[from..]` (byte ranges
+/// within it, numbered from `first_idx`) a search background, splitting
+/// sections at match edges. Runs after highlighting, so matches line up
+/// with the counter whatever the tokens.
+pub(crate) fn mark_search_matches(
+    job: &mut LayoutJob,
+    from: usize,
+    matches: impl IntoIterator<Item = std::ops::Range<usize>>,
+    first_idx: usize,
+    current_idx: usize,
+    theme: &CodeTheme,
+) {
+    let ranges: Vec<(usize, usize, Color32)> = matches
+        .into_iter()
+        .enumerate()
+        .map(|(k, found)| {
+            let bg = if first_idx + k == current_idx { theme.search_current() } else { theme.search_highlight() };
+            (from + found.start, from + found.end, bg)
+        })
+        .collect();
+    if ranges.is_empty() { return; }
+
+    let old = std::mem::take(&mut job.sections);
+    for section in old {
+        let (section_start, end) = (section.byte_range.start, section.byte_range.end);
+        let mut start = section_start;
+        for &(m_start, m_end, bg) in ranges.iter().filter(|r| r.0 < end && r.1 > section_start) {
+            if m_start > start {
+                let mut before = section.clone();
+                before.byte_range = start..m_start;
+                job.sections.push(before);
+            }
+            let mut hit = section.clone();
+            hit.byte_range = m_start.max(start)..m_end.min(end);
+            hit.format.background = bg;
+            job.sections.push(hit);
+            start = m_end.min(end);
+        }
+        if start < end {
+            let mut rest = section.clone();
+            rest.byte_range = start..end;
+            job.sections.push(rest);
+        }
+    }
+}
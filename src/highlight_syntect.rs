@@ -1,8 +1,7 @@
 use eframe::egui::{self, text::LayoutJob, Color32, FontId};
 use std::sync::OnceLock;
-use syntect::easy::HighlightLines;
-use syntect::highlighting::{Style as SynStyle, Theme, ThemeSet};
-use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::highlighting::{HighlightIterator, HighlightState, Highlighter, Style as SynStyle, Theme, ThemeSet};
+use syntect::parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet};
 
 struct SyntectEngine {
     ss: SyntaxSet,
@@ -33,28 +32,66 @@ fn syntax_for_ext<'a>(ss: &'a SyntaxSet, ext: &str) -> &'a SyntaxReference {
     ss.find_syntax_by_extension(ext).unwrap_or_else(|| ss.find_syntax_plain_text())
 }
 
-pub struct SyntectSession<'a> {
-    high: HighlightLines<'a>,
+/// Parser + highlighter state between lines; cloned into checkpoints so a
+/// session can resume mid-file instead of re-highlighting from line 1.
+#[derive(Clone)]
+pub struct SyntectState {
+    parse: ParseState,
+    highlight: HighlightState,
 }
 
-impl<'a> SyntectSession<'a> {
-    pub fn start(ext: &str, dark_mode: bool) -> SyntectSession<'static> {
+pub struct SyntectSession {
+    highlighter: Highlighter<'static>,
+    state: SyntectState,
+}
+
+impl SyntectSession {
+    pub fn start(ext: &str, dark_mode: bool) -> SyntectSession {
         let eng = engine();
         let syn = syntax_for_ext(&eng.ss, ext);
-        let theme = choose_theme(&eng.ts, dark_mode);
-        SyntectSession { high: HighlightLines::new(syn, theme) }
+        let highlighter = Highlighter::new(choose_theme(&eng.ts, dark_mode));
+        let highlight = HighlightState::new(&highlighter, ScopeStack::new());
+        SyntectSession { highlighter, state: SyntectState { parse: ParseState::new(syn), highlight } }
     }
 
-    pub fn append_line(&mut self, job: &mut LayoutJob, line: &str, font_id: FontId) {
+    /// Start with the syntax named by a code fence info string (`rust`, `py`, …).
+    pub fn start_for_token(token: &str, dark_mode: bool) -> SyntectSession {
+        let eng = engine();
+        let syn = eng.ss.find_syntax_by_token(token).unwrap_or_else(|| eng.ss.find_syntax_plain_text());
+        let highlighter = Highlighter::new(choose_theme(&eng.ts, dark_mode));
+        let highlight = HighlightState::new(&highlighter, ScopeStack::new());
+        SyntectSession { highlighter, state: SyntectState { parse: ParseState::new(syn), highlight } }
+    }
+
+    pub fn resume(state: SyntectState, dark_mode: bool) -> SyntectSession {
+        let eng = engine();
+        SyntectSession { highlighter: Highlighter::new(choose_theme(&eng.ts, dark_mode)), state }
+    }
+
+    pub fn state(&self) -> SyntectState {
+        self.state.clone()
+    }
+
+    fn spans<'b>(&mut self, line: &'b str) -> Option<Vec<(SynStyle, &'b str)>> {
         let eng = engine();
-        match self.high.highlight_line(line, &eng.ss) {
-            Ok(spans) => {
+        let ops = self.state.parse.parse_line(line, &eng.ss).ok()?;
+        Some(HighlightIterator::new(&mut self.state.highlight, &ops, line, &self.highlighter).collect())
+    }
+
+    /// Advance over a line without producing output.
+    pub fn skip_line(&mut self, line: &str) {
+        let _ = self.spans(line);
+    }
+
+    pub fn append_line(&mut self, job: &mut LayoutJob, line: &str, font_id: FontId) {
+        match self.spans(line) {
+            Some(spans) => {
                 for (style, text) in spans {
                     let color = to_egui_color(style);
                     job.append(text, 0.0, egui::TextFormat { font_id: font_id.clone(), color, ..Default::default() });
                 }
             }
-            Err(_) => {
+            None => {
                 // Fallback: append plain text
                 job.append(line, 0.0, egui::TextFormat { font_id, color: Color32::WHITE, ..Default::default() });
             }
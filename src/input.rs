@@ -3,6 +3,7 @@ use eframe::egui;
 
 pub(crate) fn handle_input(app: &mut crate::app::FileViewerApp, ctx: &egui::Context, file_to_load: &mut Option<PathBuf>) -> bool {
     let mut toggle_dark = false;
+    let typing = ctx.wants_keyboard_input();
     ctx.input(|i| {
         if (i.modifiers.command || i.modifiers.alt) && i.key_pressed(egui::Key::O) {
             app.start_open_file_dialog();
@@ -28,92 +29,103 @@ pub(crate) fn handle_input(app: &mut crate::app::FileViewerApp, ctx: &egui::Cont
             crate::settings::save_settings_to_disk(app);
         }
 
-        // Ctrl + Mouse wheel zoom for content
-        if (i.modifiers.command || i.modifiers.alt) && i.raw_scroll_delta.y != 0.0 {
-            let dir = i.raw_scroll_delta.y.signum();
-            match &app.content {
-                Some(crate::app::Content::Text(_)) => {
-                    let factor = if dir > 0.0 { 1.05 } else { 1.0 / 1.05 };
-                    app.text_zoom = (app.text_zoom * factor).clamp(0.6, 3.0);
-                }
-                Some(crate::app::Content::Image(_)) => {
-                    app.image_fit = false;
-                    let factor = if dir > 0.0 { 1.10 } else { 1.0 / 1.10 };
-                    app.image_zoom = (app.image_zoom * factor).clamp(0.1, 6.0);
-                }
-                _ => {}
-            }
+        // Ctrl + Mouse wheel zoom for text; images zoom at the cursor in
+        // central.rs, with or without modifiers
+        if (i.modifiers.command || i.modifiers.alt) && i.raw_scroll_delta.y != 0.0
+            && matches!(app.content, Some(crate::app::Content::Text(_) | crate::app::Content::Hex(_))) {
+            let factor = if i.raw_scroll_delta.y > 0.0 { 1.05 } else { 1.0 / 1.05 };
+            app.text_zoom = (app.text_zoom * factor).clamp(0.6, 3.0);
         }
 
         // Reset and keyboard zoom shortcuts
         if (i.modifiers.command || i.modifiers.alt) && i.key_pressed(egui::Key::Num0) {
             match &app.content {
-                Some(crate::app::Content::Text(_)) => app.text_zoom = 1.0,
+                Some(crate::app::Content::Text(_) | crate::app::Content::Hex(_)) => app.text_zoom = 1.0,
                 Some(crate::app::Content::Image(_)) => { app.image_fit = false; app.image_zoom = 1.0; },
                 _ => {}
             }
         }
         if (i.modifiers.command || i.modifiers.alt) && i.key_pressed(egui::Key::Equals) {
             match &app.content {
-                Some(crate::app::Content::Text(_)) => app.text_zoom = (app.text_zoom * 1.05).clamp(0.6, 3.0),
-                Some(crate::app::Content::Image(_)) => { app.image_fit = false; app.image_zoom = (app.image_zoom * 1.10).clamp(0.1, 6.0); },
+                Some(crate::app::Content::Text(_) | crate::app::Content::Hex(_)) => app.text_zoom = (app.text_zoom * 1.05).clamp(0.6, 3.0),
+                Some(crate::app::Content::Image(_)) => { app.image_fit = false; app.image_zoom = (app.image_zoom * 1.10).clamp(0.1, crate::app::MAX_IMAGE_ZOOM); },
                 _ => {}
             }
         }
         if (i.modifiers.command || i.modifiers.alt) && i.key_pressed(egui::Key::Minus) {
             match &app.content {
-                Some(crate::app::Content::Text(_)) => app.text_zoom = (app.text_zoom / 1.05).clamp(0.6, 3.0),
-                Some(crate::app::Content::Image(_)) => { app.image_fit = false; app.image_zoom = (app.image_zoom / 1.10).clamp(0.1, 6.0); },
+                Some(crate::app::Content::Text(_) | crate::app::Content::Hex(_)) => app.text_zoom = (app.text_zoom / 1.05).clamp(0.6, 3.0),
+                Some(crate::app::Content::Image(_)) => { app.image_fit = false; app.image_zoom = (app.image_zoom / 1.10).clamp(0.1, crate::app::MAX_IMAGE_ZOOM); },
                 _ => {}
             }
         }
 
-        // Navigation with arrow keys for current content type
-        if (i.key_pressed(egui::Key::ArrowRight) || (i.modifiers.alt && i.key_pressed(egui::Key::ArrowRight)))
-            && let Some(cur) = app.current_path.clone() {
-            match app.content {
-                    Some(crate::app::Content::Image(_)) => {
-                        if let Some(next) = crate::io::neighbor_image(&cur, true) { *file_to_load = Some(next); }
-                    }
-                    Some(crate::app::Content::Text(_)) => {
-                        if let Some(next) = crate::io::neighbor_text(&cur, true) { *file_to_load = Some(next); }
-                    }
-                    _ => {}
+        // Diff view: step through changes; arrows scroll instead of switching files
+        if let Some(diff) = app.diff.as_mut() {
+            let layout = app.diff_layout;
+            if i.key_pressed(egui::Key::F7) || (i.modifiers.alt && (i.key_pressed(egui::Key::ArrowDown) || i.key_pressed(egui::Key::ArrowUp))) {
+                let back = i.modifiers.shift || i.key_pressed(egui::Key::ArrowUp);
+                diff.jump_hunk(!back, layout);
             }
+            if i.key_pressed(egui::Key::Escape) { app.diff = None; }
+            return;
         }
-        if (i.key_pressed(egui::Key::ArrowLeft) || (i.modifiers.alt && i.key_pressed(egui::Key::ArrowLeft)))
-            && let Some(cur) = app.current_path.clone() {
-            match app.content {
-                    Some(crate::app::Content::Image(_)) => {
-                        if let Some(prev) = crate::io::neighbor_image(&cur, false) { *file_to_load = Some(prev); }
-                    }
-                    Some(crate::app::Content::Text(_)) => {
-                        if let Some(prev) = crate::io::neighbor_text(&cur, false) { *file_to_load = Some(prev); }
-                    }
-                    _ => {}
+        if app.image_compare.is_some() {
+            use crate::compare::CompareMode;
+            let modes = [(egui::Key::Num1, CompareMode::SideBySide), (egui::Key::Num2, CompareMode::Swipe), (egui::Key::Num3, CompareMode::OnionSkin), (egui::Key::Num4, CompareMode::Difference)];
+            for (key, mode) in modes {
+                if i.key_pressed(key) && !i.modifiers.command { app.image_compare_mode = mode; }
             }
+            if i.key_pressed(egui::Key::Escape) { app.image_compare = None; }
+            return;
         }
-        // Support '<' and '>' typed keys for both images and text
-        for ev in &i.events {
-            if let egui::Event::Text(t) = ev {
-                if t == ">" {
-                    if let Some(cur) = app.current_path.clone() {
-                        match app.content {
-                            Some(crate::app::Content::Image(_)) => { if let Some(next) = crate::io::neighbor_image(&cur, true) { *file_to_load = Some(next); } }
-                            Some(crate::app::Content::Text(_)) => { if let Some(next) = crate::io::neighbor_text(&cur, true) { *file_to_load = Some(next); } }
-                            _ => {}
-                        }
-                    }
-                } else if t == "<"
-                    && let Some(cur) = app.current_path.clone() {
-                    match app.content {
-                            Some(crate::app::Content::Image(_)) => { if let Some(prev) = crate::io::neighbor_image(&cur, false) { *file_to_load = Some(prev); } }
-                            Some(crate::app::Content::Text(_)) => { if let Some(prev) = crate::io::neighbor_text(&cur, false) { *file_to_load = Some(prev); } }
-                            _ => {}
+
+        // Animated images: Space plays/pauses, ',' and '.' step frames
+        if !typing && let Some(animation) = app.shown_animation() {
+            if i.key_pressed(egui::Key::Space) { animation.toggle_playing(); }
+            for ev in &i.events {
+                if let egui::Event::Text(t) = ev && (t == "," || t == ".") {
+                    animation.step(t == ".");
+                }
+            }
+        }
+
+        // Image orientation: R/Shift+R rotate, H and V flip
+        if matches!(app.content, Some(crate::app::Content::Image(_))) && app.image_compare.is_none() && !typing {
+            for ev in &i.events {
+                if let egui::Event::Text(t) = ev {
+                    match t.as_str() {
+                        "r" => app.image_view.rotate(true),
+                        "R" => app.image_view.rotate(false),
+                        "h" | "H" => app.image_view.flip_horizontal(),
+                        "v" | "V" => app.image_view.flip_vertical(),
+                        _ => {}
                     }
                 }
             }
         }
+
+        // Navigation with arrow keys to sibling files of the same kind
+        let kind = app.content.as_ref().map(|c| c.kind());
+        if (i.key_pressed(egui::Key::ArrowRight) || (i.modifiers.alt && i.key_pressed(egui::Key::ArrowRight)))
+            && let (Some(cur), Some(kind)) = (app.current_path.clone(), kind)
+            && let Some(next) = crate::filetype::neighbor(&cur, kind, true) {
+            *file_to_load = Some(next);
+        }
+        if (i.key_pressed(egui::Key::ArrowLeft) || (i.modifiers.alt && i.key_pressed(egui::Key::ArrowLeft)))
+            && let (Some(cur), Some(kind)) = (app.current_path.clone(), kind)
+            && let Some(prev) = crate::filetype::neighbor(&cur, kind, false) {
+            *file_to_load = Some(prev);
+        }
+        // Support '<' and '>' typed keys for every content kind
+        for ev in &i.events {
+            if let egui::Event::Text(t) = ev
+                && (t == ">" || t == "<")
+                && let (Some(cur), Some(kind)) = (app.current_path.clone(), kind)
+                && let Some(sibling) = crate::filetype::neighbor(&cur, kind, t == ">") {
+                *file_to_load = Some(sibling);
+            }
+        }
     });
     toggle_dark
 }
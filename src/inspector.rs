@@ -0,0 +1,96 @@
+// Pixel inspector for the image viewer: the pixel under the pointer with its
+// color in a loupe next to the cursor, copied on click. Reads the CPU copy of
+// the image kept next to its texture. Rendering lives in central.rs.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Pixels on each side of the inspected one in the loupe.
+pub(crate) const LOUPE_RADIUS: usize = 7;
+/// Size of one pixel in the loupe, in points.
+pub(crate) const LOUPE_CELL: f32 = 8.0;
+/// Zoom from which a grid is drawn between image pixels.
+pub(crate) const PIXEL_GRID_MIN_ZOOM: f32 = 8.0;
+// How long "Copied" stays in the loupe after a click
+const COPIED_NOTICE: Duration = Duration::from_millis(1200);
+
+/// How a picked color is written to the clipboard.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ColorFormat {
+    #[default]
+    Hex,
+    Rgba,
+    Hsl,
+}
+
+impl ColorFormat {
+    pub(crate) const ALL: [ColorFormat; 3] = [ColorFormat::Hex, ColorFormat::Rgba, ColorFormat::Hsl];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            ColorFormat::Hex => "Hex",
+            ColorFormat::Rgba => "RGBA",
+            ColorFormat::Hsl => "HSL",
+        }
+    }
+
+    pub(crate) fn format(self, color: egui::Color32) -> String {
+        let [r, g, b, a] = color.to_srgba_unmultiplied();
+        match self {
+            ColorFormat::Hex if a == 255 => format!("#{:02X}{:02X}{:02X}", r, g, b),
+            ColorFormat::Hex => format!("#{:02X}{:02X}{:02X}{:02X}", r, g, b, a),
+            ColorFormat::Rgba => format!("rgba({}, {}, {}, {:.2})", r, g, b, a as f32 / 255.0),
+            ColorFormat::Hsl => {
+                let (h, s, l) = hsl(color);
+                if a == 255 {
+                    format!("hsl({:.0}, {:.0}%, {:.0}%)", h, s, l)
+                } else {
+                    format!("hsla({:.0}, {:.0}%, {:.0}%, {:.2})", h, s, l, a as f32 / 255.0)
+                }
+            }
+        }
+    }
+}
+
+/// Hue in degrees, saturation and lightness in percent.
+pub(crate) fn hsl(color: egui::Color32) -> (f32, f32, f32) {
+    let [r, g, b, _] = color.to_srgba_unmultiplied().map(|c| c as f32 / 255.0);
+    let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+    let l = (max + min) / 2.0;
+    let d = max - min;
+    if d == 0.0 { return (0.0, 0.0, l * 100.0); }
+    let s = d / (1.0 - (2.0 * l - 1.0).abs());
+    let h = if max == r {
+        ((g - b) / d).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / d + 2.0
+    } else {
+        (r - g) / d + 4.0
+    };
+    (h * 60.0, s * 100.0, l * 100.0)
+}
+
+pub(crate) fn pixel_at(image: &egui::ColorImage, x: usize, y: usize) -> Option<egui::Color32> {
+    (x < image.size[0] && y < image.size[1]).then(|| image.pixels[y * image.size[0] + x])
+}
+
+#[derive(Default)]
+pub(crate) struct InspectorState {
+    /// Image pixel under the pointer and its color, for the status bar.
+    pub(crate) hovered: Option<([usize; 2], egui::Color32)>,
+    copied: Option<(String, Instant)>,
+}
+
+impl InspectorState {
+    pub(crate) fn copy(&mut self, ctx: &egui::Context, text: String) {
+        ctx.copy_text(text.clone());
+        self.copied = Some((text, Instant::now()));
+        ctx.request_repaint_after(COPIED_NOTICE);
+    }
+
+    /// The last copied value while its notice is still showing.
+    pub(crate) fn copied(&self) -> Option<&str> {
+        self.copied.as_ref().filter(|(_, at)| at.elapsed() < COPIED_NOTICE).map(|(text, _)| text.as_str())
+    }
+}
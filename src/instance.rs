@@ -0,0 +1,173 @@
+// Single-instance mode: the first gfv owns a Unix domain socket; later launches
+// connect to it, forward their paths (with optional line targets) and exit.
+//
+// Wire format: one request per line, `LINE \t COL \t PATH` (LINE/COL may be
+// empty), terminated by EOF. The server answers "OK\n" once it has queued them.
+
+use crate::cli::OpenRequest;
+use eframe::egui;
+use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+
+#[cfg(unix)]
+mod imp {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::channel;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    const ACK_TIMEOUT: Duration = Duration::from_secs(2);
+    const ACCEPT_POLL: Duration = Duration::from_millis(200);
+
+    fn socket_path() -> Option<PathBuf> {
+        if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR").filter(|d| !d.is_empty()) {
+            return Some(PathBuf::from(dir).join("gfv.sock"));
+        }
+        let user = std::env::var("USER").unwrap_or_else(|_| "user".to_string());
+        Some(std::env::temp_dir().join(format!("gfv-{}.sock", user)))
+    }
+
+    // Forwarded paths must survive the receiver having a different working directory
+    fn absolute(path: &std::path::Path) -> PathBuf {
+        std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+    }
+
+    /// Hands `files` to an already running instance. Returns false if there is
+    /// none (or it did not acknowledge), in which case the caller starts normally.
+    pub(crate) fn forward_to_running(files: &[OpenRequest]) -> bool {
+        let Some(path) = socket_path() else { return false; };
+        let mut stream = match UnixStream::connect(&path) {
+            Ok(s) => s,
+            Err(_) => return false,
+        };
+        let _ = stream.set_read_timeout(Some(ACK_TIMEOUT));
+        let _ = stream.set_write_timeout(Some(ACK_TIMEOUT));
+
+        let mut msg: Vec<u8> = Vec::new();
+        for req in files {
+            let num = |n: Option<usize>| n.map(|v| v.to_string()).unwrap_or_default();
+            msg.extend_from_slice(format!("{}\t{}\t", num(req.line), num(req.column)).as_bytes());
+            msg.extend_from_slice(absolute(&req.path).as_os_str().as_bytes());
+            msg.push(b'\n');
+        }
+        if stream.write_all(&msg).is_err() || stream.shutdown(std::net::Shutdown::Write).is_err() {
+            return false;
+        }
+        let mut ack = String::new();
+        stream.read_to_string(&mut ack).is_ok() && ack.trim() == "OK"
+    }
+
+    fn parse_request(line: &[u8]) -> Option<OpenRequest> {
+        let mut parts = line.splitn(3, |b| *b == b'\t');
+        let num = |p: Option<&[u8]>| p.and_then(|b| std::str::from_utf8(b).ok()).and_then(|s| s.parse::<usize>().ok());
+        let line_no = num(parts.next());
+        let column = num(parts.next());
+        let path = parts.next().filter(|p| !p.is_empty())?;
+        Some(OpenRequest { path: PathBuf::from(std::ffi::OsString::from_vec(path.to_vec())), line: line_no, column })
+    }
+
+    fn read_requests(stream: &UnixStream) -> Option<Vec<OpenRequest>> {
+        let _ = stream.set_nonblocking(false);
+        let _ = stream.set_read_timeout(Some(ACK_TIMEOUT));
+        let mut reader = BufReader::new(stream);
+        let mut files = Vec::new();
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf) {
+                Ok(0) => break,
+                Ok(_) => {
+                    if buf.last() == Some(&b'\n') { buf.pop(); }
+                    if let Some(req) = parse_request(&buf) { files.push(req); }
+                }
+                Err(_) => return None,
+            }
+        }
+        Some(files)
+    }
+
+    /// Owns the listening socket while single-instance mode is on. Dropping it
+    /// stops the accept thread and removes the socket file.
+    pub(crate) struct InstanceServer {
+        path: PathBuf,
+        stop: Arc<AtomicBool>,
+        rx: Receiver<Vec<OpenRequest>>,
+    }
+
+    impl InstanceServer {
+        pub(crate) fn bind(ctx: egui::Context) -> Option<Self> {
+            let path = socket_path()?;
+            if path.exists() {
+                // A live owner answers; a stale file from a crashed run does not
+                if UnixStream::connect(&path).is_ok() { return None; }
+                let _ = std::fs::remove_file(&path);
+            }
+            let listener = UnixListener::bind(&path).ok()?;
+            let _ = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600));
+            listener.set_nonblocking(true).ok()?;
+
+            let (tx, rx) = channel::<Vec<OpenRequest>>();
+            let stop = Arc::new(AtomicBool::new(false));
+            let stop_thread = stop.clone();
+            thread::spawn(move || {
+                while !stop_thread.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        Ok((mut stream, _)) => {
+                            if let Some(files) = read_requests(&stream) {
+                                if tx.send(files).is_err() { break; }
+                                ctx.request_repaint();
+                                let _ = stream.write_all(b"OK\n");
+                            }
+                        }
+                        // Non-blocking accept so the stop flag is noticed promptly
+                        Err(_) => thread::sleep(ACCEPT_POLL),
+                    }
+                }
+            });
+            Some(Self { path, stop, rx })
+        }
+
+        /// Drain batches forwarded by other launches since the last frame.
+        pub(crate) fn poll(&self) -> Vec<Vec<OpenRequest>> {
+            self.rx.try_iter().collect()
+        }
+    }
+
+    impl Drop for InstanceServer {
+        fn drop(&mut self) {
+            self.stop.store(true, Ordering::Relaxed);
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use super::*;
+
+    pub(crate) fn forward_to_running(_files: &[OpenRequest]) -> bool {
+        false
+    }
+
+    pub(crate) struct InstanceServer {
+        _rx: Option<Receiver<Vec<OpenRequest>>>,
+    }
+
+    impl InstanceServer {
+        pub(crate) fn bind(_ctx: egui::Context) -> Option<Self> {
+            None
+        }
+
+        pub(crate) fn poll(&self) -> Vec<Vec<OpenRequest>> {
+            Vec::new()
+        }
+    }
+}
+
+pub(crate) use imp::{forward_to_running, InstanceServer};
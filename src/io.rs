@@ -1,100 +1,110 @@
-use eframe::egui::ColorImage;
-use image::GenericImageView;
-use std::fs;
-use std::path::{Path, PathBuf};
-
-const MAX_IMAGE_TEXTURE_BYTES: usize = 128 * 1024 * 1024; // ~128 MB RGBA texture limit
-
-pub(crate) fn is_supported_image(path: &Path) -> bool {
-    let ext = path
-        .extension()
-        .and_then(|s| s.to_str())
-        .unwrap_or("")
-        .to_lowercase();
-    matches!(ext.as_str(), "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp")
-}
-
-pub(crate) fn load_text(path: &Path) -> Result<(String, bool, usize), String> {
-    let bytes = fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
-    let text = String::from_utf8_lossy(&bytes).into_owned();
-    let lossy = text.contains('\u{FFFD}');
-    let lines = text.lines().count();
-    Ok((text, lossy, lines))
-}
-
-pub(crate) fn load_image(path: &Path) -> Result<ColorImage, String> {
-    // Pre-check dimensions to estimate texture memory before decoding
-    if let Ok((w, h)) = image::image_dimensions(path) {
-        let est_bytes: usize = (w as usize)
-            .saturating_mul(h as usize)
-            .saturating_mul(4);
-        if est_bytes > MAX_IMAGE_TEXTURE_BYTES {
-            return Err(format!(
-                "Image too large: {}x{} (~{:.1} MB RGBA). Limit ~{:.0} MB",
-                w,
-                h,
-                est_bytes as f64 / (1024.0 * 1024.0),
-                MAX_IMAGE_TEXTURE_BYTES as f64 / (1024.0 * 1024.0)
-            ));
-        }
-    }
-
-    let img = image::open(path).map_err(|e| format!("Failed to open image: {}", e))?;
-    let (width, height) = img.dimensions();
-    let rgba = img.to_rgba8();
-    let pixels = rgba.into_flat_samples();
-    Ok(ColorImage::from_rgba_unmultiplied([
-        width as _,
-        height as _,
-    ], pixels.as_slice()))
+use eframe::egui::{self, ColorImage};
+use image::{GenericImageView, ImageDecoder};
+use std::fs::{self, File};
+use std::path::Path;
+
+pub(crate) const MAX_IMAGE_TEXTURE_BYTES: usize = 128 * 1024 * 1024; // ~128 MB RGBA texture limit
+// Sharp pixels when zoomed in, smooth when zoomed out
+pub(crate) const IMAGE_TEXTURE_OPTIONS: egui::TextureOptions = egui::TextureOptions {
+    magnification: egui::TextureFilter::Nearest,
+    ..egui::TextureOptions::LINEAR
+};
+
+pub(crate) fn load_text(path: &Path, encoding: Option<&'static encoding_rs::Encoding>) -> Result<crate::encoding::DecodedText, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    Ok(crate::encoding::decode(&bytes, encoding))
+}
+
+/// Fill `buf` from `offset` without moving a file cursor; fewer bytes at the
+/// end of the file, or none once it has been truncated below `offset`.
+/// Unlike a memory map, a file shrinking meanwhile cannot fault.
+pub(crate) fn read_at(file: &File, offset: u64, buf: &mut [u8]) -> usize {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match pread(file, &mut buf[filled..], offset + filled as u64) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(_) => break,
+        }
+    }
+    filled
+}
+
+#[cfg(unix)]
+fn pread(file: &File, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+    std::os::unix::fs::FileExt::read_at(file, buf, offset)
 }
 
-pub(crate) fn is_supported_text(path: &Path) -> bool {
-    let ext = path
-        .extension()
-        .and_then(|s| s.to_str())
-        .unwrap_or("")
-        .to_lowercase();
-    matches!(
-        ext.as_str(),
-        "txt" | "rs" | "py" | "toml" | "md" | "json" | "js" | "html" | "css"
-    )
+#[cfg(windows)]
+fn pread(file: &File, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+    std::os::windows::fs::FileExt::seek_read(file, buf, offset)
 }
 
-pub(crate) fn neighbor_image(path: &Path, forward: bool) -> Option<PathBuf> {
-    let parent = path.parent()?;
-    let mut images: Vec<PathBuf> = std::fs::read_dir(parent).ok()?
-        .filter_map(|e| e.ok().map(|e| e.path()))
-        .filter(|p| p.is_file() && is_supported_image(p))
-        .collect();
-    if images.is_empty() { return None; }
-    images.sort();
-    let current_name = path.file_name()?;
-    let idx = images.iter().position(|p| p.file_name() == Some(current_name))?;
-    if images.len() <= 1 { return None; }
-    let next_idx = if forward {
-        (idx + 1) % images.len()
-    } else {
-        (idx + images.len() - 1) % images.len()
-    };
-    images.get(next_idx).cloned()
+// Reader over in-memory image data; the format is sniffed from the data,
+// falling back to the file extension
+pub(crate) fn image_reader<'a>(path: &Path, bytes: &'a [u8]) -> Result<image::ImageReader<std::io::Cursor<&'a [u8]>>, String> {
+    let mut reader = image::ImageReader::new(std::io::Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| format!("Failed to open image: {}", e))?;
+    // Cursors are icons with another type field
+    if reader.format().is_none() && bytes.starts_with(b"\0\0\x02\0") {
+        reader.set_format(image::ImageFormat::Ico);
+    } else if reader.format().is_none() {
+        reader.set_format(image::ImageFormat::from_path(path).map_err(|e| format!("Failed to open image: {}", e))?);
+    }
+    Ok(reader)
 }
 
-pub(crate) fn neighbor_text(path: &Path, forward: bool) -> Option<PathBuf> {
-    let parent = path.parent()?;
-    let mut texts: Vec<PathBuf> = std::fs::read_dir(parent).ok()?
-        .filter_map(|e| e.ok().map(|e| e.path()))
-        .filter(|p| p.is_file() && is_supported_text(p))
-        .collect();
-    if texts.is_empty() { return None; }
-    texts.sort();
-    let current_name = path.file_name()?;
-    let idx = texts.iter().position(|p| p.file_name() == Some(current_name))?;
-    if texts.len() <= 1 { return None; }
-    let next_idx = if forward {
-        (idx + 1) % texts.len()
-    } else {
-        (idx + texts.len() - 1) % texts.len()
-    };
-    texts.get(next_idx).cloned()
+/// Decode at full fidelity, turned upright per the EXIF orientation.
+pub(crate) fn decode_upright(path: &Path, bytes: &[u8]) -> Result<image::DynamicImage, String> {
+    let err = |e: image::ImageError| format!("Failed to open image: {}", e);
+    let mut decoder = image_reader(path, bytes)?.into_decoder().map_err(err)?;
+    // Camera photos are stored sideways with an EXIF orientation; show them upright
+    let orientation = decoder.orientation().unwrap_or(image::metadata::Orientation::NoTransforms);
+    let mut img = image::DynamicImage::from_decoder(decoder).map_err(err)?;
+    img.apply_orientation(orientation);
+    Ok(img)
+}
+
+/// Refuse images whose RGBA texture would exceed MAX_IMAGE_TEXTURE_BYTES,
+/// going by the dimensions in the header.
+pub(crate) fn check_image_size(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    if let Ok((w, h)) = image_reader(path, bytes)?.into_dimensions() {
+        let est_bytes: usize = (w as usize)
+            .saturating_mul(h as usize)
+            .saturating_mul(4);
+        if est_bytes > MAX_IMAGE_TEXTURE_BYTES {
+            return Err(format!(
+                "Image too large: {}x{} (~{:.1} MB RGBA). Limit ~{:.0} MB",
+                w,
+                h,
+                est_bytes as f64 / (1024.0 * 1024.0),
+                MAX_IMAGE_TEXTURE_BYTES as f64 / (1024.0 * 1024.0)
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Decode an image already read into memory. SVGs are rendered at their
+/// own size and HDR images tone mapped at zero exposure.
+pub(crate) fn decode_image(path: &Path, bytes: &[u8]) -> Result<ColorImage, String> {
+    if crate::filetype::is_svg(path, bytes) {
+        return crate::svg::SvgDoc::parse(path, bytes)?.render(1.0);
+    }
+    // Pre-check dimensions to estimate texture memory before decoding
+    check_image_size(path, bytes)?;
+    if crate::hdr::is_hdr(bytes) {
+        return Ok(crate::hdr::tone_map(&decode_upright(path, bytes)?.to_rgba32f(), 0.0));
+    }
+
+    let img = decode_upright(path, bytes)?;
+    let (width, height) = img.dimensions();
+    let rgba = img.to_rgba8();
+    let pixels = rgba.into_flat_samples();
+    Ok(ColorImage::from_rgba_unmultiplied([
+        width as _,
+        height as _,
+    ], pixels.as_slice()))
 }
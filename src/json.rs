@@ -0,0 +1,274 @@
+// JSON tree view: the document (or each JSON Lines record) is parsed with
+// serde_json and flattened into one row per visible node, so only expanded
+// nodes cost anything to draw. Invalid JSON falls back to the text view with
+// the error line marked. Rendering lives in central.rs.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::Path;
+
+// Longest scalar shown in a row; the full value is one "Copy value" away
+const MAX_SUMMARY_CHARS: usize = 200;
+
+/// How a JSON file is shown.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum JsonMode {
+    Text,
+    #[default]
+    Tree,
+}
+
+pub(crate) struct ParseError {
+    /// 0-based line of the error.
+    pub(crate) line: usize,
+    pub(crate) message: String,
+}
+
+pub(crate) struct Row {
+    pub(crate) depth: usize,
+    pub(crate) record: usize,
+    /// JSON pointer within the record, for looking the value up again.
+    pub(crate) pointer: String,
+    /// Display path such as `$.a.b[3]`.
+    pub(crate) path: String,
+    pub(crate) key: String,
+    pub(crate) summary: String,
+    pub(crate) kind: &'static str,
+    pub(crate) len: Option<usize>,
+    pub(crate) expanded: bool,
+}
+
+/// Parsed records, expansion and filter state for the document on screen.
+#[derive(Default)]
+pub(crate) struct JsonTreeState {
+    doc_id: Option<u64>,
+    records: Vec<Value>,
+    // JSON Lines: the line each record came from; empty for plain JSON
+    record_lines: Vec<usize>,
+    pub(crate) error: Option<ParseError>,
+    expanded: HashSet<(usize, String)>,
+    pub(crate) filter: String,
+    pub(crate) selected: Option<usize>,
+    rows: Vec<Row>,
+    // Filter the rows were built for; None forces a rebuild
+    built_for: Option<String>,
+}
+
+impl JsonTreeState {
+    /// Re-parse when the document changed. Returns true if it did.
+    pub(crate) fn sync(&mut self, doc: &crate::document::TextDoc, json_lines: bool) -> bool {
+        if self.doc_id == Some(doc.id()) { return false; }
+        let filter = std::mem::take(&mut self.filter);
+        *self = Self { doc_id: Some(doc.id()), filter, ..Default::default() };
+        if json_lines {
+            for i in 0..doc.line_count() {
+                let line = doc.line(i);
+                if line.trim().is_empty() { continue; }
+                match serde_json::from_str(&line) {
+                    Ok(value) => {
+                        self.records.push(value);
+                        self.record_lines.push(i);
+                    }
+                    Err(e) => {
+                        self.error = Some(ParseError { line: i, message: format!("line {}: {}", i + 1, e) });
+                        break;
+                    }
+                }
+            }
+        } else {
+            let text: String = (0..doc.line_count()).map(|i| doc.line(i) + "\n").collect();
+            match serde_json::from_str(&text) {
+                Ok(value) => {
+                    self.records.push(value);
+                    self.expanded.insert((0, String::new()));
+                }
+                Err(e) => self.error = Some(ParseError { line: e.line().saturating_sub(1), message: e.to_string() }),
+            }
+        }
+        true
+    }
+
+    pub(crate) fn rows(&self) -> &[Row] {
+        &self.rows
+    }
+
+    /// Rebuild the rows if expansion or the filter changed.
+    pub(crate) fn refresh(&mut self) {
+        let filter = self.filter.trim().to_lowercase();
+        if self.built_for.as_deref() != Some(filter.as_str()) {
+            // Keep the same node selected across rebuilds
+            let selected = self.selected.and_then(|i| self.rows.get(i)).map(|r| (r.record, r.pointer.clone()));
+            let mut rows = Vec::new();
+            for (record, value) in self.records.iter().enumerate() {
+                let key = match self.record_lines.get(record) {
+                    Some(line) => format!("Line {}", line + 1),
+                    None => "$".to_string(),
+                };
+                let node = Node { record, value, key, pointer: String::new(), path: "$".to_string(), depth: 0 };
+                self.walk(&mut rows, node, &filter, false);
+            }
+            self.rows = rows;
+            self.built_for = Some(filter);
+            self.selected = selected.and_then(|(record, pointer)| self.rows.iter().position(|r| r.record == record && r.pointer == pointer));
+        }
+    }
+
+    /// Value behind a row, for "Copy value".
+    pub(crate) fn value(&self, row: &Row) -> Option<&Value> {
+        self.records.get(row.record)?.pointer(&row.pointer)
+    }
+
+    pub(crate) fn toggle(&mut self, row: usize) {
+        let Some(row) = self.rows.get(row) else { return; };
+        let key = (row.record, row.pointer.clone());
+        if !self.expanded.remove(&key) { self.expanded.insert(key); }
+        self.built_for = None;
+    }
+
+    pub(crate) fn expand_all(&mut self) {
+        fn visit(expanded: &mut HashSet<(usize, String)>, record: usize, value: &Value, pointer: String) {
+            match value {
+                Value::Object(map) => {
+                    for (k, v) in map { visit(expanded, record, v, format!("{}/{}", pointer, escape_pointer(k))); }
+                }
+                Value::Array(items) => {
+                    for (i, v) in items.iter().enumerate() { visit(expanded, record, v, format!("{}/{}", pointer, i)); }
+                }
+                _ => return,
+            }
+            expanded.insert((record, pointer));
+        }
+        for (record, value) in self.records.iter().enumerate() {
+            visit(&mut self.expanded, record, value, String::new());
+        }
+        self.built_for = None;
+    }
+
+    pub(crate) fn collapse_all(&mut self) {
+        self.expanded.clear();
+        self.built_for = None;
+    }
+
+    // Push the row for `node` and its visible descendants. With a filter, a
+    // node stays if it matches or something below it does (then it is shown
+    // expanded); everything under a match is shown as usual. Returns whether
+    // anything was pushed.
+    fn walk(&self, out: &mut Vec<Row>, node: Node, filter: &str, under_match: bool) -> bool {
+        let is_match = filter.is_empty() || under_match || node_matches(&node, filter);
+        let user_expanded = self.expanded.contains(&(node.record, node.pointer.clone()));
+        let start = out.len();
+        let (summary, kind, len) = describe(node.value);
+        out.push(Row {
+            depth: node.depth,
+            record: node.record,
+            pointer: node.pointer.clone(),
+            path: node.path.clone(),
+            key: node.key.clone(),
+            summary,
+            kind,
+            len,
+            expanded: user_expanded,
+        });
+        let search_below = !is_match;
+        if len.is_none() || !(user_expanded || search_below) {
+            return true_or_truncate(out, start, is_match);
+        }
+        let mut any_child = false;
+        for child in children(&node) {
+            any_child |= self.walk(out, child, filter, is_match && !filter.is_empty());
+        }
+        if search_below && any_child { out[start].expanded = true; }
+        true_or_truncate(out, start, is_match || any_child)
+    }
+}
+
+struct Node<'a> {
+    record: usize,
+    value: &'a Value,
+    key: String,
+    pointer: String,
+    path: String,
+    depth: usize,
+}
+
+fn true_or_truncate(out: &mut Vec<Row>, start: usize, keep: bool) -> bool {
+    if !keep { out.truncate(start); }
+    keep
+}
+
+fn children<'a>(node: &Node<'a>) -> Vec<Node<'a>> {
+    match node.value {
+        Value::Object(map) => map.iter().map(|(k, v)| Node {
+            record: node.record,
+            value: v,
+            key: k.clone(),
+            pointer: format!("{}/{}", node.pointer, escape_pointer(k)),
+            path: child_path(&node.path, k),
+            depth: node.depth + 1,
+        }).collect(),
+        Value::Array(items) => items.iter().enumerate().map(|(i, v)| Node {
+            record: node.record,
+            value: v,
+            key: format!("[{}]", i),
+            pointer: format!("{}/{}", node.pointer, i),
+            path: format!("{}[{}]", node.path, i),
+            depth: node.depth + 1,
+        }).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn node_matches(node: &Node, filter: &str) -> bool {
+    // Record labels ("$", "Line 3") are not keys
+    if node.depth > 0 && node.key.to_lowercase().contains(filter) { return true; }
+    match node.value {
+        Value::String(s) => s.to_lowercase().contains(filter),
+        Value::Number(n) => n.to_string().contains(filter),
+        Value::Bool(b) => b.to_string().contains(filter),
+        Value::Null => "null".contains(filter),
+        _ => false,
+    }
+}
+
+fn describe(value: &Value) -> (String, &'static str, Option<usize>) {
+    match value {
+        Value::Object(map) => ("{…}".to_string(), "object", Some(map.len())),
+        Value::Array(items) => ("[…]".to_string(), "array", Some(items.len())),
+        Value::String(s) => {
+            let quoted = serde_json::to_string(s).unwrap_or_default();
+            let summary = if quoted.chars().count() > MAX_SUMMARY_CHARS {
+                quoted.chars().take(MAX_SUMMARY_CHARS).collect::<String>() + "…"
+            } else {
+                quoted
+            };
+            (summary, "string", None)
+        }
+        Value::Number(n) => (n.to_string(), "number", None),
+        Value::Bool(b) => (b.to_string(), "boolean", None),
+        Value::Null => ("null".to_string(), "null", None),
+    }
+}
+
+// `$.name` for identifier-like keys, `$['odd key']` otherwise
+fn child_path(path: &str, key: &str) -> String {
+    let plain = key.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if plain {
+        format!("{}.{}", path, key)
+    } else {
+        format!("{}['{}']", path, key.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
+
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+pub(crate) fn is_json(path: &Path) -> bool {
+    matches!(crate::filetype::syntax_hint(path, "").as_str(), "json" | "geojson" | "jsonl" | "ndjson")
+}
+
+pub(crate) fn is_json_lines(path: &Path) -> bool {
+    matches!(crate::filetype::syntax_hint(path, "").as_str(), "jsonl" | "ndjson")
+}
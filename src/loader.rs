@@ -0,0 +1,235 @@
+// Background file loading. A small worker pool reads and decodes files off
+// the UI thread and reports progress and results over a channel, the same
+// way the file dialog hands back its pick via `file_open_rx`. Each load has a
+// cancel flag, checked between read chunks; a cancelled load's result is
+// dropped even if decoding had already finished.
+
+use crate::app::TextTab;
+use crate::filetype::FileKind;
+use eframe::egui;
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+const READ_CHUNK_BYTES: usize = 1024 * 1024;
+const MAX_WORKERS: usize = 4;
+
+pub(crate) enum Loaded {
+    Text(TextTab),
+    Image(egui::ColorImage),
+    Animation(crate::animation::Frames),
+    /// Float pixels, tone mapped on screen with an adjustable exposure.
+    Hdr(image::Rgba32FImage),
+    /// The document with its first raster and that raster's scale.
+    Svg(crate::svg::SvgDoc, egui::ColorImage, f32),
+    Hex(crate::hex::HexDoc),
+}
+
+/// What to do with a file once it has loaded.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum LoadPurpose {
+    /// Open as a tab, switching to it if `activate`, then jump to `line` and
+    /// put the caret at `column` (both 0-based); a background tab jumps when
+    /// it is first shown.
+    Open { activate: bool, line: Option<usize>, column: Option<usize> },
+    /// Refresh an open tab (or the image on screen) in place.
+    Reload,
+    /// Read a text file for the diff view without opening it.
+    Compare,
+}
+
+pub(crate) struct PendingLoad {
+    pub(crate) path: PathBuf,
+    pub(crate) purpose: LoadPurpose,
+    pub(crate) progress: f32,
+    id: u64,
+    encoding: Option<&'static encoding_rs::Encoding>,
+    as_text: bool,
+    cancel: Arc<AtomicBool>,
+}
+
+pub(crate) struct FinishedLoad {
+    pub(crate) path: PathBuf,
+    pub(crate) purpose: LoadPurpose,
+    pub(crate) result: Result<Loaded, String>,
+}
+
+struct Job {
+    id: u64,
+    path: PathBuf,
+    encoding: Option<&'static encoding_rs::Encoding>,
+    as_text: bool,
+    cancel: Arc<AtomicBool>,
+}
+
+enum LoadMsg {
+    Progress(u64, f32),
+    Done(u64, Result<Loaded, String>),
+}
+
+pub(crate) struct Loader {
+    jobs: Sender<Job>,
+    rx: Receiver<LoadMsg>,
+    next_id: u64,
+    pub(crate) pending: Vec<PendingLoad>,
+}
+
+impl Loader {
+    pub(crate) fn new(ctx: &egui::Context) -> Self {
+        let (jobs, job_rx) = channel::<Job>();
+        let (tx, rx) = channel::<LoadMsg>();
+        let job_rx = Arc::new(Mutex::new(job_rx));
+        let workers = thread::available_parallelism().map(|n| n.get().min(MAX_WORKERS)).unwrap_or(2);
+        for _ in 0..workers {
+            let job_rx = job_rx.clone();
+            let tx = tx.clone();
+            let ctx = ctx.clone();
+            thread::spawn(move || {
+                loop {
+                    // Hold the lock only while taking a job, not while loading it
+                    let job = match job_rx.lock() {
+                        Ok(rx) => rx.recv(),
+                        Err(_) => return,
+                    };
+                    let Ok(job) = job else { return; };
+                    if job.cancel.load(Ordering::Relaxed) { continue; }
+                    let result = load(&job, &tx, &ctx);
+                    if tx.send(LoadMsg::Done(job.id, result)).is_err() { return; }
+                    ctx.request_repaint();
+                }
+            });
+        }
+        Self { jobs, rx, next_id: 1, pending: Vec::new() }
+    }
+
+    /// Queue `path`, decoding text as `encoding` (None detects it); `as_text`
+    /// shows binary files and SVG as text too. A load already pending for it
+    /// takes over the new purpose and is kept when it decodes the same way;
+    /// otherwise it is cancelled and read again. Reads for the diff view are
+    /// pending apart from opens and reloads.
+    pub(crate) fn start(&mut self, path: PathBuf, mut purpose: LoadPurpose, encoding: Option<&'static encoding_rs::Encoding>, as_text: bool) {
+        let for_diff = purpose == LoadPurpose::Compare;
+        if let Some(idx) = self.pending.iter().position(|p| p.path == path && (p.purpose == LoadPurpose::Compare) == for_diff) {
+            let p = &mut self.pending[idx];
+            // An open stays an open, and an activating one stays activating
+            purpose = match (p.purpose, purpose) {
+                (LoadPurpose::Open { .. }, LoadPurpose::Reload)
+                | (LoadPurpose::Open { activate: true, .. }, LoadPurpose::Open { activate: false, .. }) => p.purpose,
+                _ => purpose,
+            };
+            if p.encoding == encoding && p.as_text == as_text {
+                p.purpose = purpose;
+                return;
+            }
+            p.cancel.store(true, Ordering::Relaxed);
+            self.pending.remove(idx);
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let cancel = Arc::new(AtomicBool::new(false));
+        let job = Job { id, path: path.clone(), encoding, as_text, cancel: cancel.clone() };
+        if self.jobs.send(job).is_ok() {
+            self.pending.push(PendingLoad { path, purpose, progress: 0.0, id, encoding, as_text, cancel });
+        }
+    }
+
+    /// Cancel an open or reload of `path`; reads for the diff view go on.
+    pub(crate) fn cancel(&mut self, path: &PathBuf) {
+        self.pending.retain(|p| {
+            let cancelled = &p.path == path && p.purpose != LoadPurpose::Compare;
+            if cancelled { p.cancel.store(true, Ordering::Relaxed); }
+            !cancelled
+        });
+    }
+
+    /// Apply progress updates and return loads that finished since the last call.
+    pub(crate) fn poll(&mut self) -> Vec<FinishedLoad> {
+        let mut finished = Vec::new();
+        for msg in self.rx.try_iter() {
+            match msg {
+                LoadMsg::Progress(id, progress) => {
+                    if let Some(p) = self.pending.iter_mut().find(|p| p.id == id) {
+                        p.progress = progress;
+                    }
+                }
+                LoadMsg::Done(id, result) => {
+                    // Cancelled loads are no longer pending; their result is dropped
+                    if let Some(idx) = self.pending.iter().position(|p| p.id == id) {
+                        let p = self.pending.remove(idx);
+                        finished.push(FinishedLoad { path: p.path, purpose: p.purpose, result });
+                    }
+                }
+            }
+        }
+        finished
+    }
+}
+
+fn load(job: &Job, tx: &Sender<LoadMsg>, ctx: &egui::Context) -> Result<Loaded, String> {
+    let head = crate::filetype::read_head(&job.path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let kind = match crate::filetype::classify_bytes(&job.path, &head) {
+        // "Open as text", or an encoding picked by hand, means "show it as text anyway"
+        FileKind::Binary if job.as_text || job.encoding.is_some() => FileKind::Text,
+        // ... and SVG is XML, readable as its source
+        FileKind::Image if job.as_text && crate::filetype::is_svg(&job.path, &head) => FileKind::Text,
+        kind => kind,
+    };
+    match kind {
+        FileKind::Image => {
+            // Larger text files are opened paged; images have no such fallback
+            if crate::app::needs_paging(&job.path) {
+                return Err(format!("File is too large (> {:.1}MB)", crate::app::MAX_FILE_SIZE_BYTES as f64 / 1_000_000.0));
+            }
+            let bytes = read_chunked(job, tx, ctx)?;
+            if crate::filetype::is_svg(&job.path, &head) {
+                // First raster at the screen's pixel density, zoom 1
+                let doc = crate::svg::SvgDoc::parse(&job.path, &bytes)?;
+                let scale = ctx.pixels_per_point().min(doc.max_scale(ctx.input(|i| i.max_texture_side)));
+                let image = doc.render(scale)?;
+                return Ok(Loaded::Svg(doc, image, scale));
+            }
+            if crate::hdr::is_hdr(&bytes) {
+                crate::io::check_image_size(&job.path, &bytes)?;
+                return Ok(Loaded::Hdr(crate::io::decode_upright(&job.path, &bytes)?.to_rgba32f()));
+            }
+            if let Some(pages) = crate::pages::decode(&bytes)? {
+                return Ok(Loaded::Animation(pages));
+            }
+            match crate::animation::decode(&bytes)? {
+                Some(frames) => Ok(Loaded::Animation(frames)),
+                None => crate::io::decode_image(&job.path, &bytes).map(Loaded::Image),
+            }
+        }
+        FileKind::Binary if crate::app::needs_paging(&job.path) => crate::hex::HexDoc::open(&job.path).map(Loaded::Hex),
+        FileKind::Binary => Ok(Loaded::Hex(crate::hex::HexDoc::new(read_chunked(job, tx, ctx)?))),
+        FileKind::Text if crate::app::needs_paging(&job.path) => TextTab::load(&job.path, job.encoding, ctx).map(Loaded::Text),
+        FileKind::Text => {
+            let bytes = read_chunked(job, tx, ctx)?;
+            let decoded = crate::encoding::decode(&bytes, job.encoding);
+            Ok(Loaded::Text(TextTab::decoded(job.path.clone(), decoded)))
+        }
+    }
+}
+
+// Read the whole file, reporting progress and stopping early when cancelled
+fn read_chunked(job: &Job, tx: &Sender<LoadMsg>, ctx: &egui::Context) -> Result<Vec<u8>, String> {
+    let mut file = File::open(&job.path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let total = file.metadata().map(|m| m.len() as usize).unwrap_or(0);
+    let mut bytes = Vec::with_capacity(total);
+    let mut chunk = vec![0u8; READ_CHUNK_BYTES];
+    loop {
+        if job.cancel.load(Ordering::Relaxed) { return Err("Cancelled".to_string()); }
+        let n = file.read(&mut chunk).map_err(|e| format!("Failed to read file: {}", e))?;
+        if n == 0 { break; }
+        bytes.extend_from_slice(&chunk[..n]);
+        if total > 0 {
+            let _ = tx.send(LoadMsg::Progress(job.id, (bytes.len() as f32 / total as f32).min(1.0)));
+            ctx.request_repaint();
+        }
+    }
+    Ok(bytes)
+}
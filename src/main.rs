@@ -11,6 +11,30 @@ mod input;
 mod central;
 mod style;
 mod highlight_syntect;
+mod cli;
+mod instance;
+mod watch;
+mod tail;
+mod document;
+mod paged;
+mod loader;
+mod encoding;
+mod hex;
+mod filetype;
+mod markdown;
+mod json;
+mod table;
+mod diff;
+mod compare;
+mod animation;
+mod metadata;
+mod inspector;
+mod transform;
+mod pages;
+mod hdr;
+mod svg;
+mod folder_search;
+mod match_index;
 
 use app::FileViewerApp;
 use eframe::egui;
@@ -99,6 +123,27 @@ fn build_app_icon() -> egui::IconData {
 }
 
 fn main() -> Result<(), eframe::Error> {
+    let cli = match cli::parse_args(std::env::args_os().skip(1)) {
+        Ok(cli::CliAction::Run(args)) => args,
+        Ok(cli::CliAction::Exit(msg)) => {
+            println!("{}", msg.trim_end());
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("gfv: {}", e);
+            std::process::exit(2);
+        }
+    };
+    if let Some(path) = cli.settings.clone() {
+        settings::set_settings_path_override(path);
+    }
+    // Hand the files to an already running window instead of opening another;
+    // --search and --theme only apply to a new one
+    let single_instance = settings::load_settings_from_disk().is_some_and(|s| s.single_instance);
+    if single_instance && !cli.needs_own_window() && instance::forward_to_running(&cli.forwarded_files()) {
+        return Ok(());
+    }
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([1000.0, 700.0])
@@ -112,6 +157,12 @@ fn main() -> Result<(), eframe::Error> {
     eframe::run_native(
         "gfv 2.0.2",
         options,
-        Box::new(|cc| Ok(Box::new(FileViewerApp::new(cc))))
+        Box::new(move |cc| {
+            let mut app = FileViewerApp::new(cc);
+            app.apply_cli(cli, &cc.egui_ctx);
+            app.sync_instance_server(&cc.egui_ctx);
+            app.start_file_watcher(&cc.egui_ctx);
+            Ok(Box::new(app))
+        })
     )
 }
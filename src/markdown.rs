@@ -0,0 +1,264 @@
+// Markdown preview: the document is parsed once (pulldown-cmark) into a small
+// block/inline model that central.rs renders with egui widgets. Links and
+// images are resolved relative to the Markdown file.
+
+use eframe::egui;
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// How a Markdown file is shown.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum MarkdownMode {
+    Source,
+    #[default]
+    Preview,
+    SideBySide,
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct InlineStyle {
+    pub(crate) strong: bool,
+    pub(crate) emphasis: bool,
+    pub(crate) strike: bool,
+    pub(crate) code: bool,
+    pub(crate) link: Option<String>,
+}
+
+pub(crate) enum Inline {
+    Text(String, InlineStyle),
+    Image { url: String, alt: String, link: Option<String> },
+    Break,
+}
+
+pub(crate) enum Block {
+    Heading(usize, Vec<Inline>),
+    Paragraph(Vec<Inline>),
+    Code { lang: String, text: String },
+    List { start: Option<u64>, items: Vec<Vec<Block>> },
+    Quote(Vec<Block>),
+    Table { header: Vec<Vec<Inline>>, rows: Vec<Vec<Vec<Inline>>> },
+    Rule,
+}
+
+/// Parsed blocks and loaded images for the document on screen.
+#[derive(Default)]
+pub(crate) struct PreviewState {
+    doc_id: Option<u64>,
+    pub(crate) blocks: Vec<Block>,
+    // None once loading an image has failed, so it is not retried every frame
+    pub(crate) images: HashMap<PathBuf, Option<egui::TextureHandle>>,
+}
+
+impl PreviewState {
+    /// Re-parse when the document changed (another file, or a reload).
+    pub(crate) fn sync(&mut self, doc: &crate::document::TextDoc) {
+        if self.doc_id == Some(doc.id()) { return; }
+        let text: String = (0..doc.line_count()).map(|i| doc.line(i) + "\n").collect();
+        *self = Self { doc_id: Some(doc.id()), blocks: parse(&text), images: HashMap::new() };
+    }
+}
+
+pub(crate) fn is_markdown(path: &Path) -> bool {
+    matches!(crate::filetype::syntax_hint(path, "").as_str(), "md" | "markdown" | "mdown" | "mkd")
+}
+
+/// Where a link in a Markdown file points.
+pub(crate) enum LinkTarget {
+    /// Web and mail links, opened by the system.
+    External(String),
+    File(PathBuf),
+    /// A `#fragment` within the same document.
+    Anchor,
+}
+
+pub(crate) fn resolve_link(base: &Path, url: &str) -> LinkTarget {
+    if url.starts_with('#') { return LinkTarget::Anchor; }
+    if (url.contains("://") && !url.starts_with("file://")) || url.starts_with("mailto:") {
+        return LinkTarget::External(url.to_string());
+    }
+    let path = url.strip_prefix("file://").unwrap_or(url);
+    let path = path.split(['#', '?']).next().unwrap_or(path);
+    let path = Path::new(&percent_decode(path)).to_path_buf();
+    LinkTarget::File(if path.is_absolute() { path } else { base.join(path) })
+}
+
+/// Texture for an image referenced from the document, loaded on first use.
+pub(crate) fn cached_image(images: &mut HashMap<PathBuf, Option<egui::TextureHandle>>, ctx: &egui::Context, base: &Path, url: &str) -> Option<egui::TextureHandle> {
+    let LinkTarget::File(path) = resolve_link(base, url) else { return None; };
+    images.entry(path.clone()).or_insert_with(|| {
+        let too_large = std::fs::metadata(&path).ok()?.len() > crate::app::MAX_FILE_SIZE_BYTES;
+        if too_large { return None; }
+        let bytes = std::fs::read(&path).ok()?;
+        let image = crate::io::decode_image(&path, &bytes).ok()?;
+        Some(ctx.load_texture(path.to_string_lossy(), image, Default::default()))
+    }).clone()
+}
+
+// %XX escapes in link destinations (spaces are commonly written as %20)
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(b) = s.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(h, 16).ok()) {
+            out.push(b);
+            i += 3;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+pub(crate) fn parse(text: &str) -> Vec<Block> {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS | Options::ENABLE_FOOTNOTES;
+    let mut events = Parser::new_ext(text, options);
+    blocks(&mut events, None)
+}
+
+type Events<'a> = Parser<'a>;
+
+fn blocks(events: &mut Events, end: Option<TagEnd>) -> Vec<Block> {
+    let mut out = Vec::new();
+    // Inline content directly inside a block (tight list items have no paragraph)
+    let mut loose = Vec::new();
+    while let Some(event) = events.next() {
+        match event {
+            Event::End(e) if Some(e) == end => break,
+            Event::Start(tag) if is_block(&tag) => {
+                if !loose.is_empty() { out.push(Block::Paragraph(std::mem::take(&mut loose))); }
+                match tag {
+                    Tag::Paragraph => out.push(Block::Paragraph(inlines(events, TagEnd::Paragraph))),
+                    Tag::Heading { level, .. } => out.push(Block::Heading(level as usize, inlines(events, TagEnd::Heading(level)))),
+                    Tag::CodeBlock(kind) => {
+                        let lang = match kind {
+                            CodeBlockKind::Fenced(info) => info.split([' ', ',']).next().unwrap_or("").to_string(),
+                            CodeBlockKind::Indented => String::new(),
+                        };
+                        out.push(Block::Code { lang, text: raw_text(events, TagEnd::CodeBlock) });
+                    }
+                    Tag::HtmlBlock => out.push(Block::Code { lang: "html".to_string(), text: raw_text(events, TagEnd::HtmlBlock) }),
+                    Tag::List(start) => {
+                        let mut items = Vec::new();
+                        while let Some(event) = events.next() {
+                            match event {
+                                Event::Start(Tag::Item) => items.push(blocks(events, Some(TagEnd::Item))),
+                                Event::End(TagEnd::List(_)) => break,
+                                _ => {}
+                            }
+                        }
+                        out.push(Block::List { start, items });
+                    }
+                    Tag::BlockQuote(kind) => out.push(Block::Quote(blocks(events, Some(TagEnd::BlockQuote(kind))))),
+                    Tag::Table(_) => out.push(table(events)),
+                    // Footnote definitions, definition lists, metadata: shown as plain blocks
+                    other => {
+                        let end = other.to_end();
+                        out.extend(blocks(events, Some(end)));
+                    }
+                }
+            }
+            Event::Rule => {
+                if !loose.is_empty() { out.push(Block::Paragraph(std::mem::take(&mut loose))); }
+                out.push(Block::Rule);
+            }
+            event => push_inline(event, events, &InlineStyle::default(), &mut loose),
+        }
+    }
+    if !loose.is_empty() { out.push(Block::Paragraph(loose)); }
+    out
+}
+
+fn is_block(tag: &Tag) -> bool {
+    !matches!(
+        tag,
+        Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Superscript | Tag::Subscript | Tag::Link { .. } | Tag::Image { .. }
+    )
+}
+
+fn inlines(events: &mut Events, end: TagEnd) -> Vec<Inline> {
+    let mut out = Vec::new();
+    styled(events, end, &InlineStyle::default(), &mut out);
+    out
+}
+
+fn styled(events: &mut Events, end: TagEnd, style: &InlineStyle, out: &mut Vec<Inline>) {
+    while let Some(event) = events.next() {
+        if matches!(&event, Event::End(e) if *e == end) { return; }
+        push_inline(event, events, style, out);
+    }
+}
+
+fn push_inline(event: Event, events: &mut Events, style: &InlineStyle, out: &mut Vec<Inline>) {
+    match event {
+        Event::Text(t) | Event::Html(t) | Event::InlineHtml(t) | Event::InlineMath(t) | Event::DisplayMath(t) => {
+            out.push(Inline::Text(t.into_string(), style.clone()));
+        }
+        Event::Code(t) => out.push(Inline::Text(t.into_string(), InlineStyle { code: true, ..style.clone() })),
+        Event::SoftBreak => out.push(Inline::Text(" ".to_string(), style.clone())),
+        Event::HardBreak => out.push(Inline::Break),
+        Event::TaskListMarker(done) => out.push(Inline::Text(if done { "☑ " } else { "☐ " }.to_string(), style.clone())),
+        Event::FootnoteReference(name) => out.push(Inline::Text(format!("[{}]", name), style.clone())),
+        Event::Start(Tag::Emphasis) => styled(events, TagEnd::Emphasis, &InlineStyle { emphasis: true, ..style.clone() }, out),
+        Event::Start(Tag::Strong) => styled(events, TagEnd::Strong, &InlineStyle { strong: true, ..style.clone() }, out),
+        Event::Start(Tag::Strikethrough) => styled(events, TagEnd::Strikethrough, &InlineStyle { strike: true, ..style.clone() }, out),
+        Event::Start(Tag::Link { dest_url, .. }) => {
+            styled(events, TagEnd::Link, &InlineStyle { link: Some(dest_url.into_string()), ..style.clone() }, out);
+        }
+        Event::Start(Tag::Image { dest_url, .. }) => {
+            let mut alt = Vec::new();
+            styled(events, TagEnd::Image, &InlineStyle::default(), &mut alt);
+            let alt = alt.iter().filter_map(|i| match i { Inline::Text(t, _) => Some(t.as_str()), _ => None }).collect();
+            out.push(Inline::Image { url: dest_url.into_string(), alt, link: style.link.clone() });
+        }
+        Event::Start(other) => {
+            let end = other.to_end();
+            styled(events, end, style, out);
+        }
+        _ => {}
+    }
+}
+
+// Text content up to `end`, as is (code and HTML blocks)
+fn raw_text(events: &mut Events, end: TagEnd) -> String {
+    let mut text = String::new();
+    for event in events.by_ref() {
+        match event {
+            Event::End(e) if e == end => break,
+            Event::Text(t) | Event::Html(t) => text.push_str(&t),
+            _ => {}
+        }
+    }
+    if text.ends_with('\n') { text.pop(); }
+    text
+}
+
+fn table(events: &mut Events) -> Block {
+    let mut header = Vec::new();
+    let mut rows = Vec::new();
+    while let Some(event) = events.next() {
+        match event {
+            Event::Start(Tag::TableHead) => header = cells(events, TagEnd::TableHead),
+            Event::Start(Tag::TableRow) => rows.push(cells(events, TagEnd::TableRow)),
+            Event::End(TagEnd::Table) => break,
+            _ => {}
+        }
+    }
+    Block::Table { header, rows }
+}
+
+fn cells(events: &mut Events, end: TagEnd) -> Vec<Vec<Inline>> {
+    let mut cells = Vec::new();
+    while let Some(event) = events.next() {
+        match event {
+            Event::Start(Tag::TableCell) => cells.push(inlines(events, TagEnd::TableCell)),
+            Event::End(e) if e == end => break,
+            _ => {}
+        }
+    }
+    cells
+}
@@ -0,0 +1,137 @@
+// Find matches of a text tab, indexed once per query on a background thread
+// so typing a query or opening a large file never stalls a frame. The index
+// is shared by the tab and the document on screen, so it survives tab
+// switches; it is redone only when the query or the text changes, and text
+// appended in follow mode is scanned from the old last line on.
+
+use crate::search::Matcher;
+use eframe::egui;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::thread;
+
+// Lines between checks for a newer job or a dropped tab
+const CANCEL_CHECK_LINES: usize = 4096;
+
+/// One match: its line and byte range within that line.
+#[derive(Clone)]
+pub(crate) struct IndexedMatch {
+    pub(crate) line: usize,
+    pub(crate) range: Range<usize>,
+}
+
+/// Every match of one matcher in a text, in order.
+pub(crate) struct MatchIndex {
+    matcher: Matcher,
+    matches: Vec<IndexedMatch>,
+    // Line and byte offset of the last line, which appended text may
+    // continue; a rescan after an append starts there
+    resume: (usize, usize),
+}
+
+impl MatchIndex {
+    pub(crate) fn len(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// Line of the `n`th match.
+    pub(crate) fn line_of(&self, n: usize) -> Option<usize> {
+        self.matches.get(n).map(|m| m.line)
+    }
+
+    /// Index of the first match on `line` and the matches on it.
+    pub(crate) fn on_line(&self, line: usize) -> (usize, &[IndexedMatch]) {
+        let first = self.matches.partition_point(|m| m.line < line);
+        let count = self.matches[first..].partition_point(|m| m.line == line);
+        (first, &self.matches[first..first + count])
+    }
+}
+
+/// The match index of one tab, cloned into the document shown for it.
+#[derive(Clone, Default)]
+pub(crate) struct SharedMatches(Arc<Shared>);
+
+#[derive(Default)]
+struct Shared {
+    // Bumped on every new job or text change; older jobs drop their result
+    generation: AtomicU64,
+    state: Mutex<State>,
+}
+
+impl Shared {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[derive(Default)]
+struct State {
+    ready: Option<Arc<MatchIndex>>,
+    // Text was appended since `ready` was built
+    appended: bool,
+    // Matcher of the job in flight
+    running: Option<Matcher>,
+}
+
+impl SharedMatches {
+    /// The index of `matcher` in `text`, or None while it is being built.
+    /// Starts a job when there is none for this matcher; after an append the
+    /// previous index is returned until the rescan finishes.
+    pub(crate) fn get(&self, matcher: &Matcher, text: &Arc<String>, ctx: &egui::Context) -> Option<Arc<MatchIndex>> {
+        let mut state = self.0.state();
+        let current = state.ready.clone().filter(|ready| ready.matcher == *matcher);
+        if state.running.as_ref() == Some(matcher) || (current.is_some() && !state.appended) {
+            return current;
+        }
+        let generation = self.0.generation.fetch_add(1, Ordering::AcqRel) + 1;
+        state.running = Some(matcher.clone());
+        // An append only needs the old last line and what follows it
+        let (base, resume) = match &current {
+            Some(ready) => (Some(ready.clone()), ready.resume),
+            None => (None, (0, 0)),
+        };
+        let (text, matcher, weak, ctx) = (text.clone(), matcher.clone(), Arc::downgrade(&self.0), ctx.clone());
+        thread::spawn(move || {
+            let Some(index) = build(&weak, generation, matcher, base.as_deref(), &text[resume.1..], resume) else { return; };
+            let Some(shared) = weak.upgrade() else { return; };
+            let mut state = shared.state();
+            if shared.generation.load(Ordering::Acquire) == generation {
+                *state = State { ready: Some(Arc::new(index)), appended: false, running: None };
+                ctx.request_repaint();
+            }
+        });
+        current
+    }
+
+    /// Text was appended to the tab; the next `get` rescans from its old last line.
+    pub(crate) fn appended(&self) {
+        let mut state = self.0.state();
+        self.0.generation.fetch_add(1, Ordering::AcqRel);
+        state.appended = true;
+        state.running = None;
+    }
+}
+
+// Matches in `tail`, the text from line `resume.0` (at byte `resume.1`) on,
+// after those of `base` on earlier lines. None when cancelled.
+fn build(weak: &Weak<Shared>, generation: u64, matcher: Matcher, base: Option<&MatchIndex>, tail: &str, resume: (usize, usize)) -> Option<MatchIndex> {
+    let mut matches = base.map(|b| b.matches[..b.matches.partition_point(|m| m.line < resume.0)].to_vec()).unwrap_or_default();
+    let (mut line, mut offset) = resume;
+    let mut last = resume;
+    for (i, chunk) in tail.split_inclusive('\n').enumerate() {
+        if i % CANCEL_CHECK_LINES == 0 && weak.upgrade().is_none_or(|s| s.generation.load(Ordering::Acquire) != generation) {
+            return None;
+        }
+        // Same line ends as TextDoc
+        let text = chunk.strip_suffix('\n').unwrap_or(chunk);
+        let text = text.strip_suffix('\r').unwrap_or(text);
+        matches.extend(matcher.find_iter(text).map(|range| IndexedMatch { line, range }));
+        last = (line, offset);
+        line += 1;
+        offset += chunk.len();
+    }
+    // A finished last line is not continued by an append; the next one starts after it
+    if tail.ends_with('\n') { last = (line, offset); }
+    Some(MatchIndex { matcher, matches, resume: last })
+}
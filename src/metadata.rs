@@ -0,0 +1,209 @@
+// Image metadata for the side panel: format, color type and bit depth from
+// the decoder, the ICC profile's description, EXIF (kamadak-exif) and PNG
+// text chunks. Read on demand for the image on screen. Orientation itself is
+// applied when decoding, in io.rs.
+
+use eframe::egui;
+use exif::{In, Tag};
+use image::ImageDecoder;
+use std::path::{Path, PathBuf};
+
+// PNG text values longer than this are cut off in the panel
+const MAX_TEXT_CHARS: usize = 500;
+
+pub(crate) struct Section {
+    pub(crate) title: &'static str,
+    pub(crate) rows: Vec<(String, String)>,
+}
+
+/// Metadata of the image on screen, re-read when another image (or a reload
+/// of the same one) is shown.
+#[derive(Default)]
+pub(crate) struct MetadataState {
+    key: Option<(PathBuf, egui::TextureId)>,
+    pub(crate) sections: Vec<Section>,
+    /// Every EXIF field, for the collapsed "All EXIF fields" list.
+    pub(crate) all_exif: Vec<(String, String)>,
+    pub(crate) error: Option<String>,
+}
+
+impl MetadataState {
+    pub(crate) fn sync(&mut self, path: &Path, texture: egui::TextureId) {
+        let key = (path.to_path_buf(), texture);
+        if self.key.as_ref() == Some(&key) { return; }
+        *self = Self { key: Some(key), ..Default::default() };
+        if let Err(e) = self.read(path) { self.error = Some(e); }
+    }
+
+    fn read(&mut self, path: &Path) -> Result<(), String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+        // SVG has none of the raster metadata; only its document size
+        if crate::filetype::is_svg(path, &bytes) {
+            let size = crate::svg::SvgDoc::parse(path, &bytes)?.size();
+            self.sections.push(Section { title: "Image", rows: vec![
+                ("Format".to_string(), "SVG".to_string()),
+                ("Dimensions".to_string(), format!("{} × {} user units", size.x, size.y)),
+                ("File size".to_string(), format!("{:.1} KB", bytes.len() as f64 / 1024.0)),
+            ] });
+            return Ok(());
+        }
+        let reader = crate::io::image_reader(path, &bytes)?;
+        let format = reader.format();
+        let mut decoder = reader.into_decoder().map_err(|e| format!("Failed to open image: {}", e))?;
+        let (w, h) = decoder.dimensions();
+        let color = decoder.original_color_type();
+        let orientation = decoder.orientation().unwrap_or(image::metadata::Orientation::NoTransforms);
+        let icc = decoder.icc_profile().ok().flatten();
+        // The decoder's EXIF block where it has one, otherwise let kamadak-exif
+        // find it in the container (TIFF, PNG eXIf)
+        let exif = match decoder.exif_metadata().ok().flatten() {
+            Some(raw) => exif::Reader::new().read_raw(raw).ok(),
+            None => exif::Reader::new().read_from_container(&mut std::io::Cursor::new(&bytes)).ok(),
+        };
+
+        let mut image = vec![
+            ("Format".to_string(), format.map(|f| format!("{:?}", f).to_uppercase()).unwrap_or_else(|| "Unknown".to_string())),
+            ("Dimensions".to_string(), format!("{} × {} px", w, h)),
+            ("Color type".to_string(), format!("{:?}", color)),
+            ("Bit depth".to_string(), format!("{} bits per channel", color.bits_per_pixel() / u16::from(color.channel_count().max(1)))),
+            ("File size".to_string(), format!("{:.1} KB", bytes.len() as f64 / 1024.0)),
+        ];
+        if orientation != image::metadata::Orientation::NoTransforms {
+            image.push(("Orientation".to_string(), format!("{} (applied)", orientation_name(orientation))));
+        }
+        self.sections.push(Section { title: "Image", rows: image });
+        let profile = match &icc {
+            Some(icc) => icc_description(icc).unwrap_or_else(|| format!("Embedded ({} bytes, unnamed)", icc.len())),
+            None => "None (sRGB assumed)".to_string(),
+        };
+        self.sections.push(Section { title: "Color profile", rows: vec![("ICC profile".to_string(), profile)] });
+
+        if let Some(exif) = &exif {
+            let fields = |tags: &[(Tag, &str)]| -> Vec<(String, String)> {
+                tags.iter()
+                    .filter_map(|(tag, label)| Some((label.to_string(), field_text(exif, exif.get_field(*tag, In::PRIMARY)?))))
+                    .collect()
+            };
+            let camera = fields(&[
+                (Tag::Make, "Make"),
+                (Tag::Model, "Model"),
+                (Tag::LensMake, "Lens make"),
+                (Tag::LensModel, "Lens"),
+                (Tag::Software, "Software"),
+                (Tag::DateTimeOriginal, "Taken"),
+            ]);
+            let exposure = fields(&[
+                (Tag::ExposureTime, "Exposure time"),
+                (Tag::FNumber, "Aperture"),
+                (Tag::PhotographicSensitivity, "ISO"),
+                (Tag::FocalLength, "Focal length"),
+                (Tag::FocalLengthIn35mmFilm, "Focal length (35 mm)"),
+                (Tag::ExposureBiasValue, "Exposure bias"),
+                (Tag::ExposureProgram, "Program"),
+                (Tag::MeteringMode, "Metering"),
+                (Tag::Flash, "Flash"),
+                (Tag::WhiteBalance, "White balance"),
+            ]);
+            let mut gps = Vec::new();
+            if let (Some(lat), Some(lon)) = (gps_coordinate(exif, Tag::GPSLatitude, Tag::GPSLatitudeRef), gps_coordinate(exif, Tag::GPSLongitude, Tag::GPSLongitudeRef)) {
+                gps.push(("Position".to_string(), format!("{:.6}, {:.6}", lat, lon)));
+            }
+            gps.extend(fields(&[(Tag::GPSAltitude, "Altitude"), (Tag::GPSDateStamp, "Date")]));
+            for (title, rows) in [("Camera", camera), ("Exposure", exposure), ("GPS", gps)] {
+                if !rows.is_empty() { self.sections.push(Section { title, rows }); }
+            }
+            self.all_exif = exif.fields().map(|f| (f.tag.to_string(), field_text(exif, f))).collect();
+        }
+
+        let text = png_text_chunks(&bytes);
+        if !text.is_empty() { self.sections.push(Section { title: "PNG text", rows: text }); }
+        Ok(())
+    }
+}
+
+fn field_text(exif: &exif::Exif, field: &exif::Field) -> String {
+    field.display_value().with_unit(exif).to_string().trim_matches('"').to_string()
+}
+
+// Degrees as a signed decimal, negative for the southern and western hemispheres
+fn gps_coordinate(exif: &exif::Exif, tag: Tag, reference: Tag) -> Option<f64> {
+    let exif::Value::Rational(parts) = &exif.get_field(tag, In::PRIMARY)?.value else { return None; };
+    let [d, m, s] = [parts.first()?, parts.get(1)?, parts.get(2)?].map(|r| r.to_f64());
+    let value = d + m / 60.0 + s / 3600.0;
+    let negative = exif.get_field(reference, In::PRIMARY)
+        .is_some_and(|f| matches!(&f.value, exif::Value::Ascii(v) if v.first().is_some_and(|r| r.starts_with(b"S") || r.starts_with(b"W"))));
+    Some(if negative { -value } else { value })
+}
+
+fn orientation_name(orientation: image::metadata::Orientation) -> &'static str {
+    use image::metadata::Orientation::*;
+    match orientation {
+        NoTransforms => "Normal",
+        Rotate90 => "Rotated 90° clockwise",
+        Rotate180 => "Rotated 180°",
+        Rotate270 => "Rotated 90° counter-clockwise",
+        FlipHorizontal => "Mirrored horizontally",
+        FlipVertical => "Mirrored vertically",
+        Rotate90FlipH => "Rotated 90° and mirrored",
+        Rotate270FlipH => "Rotated 270° and mirrored",
+    }
+}
+
+fn be_u32(bytes: &[u8], at: usize) -> Option<usize> {
+    Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?) as usize)
+}
+
+// The profile's 'desc' tag: ASCII in ICC v2 ('desc' type), the first
+// localized UTF-16 string in v4 ('mluc' type)
+fn icc_description(icc: &[u8]) -> Option<String> {
+    let count = be_u32(icc, 128)?;
+    let entry = (0..count.min(256)).map(|i| 132 + i * 12).find(|&at| icc.get(at..at + 4) == Some(b"desc"))?;
+    let (offset, size) = (be_u32(icc, entry + 4)?, be_u32(icc, entry + 8)?);
+    let tag = icc.get(offset..offset.checked_add(size)?)?;
+    let text = match tag.get(..4)? {
+        b"desc" => {
+            let len = be_u32(tag, 8)?;
+            String::from_utf8_lossy(tag.get(12..12 + len)?).trim_end_matches('\0').to_string()
+        }
+        b"mluc" => {
+            let (len, at) = (be_u32(tag, 20)?, be_u32(tag, 24)?);
+            let units: Vec<u16> = tag.get(at..at + len)?.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => return None,
+    };
+    let text = text.trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+// tEXt and iTXt keyword/value pairs; compressed values are only named
+fn png_text_chunks(bytes: &[u8]) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    if !bytes.starts_with(b"\x89PNG\r\n\x1a\n") { return out; }
+    let mut at = 8;
+    while let (Some(len), Some(kind)) = (be_u32(bytes, at), bytes.get(at + 4..at + 8)) {
+        let Some(data) = bytes.get(at + 8..at + 8 + len) else { break; };
+        let mut parts = data.splitn(2, |b| *b == 0);
+        let keyword = String::from_utf8_lossy(parts.next().unwrap_or_default()).into_owned();
+        let rest = parts.next().unwrap_or_default();
+        let value = match kind {
+            // Latin-1
+            b"tEXt" => Some(rest.iter().map(|&b| b as char).collect::<String>()),
+            b"zTXt" => Some("(compressed)".to_string()),
+            b"iTXt" => {
+                let compressed = rest.first() == Some(&1);
+                // Skip the flags, language tag and translated keyword
+                let text = rest.get(2..).and_then(|r| r.splitn(3, |b| *b == 0).nth(2));
+                Some(if compressed { "(compressed)".to_string() } else { String::from_utf8_lossy(text.unwrap_or_default()).into_owned() })
+            }
+            b"IEND" => break,
+            _ => None,
+        };
+        if let Some(value) = value {
+            let value = if value.chars().count() > MAX_TEXT_CHARS { value.chars().take(MAX_TEXT_CHARS).collect::<String>() + "…" } else { value };
+            out.push((keyword, value));
+        }
+        at += 12 + len;
+    }
+    out
+}
@@ -0,0 +1,207 @@
+// Paged, read-only text backend for files too large to read into memory. A
+// background thread builds a sparse line index (one offset every LINE_STRIDE
+// lines) and the viewer reads only the lines it shows. Line counts grow while
+// indexing runs, so a file is usable at once.
+//
+// The file is read with positioned reads rather than memory-mapped: a large
+// log rotated with copytruncate shrinks under us, and touching a mapping past
+// the new end raises SIGBUS. A read past the end just comes back short.
+
+use eframe::egui;
+use std::borrow::Cow;
+use std::fs::File;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Lines between stored offsets; fetching a line scans at most this many
+const LINE_STRIDE: usize = 64;
+const INDEX_CHUNK_BYTES: usize = 8 * 1024 * 1024;
+const REPAINT_INTERVAL: Duration = Duration::from_millis(100);
+// Longest slice of a single line handed to the viewer
+const MAX_LINE_BYTES: usize = 1024 * 1024;
+// Read size when fetching lines
+const READ_BLOCK_BYTES: usize = 64 * 1024;
+// Lines decoded per read, from a checkpoint on: a screenful and then some
+const WINDOW_LINES: usize = 4 * LINE_STRIDE;
+// ... unless they add up to this much first
+const WINDOW_BYTES: usize = 4 * 1024 * 1024;
+
+pub struct PagedFile {
+    file: File,
+    // Size when opened; a file that shrinks since reads as ending early
+    len: u64,
+    // Byte offset of every LINE_STRIDE-th line start
+    checkpoints: RwLock<Vec<u64>>,
+    lines: AtomicUsize,
+    indexed_bytes: AtomicU64,
+    max_line_bytes: AtomicUsize,
+    done: AtomicBool,
+    // Lines around the last one asked for; the viewer asks for the same
+    // screenful every frame
+    window: Mutex<LineWindow>,
+}
+
+#[derive(Default)]
+struct LineWindow {
+    first: usize,
+    lines: Vec<String>,
+    // Reused read buffer
+    block: Vec<u8>,
+}
+
+impl PagedFile {
+    /// Open `path` and start indexing it in the background. The indexer
+    /// stops on its own once the last handle to the file is dropped.
+    pub(crate) fn open(path: &Path, ctx: &egui::Context) -> Result<Arc<Self>, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+        let len = file.metadata().map_err(|e| format!("Failed to open file: {}", e))?.len();
+        let paged = Arc::new(Self {
+            file,
+            len,
+            checkpoints: RwLock::new(vec![0]),
+            lines: AtomicUsize::new(0),
+            indexed_bytes: AtomicU64::new(0),
+            max_line_bytes: AtomicUsize::new(0),
+            done: AtomicBool::new(false),
+            window: Mutex::new(LineWindow::default()),
+        });
+        let weak = Arc::downgrade(&paged);
+        let ctx = ctx.clone();
+        thread::spawn(move || index_lines(weak, ctx));
+        Ok(paged)
+    }
+
+    pub(crate) fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Lines indexed so far (all of them once `is_indexed`).
+    pub(crate) fn line_count(&self) -> usize {
+        self.lines.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn is_indexed(&self) -> bool {
+        self.done.load(Ordering::Acquire)
+    }
+
+    /// Indexing progress in 0.0..=1.0.
+    pub(crate) fn progress(&self) -> f32 {
+        if self.len == 0 { return 1.0; }
+        self.indexed_bytes.load(Ordering::Relaxed) as f32 / self.len as f32
+    }
+
+    pub(crate) fn max_line_bytes(&self) -> usize {
+        self.max_line_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Line `i` without its terminator, decoded lossily and capped at
+    /// MAX_LINE_BYTES. Empty if the line has not been indexed yet.
+    pub(crate) fn line(&self, i: usize) -> Cow<'_, str> {
+        if i >= self.line_count() { return Cow::Borrowed(""); }
+        let mut window = self.window.lock().unwrap_or_else(|e| e.into_inner());
+        if !(window.first..window.first + window.lines.len()).contains(&i) {
+            let Some(checkpoint) = self.checkpoints.read().ok().and_then(|c| c.get(i / LINE_STRIDE).copied()) else {
+                return Cow::Borrowed("");
+            };
+            self.read_window(&mut window, i / LINE_STRIDE * LINE_STRIDE, checkpoint, i);
+        }
+        Cow::Owned(window.lines.get(i - window.first).cloned().unwrap_or_default())
+    }
+
+    // Decode the lines from `first`, which starts at byte `offset`, through
+    // `want` and on until WINDOW_LINES or WINDOW_BYTES, in one pass
+    fn read_window(&self, window: &mut LineWindow, first: usize, offset: u64, want: usize) {
+        let LineWindow { first: window_first, lines, block } = window;
+        *window_first = first;
+        lines.clear();
+        block.resize(READ_BLOCK_BYTES, 0);
+        let mut bytes = 0;
+        let mut line = Vec::new();
+        let mut pos = offset;
+        'read: loop {
+            let n = crate::io::read_at(&self.file, pos, block);
+            if n == 0 { break; }
+            pos += n as u64;
+            let mut data = &block[..n];
+            loop {
+                let (part, ended) = match memchr::memchr(b'\n', data) {
+                    Some(nl) => (&data[..nl], true),
+                    None => (data, false),
+                };
+                let room = MAX_LINE_BYTES - line.len();
+                line.extend_from_slice(&part[..part.len().min(room)]);
+                if !ended { break; }
+                data = &data[part.len() + 1..];
+                bytes += line.len();
+                lines.push(decode_line(&mut line));
+                if first + lines.len() > want && (lines.len() >= WINDOW_LINES || bytes >= WINDOW_BYTES) { break 'read; }
+            }
+        }
+        // A last line without a newline, at the end of the file
+        if !line.is_empty() { lines.push(decode_line(&mut line)); }
+    }
+}
+
+// Take the bytes of a line, without a trailing '\r'
+fn decode_line(line: &mut Vec<u8>) -> String {
+    if line.last() == Some(&b'\r') { line.pop(); }
+    let text = String::from_utf8_lossy(line).into_owned();
+    line.clear();
+    text
+}
+
+// Scan the file for newlines in chunks, publishing counts as it goes
+fn index_lines(weak: Weak<PagedFile>, ctx: egui::Context) {
+    let mut pos = 0usize;
+    let mut lines = 0usize;
+    let mut line_start = 0usize;
+    let mut max_line = 0usize;
+    let mut last_repaint = Instant::now();
+    let mut buf = vec![0u8; INDEX_CHUNK_BYTES];
+    loop {
+        let Some(paged) = weak.upgrade() else { return; };
+        let want = (paged.len as usize - pos).min(INDEX_CHUNK_BYTES);
+        let read = crate::io::read_at(&paged.file, pos as u64, &mut buf[..want]);
+        let mut new_checkpoints = Vec::new();
+        for (off, b) in buf[..read].iter().enumerate() {
+            if *b == b'\n' {
+                let at = pos + off;
+                max_line = max_line.max(at - line_start);
+                line_start = at + 1;
+                lines += 1;
+                if lines.is_multiple_of(LINE_STRIDE) {
+                    new_checkpoints.push(line_start as u64);
+                }
+            }
+        }
+        pos += read;
+        // A short read means the file shrank; index what was there
+        let finished = pos >= paged.len as usize || read < want;
+        // A final line without a trailing newline still counts (`str::lines`)
+        let visible_lines = if finished && line_start < pos {
+            max_line = max_line.max(pos - line_start);
+            lines + 1
+        } else {
+            lines
+        };
+        if !new_checkpoints.is_empty()
+            && let Ok(mut cps) = paged.checkpoints.write() {
+            cps.extend(new_checkpoints);
+        }
+        paged.max_line_bytes.store(max_line, Ordering::Relaxed);
+        paged.indexed_bytes.store(if finished { paged.len } else { pos as u64 }, Ordering::Relaxed);
+        paged.lines.store(visible_lines, Ordering::Release);
+        if finished {
+            paged.done.store(true, Ordering::Release);
+            ctx.request_repaint();
+            return;
+        }
+        if last_repaint.elapsed() >= REPAINT_INTERVAL {
+            last_repaint = Instant::now();
+            ctx.request_repaint();
+        }
+    }
+}
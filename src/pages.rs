@@ -0,0 +1,117 @@
+// Files holding several images: the pages of a TIFF and the sizes in an ICO
+// or CUR icon. They decode into animation::Frames with a name per page and
+// are stepped through like paused animation frames. Files with a single
+// image are left to io::decode_image.
+
+use crate::animation::Frames;
+use eframe::egui;
+use std::io::Cursor;
+use std::time::Duration;
+use tiff::decoder::{Decoder, DecodingResult};
+
+const CURSOR_MAGIC: &[u8] = b"\0\0\x02\0";
+
+/// Decode every page of a multi-page TIFF or every entry of an icon. Returns
+/// None for other formats and files with one image.
+pub(crate) fn decode(bytes: &[u8]) -> Result<Option<Frames>, String> {
+    match image::guess_format(bytes) {
+        Ok(image::ImageFormat::Tiff) => tiff_pages(bytes),
+        Ok(image::ImageFormat::Ico) => icon_entries(bytes),
+        _ if bytes.starts_with(CURSOR_MAGIC) => icon_entries(bytes),
+        _ => Ok(None),
+    }
+}
+
+fn tiff_pages(bytes: &[u8]) -> Result<Option<Frames>, String> {
+    let err = |e: tiff::TiffError| format!("Failed to open image: {}", e);
+    let mut decoder = Decoder::new(Cursor::new(bytes)).map_err(err)?;
+    let mut out = Frames { frames: Vec::new(), truncated: false, pages: Vec::new(), first: 0 };
+    let mut total_bytes = 0usize;
+    for page in 1.. {
+        let (w, h) = decoder.dimensions().map_err(err)?;
+        total_bytes = total_bytes.saturating_add((w as usize).saturating_mul(h as usize).saturating_mul(4));
+        if total_bytes > crate::io::MAX_IMAGE_TEXTURE_BYTES {
+            out.truncated = true;
+            break;
+        }
+        // Pages in a sample layout we do not convert are left out
+        let color = decoder.colortype().map_err(err)?;
+        if let Some(image) = decoder.read_image().ok().and_then(|data| tiff_rgba(color, data, w as usize, h as usize)) {
+            out.frames.push((image, Duration::ZERO));
+            out.pages.push(format!("Page {} — {}×{}", page, w, h));
+        }
+        if !decoder.more_images() { break; }
+        decoder.next_image().map_err(err)?;
+    }
+    Ok((out.frames.len() > 1).then_some(out))
+}
+
+// 8-bit RGBA from TIFF samples of 8, 16 or 32-bit float gray, RGB or CMYK
+fn tiff_rgba(color: tiff::ColorType, data: DecodingResult, w: usize, h: usize) -> Option<egui::ColorImage> {
+    use tiff::ColorType::*;
+    let samples: Vec<u8> = match data {
+        DecodingResult::U8(v) => v,
+        DecodingResult::U16(v) => v.into_iter().map(|s| (s >> 8) as u8).collect(),
+        DecodingResult::F32(v) => v.into_iter().map(|s| (s.clamp(0.0, 1.0) * 255.0).round() as u8).collect(),
+        _ => return None,
+    };
+    let channels = match color {
+        Gray(_) => 1,
+        GrayA(_) => 2,
+        RGB(_) => 3,
+        RGBA(_) | CMYK(_) => 4,
+        _ => return None,
+    };
+    // Bit-packed samples (1-bit bilevel and the like) come out shorter
+    if samples.len() != w * h * channels { return None; }
+    let pixels = samples.chunks_exact(channels).map(|p| match (color, p) {
+        (Gray(_), [g]) => egui::Color32::from_gray(*g),
+        (GrayA(_), [g, a]) => egui::Color32::from_rgba_unmultiplied(*g, *g, *g, *a),
+        (RGB(_), [r, g, b]) => egui::Color32::from_rgb(*r, *g, *b),
+        (CMYK(_), [c, m, y, k]) => {
+            let ink = |v: u8| ((255 - v as u32) * (255 - *k as u32) / 255) as u8;
+            egui::Color32::from_rgb(ink(*c), ink(*m), ink(*y))
+        }
+        (_, [r, g, b, a]) => egui::Color32::from_rgba_unmultiplied(*r, *g, *b, *a),
+        _ => egui::Color32::TRANSPARENT,
+    });
+    Some(egui::ColorImage { size: [w, h], pixels: pixels.collect() })
+}
+
+// Every image in an icon or cursor, starting on the largest
+fn icon_entries(bytes: &[u8]) -> Result<Option<Frames>, String> {
+    let u16_at = |at: usize| bytes.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as usize);
+    let u32_at = |at: usize| bytes.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize);
+    let count = u16_at(4).unwrap_or(0);
+    if count < 2 { return Ok(None); }
+    let cursor = bytes.starts_with(CURSOR_MAGIC);
+    let mut out = Frames { frames: Vec::new(), truncated: false, pages: Vec::new(), first: 0 };
+    let mut largest = (0, 0);
+    for i in 0..count {
+        let entry = 6 + i * 16;
+        let (Some(dir), Some(size), Some(offset)) = (bytes.get(entry..entry + 16), u32_at(entry + 8), u32_at(entry + 12)) else { break; };
+        let Some(data) = offset.checked_add(size).and_then(|end| bytes.get(offset..end)) else { continue; };
+        // Decode the entry on its own as a one-image icon, which handles
+        // both PNG and BMP entries
+        let mut single = Vec::with_capacity(22 + size);
+        single.extend_from_slice(&[0, 0, 1, 0, 1, 0]);
+        single.extend_from_slice(&dir[..12]);
+        single.extend_from_slice(&22u32.to_le_bytes());
+        single.extend_from_slice(data);
+        let Ok(image) = image::load_from_memory_with_format(&single, image::ImageFormat::Ico) else { continue; };
+        let rgba = image.to_rgba8();
+        let (w, h) = rgba.dimensions();
+        // In cursors the bit count field holds the hotspot instead
+        let bits = if cursor { 0 } else { u16_at(entry + 6).unwrap_or(0) };
+        let mut name = format!("{}×{}", w, h);
+        if bits > 0 { name.push_str(&format!(", {}-bit", bits)); }
+        if data.starts_with(b"\x89PNG") { name.push_str(" (PNG)"); }
+        if (w * h, bits) > largest {
+            largest = (w * h, bits);
+            out.first = out.frames.len();
+        }
+        out.frames.push((egui::ColorImage::from_rgba_unmultiplied([w as usize, h as usize], rgba.as_raw()), Duration::ZERO));
+        out.pages.push(name);
+    }
+    Ok((out.frames.len() > 1).then_some(out))
+}
@@ -1,100 +1,436 @@
-pub(crate) fn recompute_count(query: &str, text: &str) -> usize {
-    if query.is_empty() { return 0; }
-    let q = query.to_ascii_lowercase();
-    text.to_ascii_lowercase().matches(&q).count()
+use caseless::Caseless;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::ops::Range;
+
+// Characters of context on each side of a match in Global Search snippets
+const SNIPPET_CONTEXT: usize = 40;
+
+/// Case, whole-word, regex and diacritic switches, shared by Find and
+/// Global Search.
+#[derive(Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub(crate) struct FindOptions {
+    pub(crate) case_sensitive: bool,
+    pub(crate) whole_word: bool,
+    pub(crate) regex: bool,
+    /// Match "e" with "é", "è", "ê"…: combining marks are left out on both sides.
+    pub(crate) ignore_diacritics: bool,
+}
+
+/// A compiled Find or Global Search query. Highlighting, counting and
+/// navigation all take their matches from here, so they cannot disagree.
+/// Matches are non-empty byte ranges found line by line; plain queries are
+/// matched as escaped regex literals.
+///
+/// Without Match case, plain queries and lines are compared under full
+/// Unicode case folding ("Straße" finds "STRASSE", "i" finds "İ"); regex
+/// queries use the regex engine's own case-insensitive mode, so classes and
+/// escapes keep their meaning. Matches are found in the folded line and
+/// mapped back to whole characters of the original.
+#[derive(Clone)]
+pub(crate) struct Matcher {
+    query: String,
+    options: FindOptions,
+    regex: regex::Regex,
+    // Case fold the line before matching (plain queries without Match case)
+    fold_case: bool,
+}
+
+// Matchers are equal when built from the same query and options, which is
+// what caches of their matches are keyed on
+impl PartialEq for Matcher {
+    fn eq(&self, other: &Self) -> bool {
+        self.query == other.query && self.options == other.options
+    }
 }
 
-pub(crate) fn find_target_line(text: &str, query: &str, target_idx: usize) -> Option<usize> {
-    if query.is_empty() { return None; }
-    let q = query.to_ascii_lowercase();
-    let mut global = 0usize;
-    for (i, line) in text.lines().enumerate() {
-        let mut rest = line.to_ascii_lowercase();
-        while let Some(pos) = rest.find(&q) {
-            if global == target_idx { return Some(i); }
-            global += 1;
-            let next = pos + q.len();
-            if next >= rest.len() { break; }
-            rest = rest[next..].to_string();
+impl Matcher {
+    pub(crate) fn new(query: &str, options: FindOptions) -> Result<Self, String> {
+        let fold_case = !options.case_sensitive && !options.regex;
+        let folded = fold(query, fold_case, options.ignore_diacritics).text;
+        let pattern = if options.regex { folded.into_owned() } else { regex::escape(&folded) };
+        let regex = regex::RegexBuilder::new(&pattern)
+            .case_insensitive(!options.case_sensitive && options.regex)
+            .build()
+            .map_err(|e| format!("Regex error: {}", e))?;
+        Ok(Self { query: query.to_string(), options, regex, fold_case })
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.query.is_empty()
+    }
+
+    /// Byte ranges of the matches in one line, in order. Ranges are on
+    /// character boundaries of `line` and never overlap.
+    pub(crate) fn find_iter<'a>(&'a self, line: &'a str) -> impl Iterator<Item = Range<usize>> + 'a {
+        let folded = fold(line, self.fold_case, self.options.ignore_diacritics);
+        let (mut at, mut last_end) = (0, 0);
+        std::iter::from_fn(move || {
+            let text = &*folded.text;
+            while !self.query.is_empty() && at <= text.len() {
+                let m = self.regex.find_at(text, at)?;
+                let range = folded.source_range(m.range());
+                // Empty matches (`a*`, `^`) mark nothing; a match inside a
+                // character already marked ("s" twice in "ß") is not another
+                // one; words need edges that are not word characters
+                if m.is_empty() || range.start < last_end || (self.options.whole_word && !is_whole_word(line, range.clone())) {
+                    at = m.start() + text[m.start()..].chars().next().map_or(1, char::len_utf8);
+                    continue;
+                }
+                at = m.end();
+                last_end = range.end;
+                return Some(range);
+            }
+            None
+        })
+    }
+
+    /// Matches in `text`, counted line by line.
+    pub(crate) fn count(&self, text: &str) -> usize {
+        if self.query.is_empty() { return 0; }
+        text.lines().map(|line| self.find_iter(line).count()).sum()
+    }
+
+    pub(crate) fn is_match(&self, text: &str) -> bool {
+        text.lines().any(|line| self.find_iter(line).next().is_some())
+    }
+
+    /// Every match in `text` as (line index, snippet, match range in the
+    /// snippet), in order.
+    pub(crate) fn find_in(&self, text: &str) -> Vec<(usize, String, Range<usize>)> {
+        let mut found = Vec::new();
+        if self.query.is_empty() { return found; }
+        for (line_idx, line) in text.lines().enumerate() {
+            for m in self.find_iter(line) {
+                let start = line.floor_char_boundary(m.start.saturating_sub(SNIPPET_CONTEXT));
+                let end = line.ceil_char_boundary(m.end.saturating_add(SNIPPET_CONTEXT).min(line.len()));
+                found.push((line_idx, line[start..end].to_string(), m.start - start..m.end - start));
+            }
         }
+        found
+    }
+
+    /// Matches in one file as Global Search results; `tab_index` is None for
+    /// files found on disk that are not open.
+    pub(crate) fn results(&self, path: &std::path::Path, tab_index: Option<usize>, text: &str) -> Vec<crate::app::GlobalSearchResult> {
+        self.find_in(text)
+            .into_iter()
+            .enumerate()
+            .map(|(match_index_in_tab, (line_index, snippet, match_range))| crate::app::GlobalSearchResult {
+                tab_index,
+                path: path.to_path_buf(),
+                line_index,
+                snippet,
+                match_range,
+                match_index_in_tab,
+            })
+            .collect()
     }
-    None
 }
 
 pub(crate) fn global_search(
-    open_text_tabs: &Vec<crate::app::TextTab>,
-    query: &str,
-    case_sensitive: bool,
-    whole_word: bool,
-    regex_mode: bool,
-) -> Result<Vec<crate::app::GlobalSearchResult>, String> {
-    let mut results: Vec<crate::app::GlobalSearchResult> = Vec::new();
-    if query.is_empty() { return Ok(results); }
-
-    let regex_opt = if regex_mode {
-        match regex::RegexBuilder::new(query)
-            .case_insensitive(!case_sensitive)
-            .build() {
-            Ok(r) => Some(r),
-            Err(e) => return Err(format!("Regex error: {}", e)),
-        }
-    } else { None };
-
-    for (tab_idx, tab) in open_text_tabs.iter().enumerate() {
-        let mut match_counter_in_tab: usize = 0;
-        for (line_idx, line) in tab.text.lines().enumerate() {
-            if let Some(re) = &regex_opt {
-                for m in re.find_iter(line) {
-                    let start = m.start().saturating_sub(40);
-                    let end = m.end().saturating_add(40).min(line.len());
-                    let snippet = line[start..end].to_string();
-                    results.push(crate::app::GlobalSearchResult {
-                        tab_index: tab_idx,
-                        path: tab.path.clone(),
-                        line_index: line_idx,
-                        snippet,
-                        match_index_in_tab: match_counter_in_tab,
-                    });
-                    match_counter_in_tab += 1;
-                }
-            } else {
-                let mut hay = line.to_string();
-                let mut needle = query.to_string();
-                if !case_sensitive {
-                    hay = hay.to_ascii_lowercase();
-                    needle = needle.to_ascii_lowercase();
+    open_text_tabs: &[crate::app::TextTab],
+    query: &Matcher,
+) -> Vec<crate::app::GlobalSearchResult> {
+    open_text_tabs
+        .iter()
+        .enumerate()
+        .flat_map(|(tab_idx, tab)| query.results(&tab.path, Some(tab_idx), &tab.text))
+        .collect()
+}
+
+/// One line of the Global Search results list.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum ResultRow {
+    /// A file's header, by group index.
+    File(usize),
+    /// A match, by result index.
+    Match(usize),
+}
+
+/// Global Search results grouped by file in the order they were found, with
+/// the rows of the list as shown: a header per file, then its matches unless
+/// the file is collapsed. Rows are kept up to date as results stream in, so
+/// the list can be drawn a screenful at a time.
+#[derive(Default)]
+pub(crate) struct GlobalResults {
+    results: Vec<crate::app::GlobalSearchResult>,
+    // Each file's run of results
+    groups: Vec<Range<usize>>,
+    collapsed: HashSet<usize>,
+    rows: Vec<ResultRow>,
+    /// Result picked with the arrow keys or the mouse.
+    pub(crate) selected: Option<usize>,
+    /// Scroll offset and height of the list when last drawn, to bring the
+    /// selection into view.
+    pub(crate) view: (f32, f32),
+}
+
+impl GlobalResults {
+    pub(crate) fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub(crate) fn extend(&mut self, found: Vec<crate::app::GlobalSearchResult>) {
+        for res in found {
+            let index = self.results.len();
+            match self.groups.last_mut() {
+                Some(group) if self.results[group.start].path == res.path => {
+                    group.end += 1;
+                    if !self.collapsed.contains(&(self.groups.len() - 1)) { self.rows.push(ResultRow::Match(index)); }
                 }
-                let mut offset_in_line: usize = 0;
-                let original_line = line;
-                while let Some(pos) = hay[offset_in_line..].find(&needle) {
-                    let abs_pos = offset_in_line + pos;
-                    if whole_word {
-                        let left_ok = abs_pos == 0 || !original_line.chars().nth(abs_pos - 1).map(|c| c.is_alphanumeric() || c == '_').unwrap_or(false);
-                        let right_index = abs_pos + query.len();
-                        let right_ok = right_index >= original_line.len() || !original_line.chars().nth(right_index).map(|c| c.is_alphanumeric() || c == '_').unwrap_or(false);
-                        if !(left_ok && right_ok) {
-                            offset_in_line = abs_pos + needle.len();
-                            continue;
-                        }
-                    }
-                    let start = abs_pos.saturating_sub(40);
-                    let end = (abs_pos + query.len()).saturating_add(40).min(original_line.len());
-                    let snippet = original_line[start..end].to_string();
-                    results.push(crate::app::GlobalSearchResult {
-                        tab_index: tab_idx,
-                        path: tab.path.clone(),
-                        line_index: line_idx,
-                        snippet,
-                        match_index_in_tab: match_counter_in_tab,
-                    });
-                    match_counter_in_tab += 1;
-                    offset_in_line = abs_pos + needle.len();
-                    if offset_in_line >= hay.len() { break; }
+                _ => {
+                    self.groups.push(index..index + 1);
+                    self.rows.push(ResultRow::File(self.groups.len() - 1));
+                    self.rows.push(ResultRow::Match(index));
                 }
             }
+            self.results.push(res);
         }
     }
 
-    Ok(results)
+    pub(crate) fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    pub(crate) fn file_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub(crate) fn get(&self, index: usize) -> Option<&crate::app::GlobalSearchResult> {
+        self.results.get(index)
+    }
+
+    /// Path and match count of a file's group.
+    pub(crate) fn group(&self, group: usize) -> (&std::path::Path, usize) {
+        let range = &self.groups[group];
+        (&self.results[range.start].path, range.len())
+    }
+
+    pub(crate) fn rows(&self) -> &[ResultRow] {
+        &self.rows
+    }
+
+    pub(crate) fn is_collapsed(&self, group: usize) -> bool {
+        self.collapsed.contains(&group)
+    }
+
+    pub(crate) fn toggle_collapsed(&mut self, group: usize) {
+        if !self.collapsed.remove(&group) { self.collapsed.insert(group); }
+        // A selection hidden in a collapsed file is dropped
+        if self.selected.is_some_and(|s| self.groups[group].contains(&s)) { self.selected = None; }
+        self.rows.clear();
+        for (g, range) in self.groups.iter().enumerate() {
+            self.rows.push(ResultRow::File(g));
+            if !self.collapsed.contains(&g) { self.rows.extend(range.clone().map(ResultRow::Match)); }
+        }
+    }
+
+    /// Select the next (or previous) shown match and return its row.
+    pub(crate) fn select_step(&mut self, forward: bool) -> Option<usize> {
+        let current = self.selected.and_then(|s| self.rows.iter().position(|r| *r == ResultRow::Match(s)));
+        let is_match = |r: &&ResultRow| matches!(r, ResultRow::Match(_));
+        let row = match (current, forward) {
+            (Some(at), true) => self.rows.iter().enumerate().skip(at + 1).find(|(_, r)| is_match(r)),
+            (Some(at), false) => self.rows.iter().enumerate().take(at).rev().find(|(_, r)| is_match(r)),
+            (None, true) => self.rows.iter().enumerate().find(|(_, r)| is_match(r)),
+            (None, false) => self.rows.iter().enumerate().rev().find(|(_, r)| is_match(r)),
+        };
+        let (row, &ResultRow::Match(index)) = row? else { return None; };
+        self.selected = Some(index);
+        Some(row)
+    }
+}
+
+// A line as the regex sees it. `starts` pairs, for each original character
+// with folded output, the offset of that output in `text` with the
+// character's own offset; None when the offsets are the same.
+struct Folded<'a> {
+    text: Cow<'a, str>,
+    starts: Option<Vec<(usize, usize)>>,
+    source_len: usize,
+}
+
+impl Folded<'_> {
+    // Range in the original line covering every character behind `range`,
+    // looked up only for the matches reported
+    fn source_range(&self, range: Range<usize>) -> Range<usize> {
+        match &self.starts {
+            Some(starts) if !range.is_empty() => {
+                let first = starts.partition_point(|&(folded, _)| folded <= range.start) - 1;
+                let next = starts.partition_point(|&(folded, _)| folded < range.end);
+                starts[first].1..starts.get(next).map_or(self.source_len, |&(_, source)| source)
+            }
+            _ => range,
+        }
+    }
+}
+
+// Case fold and/or strip diacritics one character at a time, recording where
+// each character's output starts. Characters with no output (stripped marks)
+// get no entry and so run up to the next one, so highlighting "e" in "é"
+// written as e + U+0301 covers the accent too.
+fn fold(line: &str, fold_case: bool, strip_marks: bool) -> Folded<'_> {
+    if !fold_case && !strip_marks {
+        return Folded { text: Cow::Borrowed(line), starts: None, source_len: line.len() };
+    }
+    // ASCII has no marks and folds to its lowercase
+    if line.is_ascii() {
+        let text = if fold_case { Cow::Owned(line.to_ascii_lowercase()) } else { Cow::Borrowed(line) };
+        return Folded { text, starts: None, source_len: line.len() };
+    }
+    let mut text = String::with_capacity(line.len());
+    let mut starts = Vec::new();
+    for (start, c) in line.char_indices() {
+        let before = text.len();
+        if strip_marks {
+            unicode_normalization::char::decompose_canonical(c, |d| {
+                if !is_diacritic(d) { push_folded(&mut text, d, fold_case); }
+            });
+        } else {
+            push_folded(&mut text, c, fold_case);
+        }
+        if text.len() > before { starts.push((before, start)); }
+    }
+    Folded { text: Cow::Owned(text), starts: Some(starts), source_len: line.len() }
+}
+
+fn push_folded(out: &mut String, c: char, fold_case: bool) {
+    if fold_case { out.extend(std::iter::once(c).default_case_fold()); } else { out.push(c); }
+}
+
+// Marks that attach to a base letter (accents, cedillas, Hebrew and Arabic
+// vowel points). Spacing marks with combining class 0, such as Indic vowel
+// signs, spell a different sound and are kept.
+fn is_diacritic(c: char) -> bool {
+    unicode_normalization::char::is_combining_mark(c) && unicode_normalization::char::canonical_combining_class(c) != 0
 }
 
+// Whether the match at `range` starts and ends at word edges
+fn is_whole_word(line: &str, range: Range<usize>) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    !line[..range.start].chars().next_back().is_some_and(is_word) && !line[range.end..].chars().next().is_some_and(is_word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher(query: &str, options: FindOptions) -> Matcher {
+        Matcher::new(query, options).unwrap()
+    }
+
+    fn found<'a>(m: &Matcher, line: &'a str) -> Vec<&'a str> {
+        m.find_iter(line).map(|r| &line[r]).collect()
+    }
+
+    const PLAIN: FindOptions = FindOptions { case_sensitive: false, whole_word: false, regex: false, ignore_diacritics: false };
+
+    #[test]
+    fn cyrillic_case_folding() {
+        let m = matcher("привет", PLAIN);
+        assert_eq!(found(&m, "Привет, ПРИВЕТ и приветствие"), ["Привет", "ПРИВЕТ", "привет"]);
+        let m = matcher("привет", FindOptions { whole_word: true, ..PLAIN });
+        assert_eq!(found(&m, "Привет, ПРИВЕТ и приветствие"), ["Привет", "ПРИВЕТ"]);
+        let m = matcher("привет", FindOptions { case_sensitive: true, ..PLAIN });
+        assert_eq!(found(&m, "Привет, привет"), ["привет"]);
+    }
+
+    #[test]
+    fn full_case_folding() {
+        let m = matcher("strasse", PLAIN);
+        assert_eq!(found(&m, "Straße STRASSE"), ["Straße", "STRASSE"]);
+        // Half of an expanded character marks the whole character once
+        let m = matcher("s", PLAIN);
+        assert_eq!(found(&m, "ß"), ["ß"]);
+        // Final and medial sigma fold alike
+        let m = matcher("σ", PLAIN);
+        assert_eq!(found(&m, "ΟΔΟΣ οδος"), ["Σ", "ς"]);
+    }
+
+    #[test]
+    fn turkish_dotted_i() {
+        // İ folds to i + combining dot above, so a plain "i" finds it and the
+        // whole two-byte character is marked
+        let m = matcher("i", PLAIN);
+        let line = "İstanbul";
+        assert_eq!(m.find_iter(line).collect::<Vec<_>>(), vec![0..2]);
+        let m = matcher("istanbul", PLAIN);
+        assert_eq!(found(&m, "İSTANBUL İstanbul istanbul"), ["istanbul"]);
+        let m = matcher("İSTANBUL", PLAIN);
+        assert_eq!(found(&m, "İSTANBUL İstanbul istanbul"), ["İSTANBUL", "İstanbul"]);
+        // The dot stays significant unless diacritics are ignored
+        let m = matcher("istanbul", FindOptions { ignore_diacritics: true, ..PLAIN });
+        assert_eq!(found(&m, "İSTANBUL İstanbul istanbul"), ["İSTANBUL", "İstanbul", "istanbul"]);
+        let m = matcher("İstanbul", PLAIN);
+        assert_eq!(found(&m, "İstanbul ISTANBUL"), ["İstanbul"]);
+        let m = matcher("İstanbul", FindOptions { ignore_diacritics: true, ..PLAIN });
+        assert_eq!(found(&m, "İstanbul ISTANBUL"), ["İstanbul", "ISTANBUL"]);
+        // Dotless ı is its own letter under default (non-Turkic) folding
+        let m = matcher("ı", PLAIN);
+        assert_eq!(found(&m, "I i ı"), ["ı"]);
+    }
+
+    #[test]
+    fn emoji_offsets() {
+        let line = "👍 ok 👨‍👩‍👧 OK 🎉ok";
+        let m = matcher("ok", PLAIN);
+        let ranges: Vec<_> = m.find_iter(line).collect();
+        assert_eq!(ranges.iter().map(|r| &line[r.clone()]).collect::<Vec<_>>(), ["ok", "OK", "ok"]);
+        assert!(ranges.iter().all(|r| line.is_char_boundary(r.start) && line.is_char_boundary(r.end)));
+        assert_eq!(ranges[0], 5..7);
+        let m = matcher("ok", FindOptions { whole_word: true, ..PLAIN });
+        assert_eq!(found(&m, line).len(), 3);
+        let m = matcher("🎉", PLAIN);
+        assert_eq!(found(&m, line), ["🎉"]);
+        let m = matcher("👨‍👩‍👧", PLAIN);
+        assert_eq!(found(&m, line), ["👨‍👩‍👧"]);
+    }
+
+    #[test]
+    fn ignore_diacritics() {
+        let options = FindOptions { ignore_diacritics: true, ..PLAIN };
+        let m = matcher("cafe", options);
+        // Precomposed é and e + U+0301: the accent belongs to the match
+        assert_eq!(found(&m, "Café café CAFÉ"), ["Café", "café", "CAFÉ"]);
+        let m = matcher("résumé", options);
+        assert_eq!(found(&m, "resume RÉSUMÉ"), ["resume", "RÉSUMÉ"]);
+        let m = matcher("е", options);
+        assert_eq!(found(&m, "Ёлка"), ["Ё"]);
+        // Case-sensitive still tells cases apart
+        let m = matcher("cafe", FindOptions { case_sensitive: true, ..options });
+        assert_eq!(found(&m, "Café café"), ["café"]);
+        // Without the option accents count
+        let m = matcher("cafe", PLAIN);
+        assert!(found(&m, "café").is_empty());
+    }
+
+    #[test]
+    fn regex_queries() {
+        let regex = FindOptions { regex: true, ..PLAIN };
+        let m = matcher(r"\w+ет", regex);
+        assert_eq!(found(&m, "ПРИВЕТ, привет"), ["ПРИВЕТ", "привет"]);
+        let m = matcher(r"caf\w\b", FindOptions { ignore_diacritics: true, ..regex });
+        assert_eq!(found(&m, "café cafe"), ["café", "cafe"]);
+        let m = matcher(r"x*", regex);
+        assert!(found(&m, "abc").is_empty());
+        assert!(Matcher::new("(", regex).is_err());
+    }
+
+    #[test]
+    fn counts_and_snippets() {
+        let m = matcher("ё", PLAIN);
+        assert_eq!(m.count("Ёж\nёлка\nпоезд"), 2);
+        // Snippet context is cut on character boundaries
+        let line = format!("{}needle{}", "ж".repeat(60), "🎉".repeat(30));
+        let results = matcher("NEEDLE", PLAIN).find_in(&line);
+        assert_eq!(results.len(), 1);
+        let (_, snippet, range) = &results[0];
+        assert_eq!(&snippet[range.clone()], "needle");
+    }
+}
@@ -1,7 +1,22 @@
 use std::fs;
 use std::path::PathBuf;
+use std::sync::OnceLock;
+
+static SETTINGS_PATH_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use `path` instead of the per-user config location (from `--settings`).
+pub(crate) fn set_settings_path_override(path: PathBuf) {
+    let _ = SETTINGS_PATH_OVERRIDE.set(path);
+}
+
+pub(crate) fn has_settings_path_override() -> bool {
+    SETTINGS_PATH_OVERRIDE.get().is_some()
+}
 
 pub(crate) fn settings_path() -> Option<PathBuf> {
+    if let Some(p) = SETTINGS_PATH_OVERRIDE.get() {
+        return Some(p.clone());
+    }
     directories::ProjectDirs::from("", "", "gfv")
         .map(|dirs| dirs.config_dir().join("settings.json"))
 }
@@ -0,0 +1,109 @@
+// SVG images: parsed with usvg and rasterized with resvg. The raster follows
+// the zoom; once zooming settles it is redrawn at the on-screen scale, so
+// edges stay crisp instead of being magnified from a fixed bitmap. Layout
+// uses the document's own size, whatever the raster resolution.
+
+use eframe::egui;
+use resvg::{tiny_skia, usvg};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+// How long the zoom has to hold still before the raster is redrawn
+const SETTLE: Duration = Duration::from_millis(150);
+// Scale changes smaller than this keep the current raster
+const RESCALE_TOLERANCE: f32 = 0.02;
+
+pub(crate) struct SvgDoc {
+    // Boxed; a tree is several hundred bytes and travels in loader messages
+    tree: Box<usvg::Tree>,
+}
+
+impl SvgDoc {
+    pub(crate) fn parse(path: &Path, bytes: &[u8]) -> Result<Self, String> {
+        // System fonts are loaded once, on the first SVG
+        static FONTS: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+        let fontdb = FONTS.get_or_init(|| {
+            let mut db = usvg::fontdb::Database::new();
+            db.load_system_fonts();
+            Arc::new(db)
+        });
+        let options = usvg::Options {
+            resources_dir: path.parent().map(Path::to_path_buf),
+            fontdb: fontdb.clone(),
+            ..Default::default()
+        };
+        let tree = usvg::Tree::from_data(bytes, &options).map_err(|e| format!("Failed to open SVG: {}", e))?;
+        Ok(Self { tree: Box::new(tree) })
+    }
+
+    /// Size of the document in SVG user units (CSS pixels).
+    pub(crate) fn size(&self) -> egui::Vec2 {
+        let size = self.tree.size();
+        egui::vec2(size.width(), size.height())
+    }
+
+    /// Largest scale whose raster stays within `max_side` and the texture budget.
+    pub(crate) fn max_scale(&self, max_side: usize) -> f32 {
+        let size = self.size().max(egui::Vec2::splat(1.0));
+        let by_side = max_side as f32 / size.x.max(size.y);
+        let by_bytes = (crate::io::MAX_IMAGE_TEXTURE_BYTES as f32 / 4.0 / (size.x * size.y)).sqrt();
+        by_side.min(by_bytes)
+    }
+
+    /// Rasterize at `scale` raster pixels per user unit.
+    pub(crate) fn render(&self, scale: f32) -> Result<egui::ColorImage, String> {
+        let size = (self.size() * scale).ceil().max(egui::Vec2::splat(1.0));
+        let mut pixmap = tiny_skia::Pixmap::new(size.x as u32, size.y as u32)
+            .ok_or_else(|| format!("SVG too large to render: {}x{}", size.x, size.y))?;
+        resvg::render(&self.tree, tiny_skia::Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+        Ok(egui::ColorImage::from_rgba_premultiplied([size.x as usize, size.y as usize], pixmap.data()))
+    }
+}
+
+/// The SVG on screen and the scale of its current raster.
+pub(crate) struct SvgView {
+    pub(crate) path: PathBuf,
+    doc: SvgDoc,
+    scale: f32,
+    // Scale asked for and since when, while waiting for the zoom to settle
+    wanted: Option<(f32, Instant)>,
+}
+
+impl SvgView {
+    pub(crate) fn new(path: PathBuf, doc: SvgDoc, scale: f32) -> Self {
+        Self { path, doc, scale, wanted: None }
+    }
+
+    pub(crate) fn size(&self) -> egui::Vec2 {
+        self.doc.size()
+    }
+
+    /// A new raster when `scale` differs from the current one and has held
+    /// for a moment; None while it is settling or nothing changed.
+    pub(crate) fn update(&mut self, ctx: &egui::Context, scale: f32) -> Option<egui::ColorImage> {
+        let max_side = ctx.input(|i| i.max_texture_side);
+        let scale = scale.min(self.doc.max_scale(max_side));
+        let close = |a: f32, b: f32| (a / b - 1.0).abs() < RESCALE_TOLERANCE;
+        if close(scale, self.scale) {
+            self.wanted = None;
+            return None;
+        }
+        let now = Instant::now();
+        let since = match self.wanted {
+            Some((wanted, since)) if close(scale, wanted) => since,
+            _ => {
+                self.wanted = Some((scale, now));
+                now
+            }
+        };
+        let left = SETTLE.saturating_sub(now - since);
+        if !left.is_zero() {
+            ctx.request_repaint_after(left);
+            return None;
+        }
+        self.wanted = None;
+        self.scale = scale;
+        self.doc.render(scale).ok()
+    }
+}
@@ -0,0 +1,324 @@
+// CSV/TSV table view: the document is parsed with the `csv` crate on a
+// worker thread after sniffing the delimiter and whether the first record is
+// a header. Sorting and per-column filters produce a list of row indices (the
+// view); drawing only touches the rows and columns inside the viewport.
+// Rendering lives in central.rs.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::thread;
+
+// Records looked at when sniffing the delimiter, header and column widths
+const SNIFF_RECORDS: usize = 50;
+pub(crate) const DELIMITERS: &[(u8, &str)] = &[(b',', "Comma"), (b'\t', "Tab"), (b';', "Semicolon"), (b'|', "Pipe")];
+// Column widths in characters
+const MIN_COLUMN_CHARS: f32 = 4.0;
+const MAX_AUTO_COLUMN_CHARS: f32 = 40.0;
+
+/// How a CSV/TSV file is shown.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum TableMode {
+    Text,
+    #[default]
+    Table,
+}
+
+// Filters and sort the view was built for
+type ViewKey = (Vec<String>, Option<(usize, bool)>);
+
+/// Parsed records plus sort, filter and column state for the document on screen.
+#[derive(Default)]
+pub(crate) struct TableState {
+    doc_id: Option<u64>,
+    pub(crate) delimiter: u8,
+    pub(crate) has_header: bool,
+    pub(crate) header: Vec<String>,
+    pub(crate) records: Vec<Vec<String>>,
+    pub(crate) error: Option<String>,
+    /// Column widths in characters, so they follow the zoom.
+    pub(crate) widths: Vec<f32>,
+    /// (column, ascending)
+    pub(crate) sort: Option<(usize, bool)>,
+    pub(crate) filters: Vec<String>,
+    /// Record indices shown, in display order.
+    view: Vec<usize>,
+    view_key: Option<ViewKey>,
+    // Cells holding Find matches in document order, with the index of their first match
+    match_cells: Vec<(usize, usize, usize)>,
+    match_total: usize,
+    match_query: Option<crate::search::Matcher>,
+    pub(crate) selected: Option<(usize, usize)>,
+    /// (query, match index) the view last scrolled to.
+    pub(crate) last_search_jump: Option<(crate::search::Matcher, usize)>,
+    // Parse running on a worker thread; dropping it discards the result
+    parsing: Option<Receiver<Parsed>>,
+    // Per column, built the first time it is sorted
+    sort_keys: Vec<Option<Vec<SortKey>>>,
+}
+
+// Result of a parse, applied on the UI thread
+struct Parsed {
+    delimiter: u8,
+    has_header: bool,
+    header: Vec<String>,
+    records: Vec<Vec<String>>,
+    widths: Vec<f32>,
+    error: Option<String>,
+}
+
+// Numbers sort numerically and before text; text case-insensitively
+#[derive(PartialEq, PartialOrd)]
+enum SortKey {
+    Number(f64),
+    Text(String),
+}
+
+impl SortKey {
+    fn new(cell: &str) -> Self {
+        match cell.trim().parse::<f64>() {
+            Ok(n) => Self::Number(n),
+            Err(_) => Self::Text(cell.to_lowercase()),
+        }
+    }
+}
+
+impl TableState {
+    /// Start parsing when the document changed, sniffing delimiter and
+    /// header, and take a finished parse.
+    pub(crate) fn sync(&mut self, doc: &crate::document::TextDoc, path: &Path, ctx: &egui::Context) {
+        if self.doc_id != Some(doc.id()) {
+            *self = Self { doc_id: Some(doc.id()), ..Default::default() };
+            self.start(doc, path, None, ctx);
+        }
+        let Some(rx) = &self.parsing else { return; };
+        match rx.try_recv() {
+            Ok(parsed) => self.apply(parsed),
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {}
+        }
+        self.parsing = None;
+    }
+
+    /// Parse again after the delimiter or header choice changed.
+    pub(crate) fn reparse(&mut self, doc: &crate::document::TextDoc, path: &Path, ctx: &egui::Context) {
+        self.start(doc, path, Some((self.delimiter, self.has_header)), ctx);
+    }
+
+    /// Whether a parse is still running; the records are stale until it ends.
+    pub(crate) fn is_parsing(&self) -> bool {
+        self.parsing.is_some()
+    }
+
+    fn start(&mut self, doc: &crate::document::TextDoc, path: &Path, choice: Option<(u8, bool)>, ctx: &egui::Context) {
+        let (tx, rx) = channel();
+        let (text, path, ctx) = (doc.shared_text(), path.to_path_buf(), ctx.clone());
+        thread::spawn(move || {
+            if tx.send(parse(&text, &path, choice)).is_ok() { ctx.request_repaint(); }
+        });
+        self.parsing = Some(rx);
+    }
+
+    fn apply(&mut self, parsed: Parsed) {
+        let columns = parsed.widths.len();
+        self.delimiter = parsed.delimiter;
+        self.has_header = parsed.has_header;
+        self.header = parsed.header;
+        self.records = parsed.records;
+        self.widths = parsed.widths;
+        self.error = parsed.error;
+        self.filters = vec![String::new(); columns];
+        self.sort = None;
+        self.sort_keys = Vec::new();
+        self.view_key = None;
+        self.match_query = None;
+        self.selected = None;
+    }
+
+    pub(crate) fn columns(&self) -> usize {
+        self.widths.len()
+    }
+
+    pub(crate) fn cell(&self, record: usize, column: usize) -> &str {
+        self.records.get(record).and_then(|r| r.get(column)).map(|s| s.as_str()).unwrap_or("")
+    }
+
+    pub(crate) fn resize(&mut self, column: usize, delta_chars: f32) {
+        if let Some(w) = self.widths.get_mut(column) {
+            *w = (*w + delta_chars).max(MIN_COLUMN_CHARS);
+        }
+    }
+
+    /// Click on a header: ascending, then descending, then unsorted.
+    pub(crate) fn cycle_sort(&mut self, column: usize) {
+        self.sort = match self.sort {
+            Some((c, true)) if c == column => Some((column, false)),
+            Some((c, false)) if c == column => None,
+            _ => Some((column, true)),
+        };
+    }
+
+    /// Record indices shown, in display order (see `refresh_view`).
+    pub(crate) fn view(&self) -> &[usize] {
+        &self.view
+    }
+
+    /// Recompute the shown rows if filters or sort changed.
+    pub(crate) fn refresh_view(&mut self) {
+        let key = (self.filters.clone(), self.sort);
+        if self.view_key.as_ref() != Some(&key) {
+            let filters: Vec<(usize, String)> = self.filters.iter().enumerate()
+                .filter(|(_, f)| !f.trim().is_empty())
+                .map(|(c, f)| (c, f.trim().to_lowercase()))
+                .collect();
+            let mut view: Vec<usize> = (0..self.records.len())
+                .filter(|&r| filters.iter().all(|(c, f)| self.cell(r, *c).to_lowercase().contains(f.as_str())))
+                .collect();
+            if let Some((column, ascending)) = self.sort {
+                let keys = self.sort_keys(column);
+                view.sort_by(|&a, &b| {
+                    let order = keys[a].partial_cmp(&keys[b]).unwrap_or(Ordering::Equal);
+                    if ascending { order } else { order.reverse() }
+                });
+            }
+            self.view = view;
+            self.view_key = Some(key);
+        }
+    }
+
+    fn sort_keys(&mut self, column: usize) -> &[SortKey] {
+        if self.sort_keys.len() <= column { self.sort_keys.resize_with(column + 1, || None); }
+        let records = &self.records;
+        self.sort_keys[column].get_or_insert_with(|| {
+            records.iter().map(|r| SortKey::new(r.get(column).map(|s| s.as_str()).unwrap_or(""))).collect()
+        })
+    }
+
+    /// Find matches in the record cells. The table view counts these rather
+    /// than those in the raw text, which also has the header and quoting.
+    pub(crate) fn match_count(&mut self, matcher: &crate::search::Matcher) -> usize {
+        self.index_matches(matcher);
+        self.match_total
+    }
+
+    /// Cell holding Find match number `index`, counting cells in document order.
+    pub(crate) fn match_cell(&mut self, matcher: &crate::search::Matcher, index: usize) -> Option<(usize, usize)> {
+        self.index_matches(matcher);
+        let at = self.match_cells.partition_point(|&(_, _, first)| first <= index).checked_sub(1)?;
+        let (r, c, _) = self.match_cells[at];
+        Some((r, c))
+    }
+
+    // Cells with matches of `matcher` and their total, kept until the query or text changes
+    fn index_matches(&mut self, matcher: &crate::search::Matcher) {
+        if self.match_query.as_ref() == Some(matcher) { return; }
+        self.match_cells.clear();
+        let mut total = 0;
+        for (r, record) in self.records.iter().enumerate() {
+            for (c, cell) in record.iter().enumerate() {
+                let n = matcher.count(cell);
+                if n > 0 {
+                    self.match_cells.push((r, c, total));
+                    total += n;
+                }
+            }
+        }
+        self.match_total = total;
+        self.match_query = Some(matcher.clone());
+    }
+}
+
+// Parse `text`, with the delimiter and header choice given or sniffed
+fn parse(text: &str, path: &Path, choice: Option<(u8, bool)>) -> Parsed {
+    let (delimiter, has_header) = choice.unwrap_or_else(|| {
+        let delimiter = sniff_delimiter(text, path);
+        let sample = read_records(text, delimiter, Some(SNIFF_RECORDS)).unwrap_or_default();
+        (delimiter, sniff_header(&sample))
+    });
+    let (mut records, error) = match read_records(text, delimiter, None) {
+        Ok(records) => (records, None),
+        Err(e) => (Vec::new(), Some(e)),
+    };
+    let columns = records.iter().map(|r| r.len()).max().unwrap_or(0);
+    let mut header = if has_header && !records.is_empty() { records.remove(0) } else { Vec::new() };
+    header.resize(columns, String::new());
+    for (i, name) in header.iter_mut().enumerate() {
+        if name.is_empty() { *name = column_name(i); }
+    }
+    let widths = (0..columns).map(|c| {
+        let longest = std::iter::once(&header[c])
+            .chain(records.iter().take(SNIFF_RECORDS).filter_map(|r| r.get(c)))
+            .map(|s| s.chars().count())
+            .max()
+            .unwrap_or(0);
+        // Room for the sort arrow next to the name
+        (longest as f32 + 3.0).clamp(MIN_COLUMN_CHARS, MAX_AUTO_COLUMN_CHARS)
+    }).collect();
+    Parsed { delimiter, has_header, header, records, widths, error }
+}
+
+fn read_records(text: &str, delimiter: u8, limit: Option<usize>) -> Result<Vec<Vec<String>>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let mut records = Vec::new();
+    for record in reader.records().take(limit.unwrap_or(usize::MAX)) {
+        let record = record.map_err(|e| format!("CSV parse error: {}", e))?;
+        records.push(record.iter().map(|s| s.to_string()).collect());
+    }
+    Ok(records)
+}
+
+// `.tsv` means tabs; otherwise the candidate giving the most columns with the
+// same count on every sampled record wins
+fn sniff_delimiter(text: &str, path: &Path) -> u8 {
+    if crate::filetype::syntax_hint(path, "") == "tsv" { return b'\t'; }
+    let mut best = (b',', 1usize);
+    for &(delimiter, _) in DELIMITERS {
+        let Ok(sample) = read_records(text, delimiter, Some(SNIFF_RECORDS)) else { continue; };
+        let Some(first) = sample.first().map(|r| r.len()) else { continue; };
+        let consistent = sample.iter().filter(|r| r.len() == first).count() * 10 >= sample.len() * 9;
+        if consistent && first > best.1 { best = (delimiter, first); }
+    }
+    best.0
+}
+
+// A header row has no empty, numeric or repeated cells, and differs from the
+// data: some column below it is numeric, or none of its names recur there
+fn sniff_header(sample: &[Vec<String>]) -> bool {
+    let Some((first, rest)) = sample.split_first() else { return false; };
+    if rest.is_empty() { return false; }
+    let names_ok = first.iter().all(|c| !c.trim().is_empty() && !is_number(c))
+        && first.iter().enumerate().all(|(i, c)| !first[..i].contains(c));
+    if !names_ok { return false; }
+    let numeric_column = (0..first.len()).any(|c| {
+        let values: Vec<&String> = rest.iter().filter_map(|r| r.get(c)).filter(|v| !v.trim().is_empty()).collect();
+        !values.is_empty() && values.iter().all(|v| is_number(v))
+    });
+    let names_recur = first.iter().enumerate().any(|(c, name)| rest.iter().any(|r| r.get(c) == Some(name)));
+    numeric_column || !names_recur
+}
+
+fn is_number(s: &str) -> bool {
+    s.trim().parse::<f64>().is_ok()
+}
+
+// Spreadsheet-style names for columns without a header: A, B, …, Z, AA, …
+fn column_name(mut i: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (i % 26) as u8);
+        if i < 26 { break; }
+        i = i / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+pub(crate) fn is_table(path: &Path) -> bool {
+    matches!(crate::filetype::syntax_hint(path, "").as_str(), "csv" | "tsv")
+}
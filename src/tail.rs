@@ -0,0 +1,86 @@
+// Follow mode (`tail -f`) for text tabs: read only the bytes appended since the
+// last poll, and start over when the file is truncated or replaced (rotation).
+
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+pub(crate) enum TailUpdate {
+    /// New text appended to the end of the file.
+    Appended(String),
+    /// The file was truncated or rotated; this is its full new content.
+    Reset(String),
+}
+
+pub(crate) struct FollowState {
+    offset: u64,
+    file_id: Option<(u64, u64)>,
+    // Trailing bytes of an incomplete UTF-8 sequence, kept for the next read
+    carry: Vec<u8>,
+}
+
+#[cfg(unix)]
+fn file_id(meta: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_meta: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+impl FollowState {
+    /// Start following from the current end of `path`.
+    pub(crate) fn start(path: &Path) -> std::io::Result<Self> {
+        let meta = fs::metadata(path)?;
+        Ok(Self { offset: meta.len(), file_id: file_id(&meta), carry: Vec::new() })
+    }
+
+    pub(crate) fn poll(&mut self, path: &Path) -> std::io::Result<Option<TailUpdate>> {
+        let meta = fs::metadata(path)?;
+        let id = file_id(&meta);
+        if id != self.file_id || meta.len() < self.offset {
+            let bytes = fs::read(path)?;
+            self.offset = bytes.len() as u64;
+            self.file_id = id;
+            self.carry.clear();
+            return Ok(Some(TailUpdate::Reset(String::from_utf8_lossy(&bytes).into_owned())));
+        }
+        if meta.len() == self.offset {
+            return Ok(None);
+        }
+
+        let mut f = File::open(path)?;
+        f.seek(SeekFrom::Start(self.offset))?;
+        let mut buf = std::mem::take(&mut self.carry);
+        let read = f.take(meta.len() - self.offset).read_to_end(&mut buf)?;
+        self.offset += read as u64;
+
+        // Hold back a split multi-byte character instead of emitting U+FFFD
+        let valid_up_to = match std::str::from_utf8(&buf) {
+            Ok(_) => buf.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => buf.len(),
+        };
+        self.carry = buf.split_off(valid_up_to);
+        if buf.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(TailUpdate::Appended(String::from_utf8_lossy(&buf).into_owned())))
+    }
+}
+
+/// `text.lines().count()` after appending `chunk`, without rescanning `text`.
+pub(crate) fn appended_line_count(text: &str, line_count: usize, chunk: &str) -> usize {
+    if chunk.is_empty() {
+        return line_count;
+    }
+    let added = chunk.lines().count();
+    if text.is_empty() || text.ends_with('\n') {
+        line_count + added
+    } else {
+        // The chunk's first line continues the old partial last line
+        line_count + added.saturating_sub(1)
+    }
+}
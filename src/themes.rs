@@ -1,7 +1,8 @@
 use eframe::egui::Color32;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum CodeTheme {
+    #[default]
     OneDarkPro,
     Dracula,
     MaterialTheme,
@@ -14,12 +15,6 @@ pub enum CodeTheme {
     Noctis,
 }
 
-impl Default for CodeTheme {
-    fn default() -> Self {
-        Self::OneDarkPro
-    }
-}
-
 impl CodeTheme {
     pub fn name(&self) -> &'static str {
         match self {
@@ -51,6 +46,14 @@ impl CodeTheme {
         ]
     }
 
+    /// Look up a theme by display name, ignoring case, spaces and punctuation
+    /// (so "night-owl", "NightOwl" and "Night Owl" all match).
+    pub fn from_name(name: &str) -> Option<Self> {
+        let key = |s: &str| -> String { s.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase() };
+        let wanted = key(name);
+        Self::all().iter().copied().find(|t| key(t.name()) == wanted)
+    }
+
     pub fn background(&self) -> Color32 {
         match self {
             Self::OneDarkPro => Color32::from_rgb(40, 44, 52),      // Dark gray-blue
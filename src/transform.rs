@@ -0,0 +1,138 @@
+// View transforms for the image viewer: pan, quarter-turn rotation and
+// mirroring. They are applied when painting, so the texture is never
+// rebuilt; saving a transformed copy re-decodes the file and bakes them in.
+
+use eframe::egui;
+use std::path::{Path, PathBuf};
+
+/// Pan and orientation of the image on screen, reset when another image is
+/// shown. The displayed image is the source mirrored (if `mirrored`), then
+/// turned clockwise `quarter_turns` times.
+#[derive(Default)]
+pub(crate) struct ImageView {
+    path: Option<PathBuf>,
+    /// Offset of the image centre from the view centre, in points.
+    pub(crate) pan: egui::Vec2,
+    quarter_turns: u8,
+    mirrored: bool,
+    // Zoom the pan was last laid out for, to keep the same spot centred when
+    // the zoom changes elsewhere (toolbar, keyboard)
+    zoom: f32,
+}
+
+impl ImageView {
+    pub(crate) fn sync(&mut self, path: Option<&Path>) {
+        if self.path.as_deref() != path {
+            *self = Self { path: path.map(Path::to_path_buf), ..Default::default() };
+        }
+    }
+
+    pub(crate) fn rotate(&mut self, clockwise: bool) {
+        self.quarter_turns = (self.quarter_turns + if clockwise { 1 } else { 3 }) % 4;
+    }
+
+    /// Mirror what is on screen left to right.
+    pub(crate) fn flip_horizontal(&mut self) {
+        // Mirroring after a turn equals mirroring first and turning the other way
+        self.quarter_turns = (4 - self.quarter_turns) % 4;
+        self.mirrored = !self.mirrored;
+    }
+
+    /// Mirror what is on screen top to bottom: a horizontal flip and a half turn.
+    pub(crate) fn flip_vertical(&mut self) {
+        self.flip_horizontal();
+        self.quarter_turns = (self.quarter_turns + 2) % 4;
+    }
+
+    pub(crate) fn reset_orientation(&mut self) {
+        self.quarter_turns = 0;
+        self.mirrored = false;
+    }
+
+    pub(crate) fn is_transformed(&self) -> bool {
+        self.quarter_turns != 0 || self.mirrored
+    }
+
+    /// Short description for the status bar.
+    pub(crate) fn describe(&self) -> String {
+        let turn = match self.quarter_turns {
+            1 => "↻ 90°",
+            2 => "↻ 180°",
+            3 => "↻ 270°",
+            _ => "",
+        };
+        match (turn.is_empty(), self.mirrored) {
+            (true, true) => "⇔ Mirrored".to_string(),
+            (false, true) => format!("{}, mirrored", turn),
+            _ => turn.to_string(),
+        }
+    }
+
+    /// Size of the image as displayed, in image pixels.
+    pub(crate) fn display_size(&self, [w, h]: [usize; 2]) -> [usize; 2] {
+        if self.quarter_turns % 2 == 1 { [h, w] } else { [w, h] }
+    }
+
+    /// Source pixel shown at `[x, y]` of the displayed image.
+    pub(crate) fn source_pixel(&self, [x, y]: [usize; 2], size: [usize; 2]) -> [usize; 2] {
+        let [mut x, mut y] = [x, y];
+        let [w, h] = self.display_size(size);
+        // Undo the clockwise turns one at a time; the width alternates
+        for turn in 0..self.quarter_turns {
+            let width = if turn % 2 == 0 { w } else { h };
+            (x, y) = (y, width - 1 - x);
+        }
+        if self.mirrored { x = size[0] - 1 - x; }
+        [x, y]
+    }
+
+    /// Texture coordinates for the displayed top-left, top-right,
+    /// bottom-right and bottom-left corners.
+    pub(crate) fn uv_corners(&self) -> [egui::Pos2; 4] {
+        let mut corners = [egui::pos2(0.0, 0.0), egui::pos2(1.0, 0.0), egui::pos2(1.0, 1.0), egui::pos2(0.0, 1.0)];
+        if self.mirrored { corners = [corners[1], corners[0], corners[3], corners[2]]; }
+        for _ in 0..self.quarter_turns {
+            corners = [corners[3], corners[0], corners[1], corners[2]];
+        }
+        corners
+    }
+
+    /// Screen rectangle of an image of `size` points at zoom 1, drawn in
+    /// `view` at `zoom`. Keeps the pan within the image's overhang.
+    pub(crate) fn layout(&mut self, view: egui::Rect, size: egui::Vec2, zoom: f32) -> egui::Rect {
+        if self.zoom > 0.0 && self.zoom != zoom { self.pan *= zoom / self.zoom; }
+        self.zoom = zoom;
+        let scaled = size * zoom;
+        let overhang = ((scaled - view.size()) / 2.0).max(egui::Vec2::ZERO);
+        self.pan = self.pan.clamp(-overhang, overhang);
+        egui::Rect::from_center_size(view.center() + self.pan, scaled)
+    }
+
+    /// Change the zoom from `from` to `to` keeping the point under `anchor`
+    /// (relative to the view centre) in place.
+    pub(crate) fn zoom_at(&mut self, from: f32, to: f32, anchor: egui::Vec2) {
+        self.pan = anchor - (anchor - self.pan) * (to / from);
+        self.zoom = to;
+    }
+
+    /// Apply the rotation and mirroring to a decoded image.
+    pub(crate) fn apply(&self, image: image::DynamicImage) -> image::DynamicImage {
+        let image = if self.mirrored { image.fliph() } else { image };
+        match self.quarter_turns {
+            1 => image.rotate90(),
+            2 => image.rotate180(),
+            3 => image.rotate270(),
+            _ => image,
+        }
+    }
+}
+
+/// Write `source` with the view's rotation and mirroring baked in to
+/// `target`, in the source's format.
+pub(crate) fn save_transformed(source: &Path, target: &Path, view: &ImageView) -> Result<(), String> {
+    let bytes = std::fs::read(source).map_err(|e| format!("Failed to read file: {}", e))?;
+    let format = crate::io::image_reader(source, &bytes)?.format().ok_or("Unknown image format")?;
+    view.apply(crate::io::decode_upright(source, &bytes)?)
+        .save_with_format(target, format)
+        .map_err(|e| format!("Failed to save {}: {}", target.display(), e))
+}
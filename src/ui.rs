@@ -27,11 +27,7 @@ pub(crate) fn toolbar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, _c
         let open_clicked = ui.add_enabled(!app.file_open_in_flight, open_button).clicked();
         if open_clicked {
             // Use blocking dialog here for reliability
-            if let Some(path) = rfd::FileDialog::new()
-                .add_filter("All Supported", &["txt","rs","py","toml","md","json","js","html","css","png","jpg","jpeg","gif","bmp","webp"])
-                .add_filter("Images", &["png","jpg","jpeg","gif","bmp","webp"])
-                .add_filter("Text/Source", &["txt","rs","py","toml","md","json","js","html","css"])
-                .pick_file() {
+            if let Some(path) = crate::filetype::file_dialog().pick_file() {
                 *file_to_load = Some(path);
             }
         }
@@ -65,13 +61,8 @@ pub(crate) fn toolbar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, _c
                 if p.exists() {
                     if idx == active_idx {
                         *file_to_load = Some(p.clone());
-                    } else if crate::io::is_supported_text(&p) {
-                        if let Ok((text, lossy, lines)) = crate::io::load_text(&p) {
-                            let exists = app.open_text_tabs.iter().any(|t| t.path == p);
-                            if !exists {
-                                app.open_text_tabs.push(crate::app::TextTab { path: p.clone(), text, is_lossy: lossy, line_count: lines });
-                            }
-                        }
+                    } else if crate::filetype::classify(&p) == crate::filetype::FileKind::Text {
+                        app.request_load(p.clone(), crate::loader::LoadPurpose::Open { activate: false, line: None, column: None }, ui.ctx());
                     }
                 }
             }
@@ -119,6 +110,7 @@ pub(crate) fn toolbar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, _c
             app.content = None;
             app.current_path = None;
             app.error_message = None;
+            app.clear_image_state();
         }
 
         // (global search was moved back to earlier position)
@@ -136,6 +128,21 @@ pub(crate) fn toolbar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, _c
         ui.horizontal(|ui| {
             ui.checkbox(&mut app.image_fit, RichText::new("📐 Fit to Window").strong());
             if app.image_fit != prev_fit { crate::settings::save_settings_to_disk(app); }
+            if ui.toggle_value(&mut app.show_image_info, RichText::new("ℹ Info").strong()).on_hover_text("Image metadata: EXIF, color profile, text chunks").changed() {
+                crate::settings::save_settings_to_disk(app);
+            }
+            if ui.toggle_value(&mut app.pixel_inspector, RichText::new("🎯 Inspect").strong()).on_hover_text("Show the pixel under the pointer; click to copy its color").changed() {
+                crate::settings::save_settings_to_disk(app);
+            }
+            if app.pixel_inspector {
+                let before = app.pixel_copy_format;
+                egui::ComboBox::from_id_salt("pixel_copy_format").selected_text(format!("Copy {}", before.label())).show_ui(ui, |ui| {
+                    for format in crate::inspector::ColorFormat::ALL {
+                        ui.selectable_value(&mut app.pixel_copy_format, format, format.label());
+                    }
+                });
+                if app.pixel_copy_format != before { crate::settings::save_settings_to_disk(app); }
+            }
         });
         
         ui.add_space(8.0);
@@ -144,13 +151,13 @@ pub(crate) fn toolbar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, _c
             zoom_out_button = zoom_out_button.fill(egui::Color32::from_rgb(245, 158, 11)); // Orange
             if ui.add(zoom_out_button).clicked() { 
                 app.image_fit = false; 
-                app.image_zoom = (app.image_zoom / 1.10).clamp(0.1, 6.0); 
+                app.image_zoom = (app.image_zoom / 1.10).clamp(0.1, crate::app::MAX_IMAGE_ZOOM); 
             }
             let mut zoom_in_button = egui::Button::new(RichText::new("🔍+").strong());
             zoom_in_button = zoom_in_button.fill(egui::Color32::from_rgb(245, 158, 11)); // Orange
             if ui.add(zoom_in_button).clicked() { 
                 app.image_fit = false; 
-                app.image_zoom = (app.image_zoom * 1.10).clamp(0.1, 6.0); 
+                app.image_zoom = (app.image_zoom * 1.10).clamp(0.1, crate::app::MAX_IMAGE_ZOOM); 
             }
             let mut reset_button = egui::Button::new(RichText::new("100%").strong());
             reset_button = reset_button.fill(egui::Color32::from_rgb(34, 197, 94)); // Green
@@ -159,6 +166,33 @@ pub(crate) fn toolbar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp, _c
                 app.image_zoom = 1.0; 
             }
         });
+
+        ui.add_space(8.0);
+        ui.horizontal(|ui| {
+            if ui.button(RichText::new("⟲").strong()).on_hover_text("Rotate left (Shift+R)").clicked() { app.image_view.rotate(false); }
+            if ui.button(RichText::new("⟳").strong()).on_hover_text("Rotate right (R)").clicked() { app.image_view.rotate(true); }
+            if ui.button(RichText::new("⇔").strong()).on_hover_text("Flip horizontally (H)").clicked() { app.image_view.flip_horizontal(); }
+            if ui.button(RichText::new("⇕").strong()).on_hover_text("Flip vertically (V)").clicked() { app.image_view.flip_vertical(); }
+            if app.image_view.is_transformed() {
+                if ui.button("↺ Reset").on_hover_text("Undo rotation and flips").clicked() { app.image_view.reset_orientation(); }
+                let still = app.shown_animation().is_none() && app.shown_svg().is_none();
+                if ui.add_enabled(still, egui::Button::new("💾 Save copy…"))
+                    .on_hover_text("Save the rotated/flipped image in its original format")
+                    .on_disabled_hover_text("Animated, multi-page and vector images cannot be saved transformed")
+                    .clicked() {
+                    app.save_transformed_copy();
+                }
+            }
+            // SVG is XML; show its source like "Open as text" in the hex view
+            if app.shown_svg().is_some() && app.current_path.as_deref().is_some_and(is_plain_svg) {
+                ui.add_space(12.0);
+                if ui.button("📝 Source").on_hover_text("Open the SVG markup as text").clicked()
+                    && let Some(path) = app.current_path.clone() {
+                    app.forced_text.insert(path.clone());
+                    app.load_file(path, ui.ctx());
+                }
+            }
+        });
     }
 }
 
@@ -188,49 +222,158 @@ pub(crate) fn search_bar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp,
                     }
                 }
 
-                if resp.changed() || (prev.is_empty() && !app.search_query.is_empty()) {
-                    app.search_count = 0;
-                    app.search_current = 0;
-                    if let Some(crate::app::Content::Text(ref text)) = app.content
-                        && !app.search_query.is_empty()
-                        && text.len() <= crate::app::HIGHLIGHT_CHAR_THRESHOLD {
-                        app.search_count = crate::search::recompute_count(&app.search_query, text);
-                    }
+                let before = app.find_options;
+                find_option_toggles(ui, &mut app.find_options);
+                if app.find_options != before { crate::settings::save_settings_to_disk(app); }
+                if resp.changed() || (prev.is_empty() && !app.search_query.is_empty()) || app.find_options != before {
+                    app.recount_find(ui.ctx());
+                }
+                if let Some(err) = app.find_error() {
+                    ui.colored_label(egui::Color32::from_rgb(239, 68, 68), err); // Red
                 }
             }
             
+            // Byte search and go-to-offset for the hex view
+            if let Some(crate::app::Content::Hex(ref doc)) = app.content {
+                ui.label(RichText::new("🔍 Find bytes:").strong());
+                ui.add_space(8.0);
+                let resp = ui.add(egui::TextEdit::singleline(&mut app.hex_view.pattern).hint_text("DE AD BE EF or text"));
+                if app.search_active {
+                    resp.request_focus();
+                    app.search_active = false;
+                }
+                let (enter, shift) = ui.input(|i| (i.key_pressed(egui::Key::Enter), i.modifiers.shift));
+                if resp.lost_focus() && enter {
+                    app.hex_view.find(doc, !shift);
+                    resp.request_focus();
+                }
+                if ui.small_button(RichText::new("⬅️").size(10.0)).on_hover_text("Previous match").clicked() {
+                    app.hex_view.find(doc, false);
+                }
+                if ui.small_button(RichText::new("➡️").size(10.0)).on_hover_text("Next match").clicked() {
+                    app.hex_view.find(doc, true);
+                }
+
+                ui.add_space(12.0);
+                ui.label(RichText::new("Go to:").strong());
+                let resp = ui.add(egui::TextEdit::singleline(&mut app.hex_view.goto).hint_text("0x1F40").desired_width(100.0));
+                if (resp.lost_focus() && enter) || ui.small_button("↵").on_hover_text("Go to offset").clicked() {
+                    app.hex_view.go_to(doc);
+                }
+                if let Some(msg) = &app.hex_view.message {
+                    ui.add_space(8.0);
+                    ui.colored_label(egui::Color32::from_rgb(245, 158, 11), msg); // Orange
+                }
+
+                // Decode as text anyway, for as long as the tab stays open
+                ui.add_space(12.0);
+                if ui.button(RichText::new("📝 Open as text").strong()).clicked()
+                    && let Some(path) = app.current_path.clone() {
+                    app.forced_text.insert(path.clone());
+                    app.load_file(path, ui.ctx());
+                }
+            }
+
+            // An SVG shown as its source can go back to the picture
+            if matches!(app.content, Some(crate::app::Content::Text(_)))
+                && let Some(path) = app.current_path.clone()
+                && is_plain_svg(&path)
+                && app.forced_text.contains(&path) {
+                ui.add_space(12.0);
+                if ui.button(RichText::new("🖼 View as image").strong()).clicked() {
+                    app.forced_text.remove(&path);
+                    app.load_file(path, ui.ctx());
+                }
+            }
+
+            // Follow (tail -f) toggle for the active text tab
+            if let Some(crate::app::Content::Text(ref doc)) = app.content
+                && doc.paged_file().is_none()
+                && let Some(idx) = app.active_text_tab {
+                ui.add_space(12.0);
+                let following = app.is_following_current();
+                if ui.selectable_label(following, RichText::new("⏬ Follow").strong())
+                    .on_hover_text("Follow the file as it grows (tail -f) and keep the view at the end")
+                    .clicked() {
+                    app.toggle_follow(idx, ui.ctx());
+                }
+            }
+
+            // Diff against another tab or a file on disk; images open the image compare
+            let comparable = match &app.content {
+                Some(crate::app::Content::Text(doc)) => doc.paged_file().is_none(),
+                Some(crate::app::Content::Image(_)) => true,
+                _ => false,
+            };
+            if comparable && let Some(path) = app.current_path.clone() {
+                ui.add_space(12.0);
+                let mut other = None;
+                ui.menu_button(RichText::new("⇄ Compare").strong(), |ui| other = compare_menu(ui, app, &path))
+                    .response.on_hover_text("Show the differences to another file");
+                if let Some(other) = other { app.compare_files(path, other, ui.ctx()); }
+            }
+
+            // Markdown files: source, rendered preview, or both
+            if let Some(crate::app::Content::Text(ref doc)) = app.content
+                && doc.paged_file().is_none()
+                && app.current_path.as_deref().is_some_and(crate::markdown::is_markdown) {
+                use crate::markdown::MarkdownMode;
+                ui.add_space(12.0);
+                let before = app.markdown_mode;
+                ui.selectable_value(&mut app.markdown_mode, MarkdownMode::Source, RichText::new("📝 Source").strong());
+                ui.selectable_value(&mut app.markdown_mode, MarkdownMode::Preview, RichText::new("👁 Preview").strong());
+                ui.selectable_value(&mut app.markdown_mode, MarkdownMode::SideBySide, RichText::new("◫ Side by side").strong());
+                if app.markdown_mode != before { crate::settings::save_settings_to_disk(app); }
+            }
+
+            // JSON files: structured tree or plain text
+            if let Some(crate::app::Content::Text(ref doc)) = app.content
+                && doc.paged_file().is_none()
+                && app.current_path.as_deref().is_some_and(crate::json::is_json) {
+                use crate::json::JsonMode;
+                ui.add_space(12.0);
+                let before = app.json_mode;
+                ui.selectable_value(&mut app.json_mode, JsonMode::Tree, RichText::new("🌳 Tree").strong());
+                ui.selectable_value(&mut app.json_mode, JsonMode::Text, RichText::new("📝 Text").strong());
+                if app.json_mode != before { crate::settings::save_settings_to_disk(app); }
+            }
+
+            // CSV/TSV files: table or plain text
+            if let Some(crate::app::Content::Text(ref doc)) = app.content
+                && doc.paged_file().is_none()
+                && app.current_path.as_deref().is_some_and(crate::table::is_table) {
+                use crate::table::TableMode;
+                ui.add_space(12.0);
+                let before = app.table_mode;
+                ui.selectable_value(&mut app.table_mode, TableMode::Table, RichText::new("▦ Table").strong());
+                ui.selectable_value(&mut app.table_mode, TableMode::Text, RichText::new("📝 Text").strong());
+                if app.table_mode != before { crate::settings::save_settings_to_disk(app); }
+            }
+
             // File navigation buttons (Prev/Next) - compact with just arrows
             if let Some(cur) = app.current_path.clone() {
                 ui.add_space(12.0);
-                match app.content {
-                    Some(crate::app::Content::Image(_)) => {
-                        if ui.small_button(RichText::new("⬅️").size(10.0)).on_hover_text("Previous file").clicked()
-                            && let Some(prev) = crate::io::neighbor_image(&cur, false) {
-                            *file_to_load = Some(prev);
-                        }
-                        if ui.small_button(RichText::new("➡️").size(10.0)).on_hover_text("Next file").clicked()
-                            && let Some(next) = crate::io::neighbor_image(&cur, true) {
-                            *file_to_load = Some(next);
-                        }
+                if let Some(kind) = app.content.as_ref().map(|c| c.kind()) {
+                    if ui.small_button(RichText::new("⬅️").size(10.0)).on_hover_text("Previous file").clicked()
+                        && let Some(prev) = crate::filetype::neighbor(&cur, kind, false) {
+                        *file_to_load = Some(prev);
                     }
-                    Some(crate::app::Content::Text(_)) => {
-                        if ui.small_button(RichText::new("⬅️").size(10.0)).on_hover_text("Previous file").clicked()
-                            && let Some(prev) = crate::io::neighbor_text(&cur, false) {
-                            *file_to_load = Some(prev);
-                        }
-                        if ui.small_button(RichText::new("➡️").size(10.0)).on_hover_text("Next file").clicked()
-                            && let Some(next) = crate::io::neighbor_text(&cur, true) {
-                            *file_to_load = Some(next);
-                        }
+                    if ui.small_button(RichText::new("➡️").size(10.0)).on_hover_text("Next file").clicked()
+                        && let Some(next) = crate::filetype::neighbor(&cur, kind, true) {
+                        *file_to_load = Some(next);
                     }
-                    _ => {}
                 }
             }
             
             // Search result navigation (only for text files with search query)
             if matches!(app.content, Some(crate::app::Content::Text(_))) && !app.search_query.is_empty() {
                 ui.add_space(12.0);
-                ui.label(RichText::new(format!("{} match(es)", app.search_count)).weak());
+                if app.search_counting {
+                    ui.add(egui::Spinner::new());
+                    ui.label(RichText::new("Counting…").weak());
+                } else {
+                    ui.label(RichText::new(format!("{} match(es)", app.search_count)).weak());
+                }
                 ui.add_space(8.0);
                 
                 if ui.small_button(RichText::new("⬅️").size(10.0)).on_hover_text("Previous match").clicked() && app.search_count > 0 {
@@ -262,7 +405,7 @@ pub(crate) fn status_bar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp)
                 
                 if let Ok(metadata) = fs::metadata(path) {
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        ui.label(RichText::new(format!("({:.1} KB)", metadata.len() as f64 / 1024.0)).weak());
+                        ui.label(RichText::new(format!("({})", format_size(metadata.len()))).weak());
                     });
                 }
                 
@@ -289,6 +432,19 @@ pub(crate) fn status_bar(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp)
     });
 }
 
+// File size in KB, MB or GB, whichever reads best
+fn format_size(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    let bytes = bytes as f64;
+    if bytes >= KB * KB * KB {
+        format!("{:.1} GB", bytes / (KB * KB * KB))
+    } else if bytes >= KB * KB {
+        format!("{:.1} MB", bytes / (KB * KB))
+    } else {
+        format!("{:.1} KB", bytes / KB)
+    }
+}
+
 pub(crate) fn status_extra(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) {
     // Modern status extra with icons and better formatting
     egui::Frame::group(ui.style()).show(ui, |ui| {
@@ -298,7 +454,10 @@ pub(crate) fn status_extra(ui: &mut egui::Ui, app: &mut crate::app::FileViewerAp
                     let size = texture.size();
                     ui.colored_label(egui::Color32::from_rgb(34, 197, 94), RichText::new("🖼️").size(16.0)); // Green
                     ui.add_space(8.0);
-                    ui.label(RichText::new(format!("{}x{} px", size[0], size[1])).strong());
+                    match app.shown_svg().map(|svg| svg.size()) {
+                        Some(doc) => ui.label(RichText::new(format!("{:.0}x{:.0} SVG", doc.x, doc.y)).strong()),
+                        None => ui.label(RichText::new(format!("{}x{} px", size[0], size[1])).strong()),
+                    };
                     
                     let eff = if app.image_fit { None } else { Some(app.image_zoom) };
                     if let Some(z) = eff { 
@@ -306,7 +465,9 @@ pub(crate) fn status_extra(ui: &mut egui::Ui, app: &mut crate::app::FileViewerAp
                         ui.colored_label(egui::Color32::from_rgb(245, 158, 11), RichText::new(format!("🔍 {:.0}%", z * 100.0))); // Orange
                     }
                     
-                    let est = size[0].saturating_mul(size[1]).saturating_mul(4);
+                    // Animations hold one texture per frame
+                    let frames = app.shown_animation().map(|a| a.frame_count()).unwrap_or(1);
+                    let est = size[0].saturating_mul(size[1]).saturating_mul(4).saturating_mul(frames);
                     ui.add_space(12.0);
                     ui.colored_label(egui::Color32::from_rgb(59, 130, 246), RichText::new(format!("💾 ~{:.1} MB", est as f64 / (1024.0 * 1024.0)))); // Blue
                     
@@ -314,22 +475,59 @@ pub(crate) fn status_extra(ui: &mut egui::Ui, app: &mut crate::app::FileViewerAp
                         ui.add_space(12.0);
                         ui.colored_label(egui::Color32::from_rgb(168, 85, 247), RichText::new("📐 Fit: on")); // Purple
                     }
+                    if let Some(animation) = app.shown_animation() && !animation.pages().is_empty() {
+                        ui.add_space(12.0);
+                        let page = &animation.pages()[animation.current];
+                        ui.colored_label(egui::Color32::from_rgb(34, 197, 94), RichText::new(format!("📄 Page {}/{} ({})", animation.current + 1, animation.frame_count(), page))); // Green
+                    } else if let Some(animation) = app.shown_animation() {
+                        ui.add_space(12.0);
+                        let state = if animation.playing { "▶" } else { "⏸" };
+                        ui.colored_label(egui::Color32::from_rgb(34, 197, 94), RichText::new(format!("🎞 {} Frame {}/{} ({} ms)", state, animation.current + 1, animation.frame_count(), animation.delay().as_millis()))); // Green
+                    }
+                    if app.image_view.is_transformed() {
+                        ui.add_space(12.0);
+                        ui.colored_label(egui::Color32::from_rgb(168, 85, 247), RichText::new(app.image_view.describe())); // Purple
+                    }
+                    if app.pixel_inspector && let Some(([x, y], color)) = app.inspector.hovered {
+                        ui.add_space(12.0);
+                        ui.colored_label(egui::Color32::from_rgb(20, 184, 166), RichText::new(format!("📍 {}, {}  {}", x, y, crate::inspector::ColorFormat::Hex.format(color))).monospace()); // Teal
+                    }
+                    disk_status(ui, app);
                 }
-                Some(crate::app::Content::Text(_)) => {
+                Some(crate::app::Content::Text(doc)) => {
+                    let paged = doc.paged_file().cloned();
                     ui.colored_label(egui::Color32::from_rgb(34, 197, 94), RichText::new("📝").size(16.0)); // Green
                     ui.add_space(8.0);
-                    ui.label(RichText::new(format!("{} lines", app.text_line_count)).strong());
+                    // Paged files count lines as the background index grows
+                    let lines = if paged.is_some() { doc.line_count() } else { app.text_line_count };
+                    ui.label(RichText::new(format!("{} lines", lines)).strong());
                     ui.add_space(12.0);
                     ui.colored_label(egui::Color32::from_rgb(245, 158, 11), RichText::new(format!("🔍 {:.0}%", app.text_zoom * 100.0))); // Orange
                     
-                    if app.text_is_big { 
+                    if let Some(paged) = paged {
                         ui.add_space(12.0);
-                        ui.colored_label(egui::Color32::from_rgb(239, 68, 68), RichText::new("⚠️ Large file: reduced features")); // Red
+                        ui.colored_label(egui::Color32::from_rgb(59, 130, 246), RichText::new(format!("📄 Paged, read-only ({})", format_size(paged.len())))) // Blue
+                            .on_hover_text("Read on demand: Find highlights matches in visible lines only");
+                        if !paged.is_indexed() {
+                            ui.add_space(12.0);
+                            ui.add(egui::ProgressBar::new(paged.progress()).desired_width(120.0).show_percentage().text("Indexing lines"));
+                        }
+                    } else {
+                        encoding_menu(ui, app);
                     }
-                    if app.text_is_lossy { 
+                    disk_status(ui, app);
+                }
+                Some(crate::app::Content::Hex(doc)) => {
+                    ui.colored_label(egui::Color32::from_rgb(34, 197, 94), RichText::new("🔢").size(16.0)); // Green
+                    ui.add_space(8.0);
+                    ui.label(RichText::new(format!("{} bytes", doc.len())).strong());
+                    ui.add_space(12.0);
+                    ui.colored_label(egui::Color32::from_rgb(245, 158, 11), RichText::new(format!("🔍 {:.0}%", app.text_zoom * 100.0))); // Orange
+                    if let Some((start, end)) = app.hex_view.selection() {
                         ui.add_space(12.0);
-                        ui.colored_label(egui::Color32::from_rgb(239, 68, 68), RichText::new("⚠️ UTF-8 (lossy)")); // Red
+                        ui.colored_label(egui::Color32::from_rgb(59, 130, 246), RichText::new(format!("📍 0x{:X}–0x{:X} ({} bytes)", start, end, end - start + 1))); // Blue
                     }
+                    disk_status(ui, app);
                 }
                 _ => {}
             }
@@ -337,6 +535,55 @@ pub(crate) fn status_extra(ui: &mut egui::Ui, app: &mut crate::app::FileViewerAp
     });
 }
 
+// Encoding of the active text tab; the menu re-decodes it with another one.
+// Paged tabs are always read as UTF-8, so they get no menu.
+fn encoding_menu(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) {
+    let Some(tab) = app.active_text_tab.and_then(|i| app.open_text_tabs.get(i)).filter(|t| t.paged.is_none()) else { return; };
+    let (path, current) = (tab.path.clone(), tab.encoding);
+    let forced = app.encoding_for(&path).is_some();
+    let mut choice: Option<Option<&'static encoding_rs::Encoding>> = None;
+    ui.add_space(12.0);
+    let caption = if forced { format!("🔤 {} (manual)", current.name()) } else { format!("🔤 {}", current.name()) };
+    ui.menu_button(RichText::new(caption).strong(), |ui| {
+        ui.label(RichText::new("Reopen with encoding…").strong());
+        ui.separator();
+        if ui.selectable_label(!forced, "Auto-detect").clicked() {
+            choice = Some(None);
+            ui.close_menu();
+        }
+        egui::ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+            for enc in crate::encoding::CHOICES {
+                if ui.selectable_label(forced && *enc == current, enc.name()).clicked() {
+                    choice = Some(Some(*enc));
+                    ui.close_menu();
+                }
+            }
+        });
+    });
+    if app.text_is_lossy {
+        ui.add_space(12.0);
+        ui.colored_label(egui::Color32::from_rgb(239, 68, 68), RichText::new(format!("⚠️ {} (lossy)", current.name()))) // Red
+            .on_hover_text("Some bytes are not valid in this encoding; try Reopen with encoding…");
+    }
+    if let Some(enc) = choice { app.reopen_with_encoding(path, enc, ui.ctx()); }
+}
+
+// "Deleted" / "Changed on disk" notice for the file on screen
+fn disk_status(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) {
+    let Some(path) = app.current_path.clone() else { return; };
+    if app.missing_on_disk.contains(&path) {
+        ui.add_space(12.0);
+        ui.colored_label(egui::Color32::from_rgb(239, 68, 68), RichText::new("⚠️ Deleted on disk")); // Red
+    } else if app.changed_on_disk.contains(&path) {
+        ui.add_space(12.0);
+        ui.colored_label(egui::Color32::from_rgb(245, 158, 11), RichText::new("● Changed on disk")); // Orange
+        if ui.small_button("⟳ Reload").clicked() {
+            let ctx = ui.ctx().clone();
+            app.reload_path(&path, &ctx);
+        }
+    }
+}
+
 pub(crate) fn tab_strip(ctx: &egui::Context, app: &mut crate::app::FileViewerApp) {
     egui::TopBottomPanel::top("tabstrip").show(ctx, |ui| {
         egui::ScrollArea::horizontal().auto_shrink([false, true]).show(ui, |ui| {
@@ -344,6 +591,8 @@ pub(crate) fn tab_strip(ctx: &egui::Context, app: &mut crate::app::FileViewerApp
             let mut text_to_close: Option<usize> = None;
             let mut img_to_switch: Option<usize> = None;
             let mut img_to_close: Option<usize> = None;
+            let mut disk_action: Option<(PathBuf, DiskAction)> = None;
+            let mut load_to_cancel: Option<PathBuf> = None;
             ui.horizontal(|ui| {
                 // Text tabs first
                 for (idx, tab) in app.open_text_tabs.iter().enumerate() {
@@ -353,7 +602,9 @@ pub(crate) fn tab_strip(ctx: &egui::Context, app: &mut crate::app::FileViewerApp
                     if is_active { frame = frame.fill(egui::Color32::from_rgb(30, 41, 59)); }
                     frame.show(ui, |ui| {
                         ui.horizontal(|ui| {
-                            if ui.selectable_label(is_active, egui::RichText::new(file_name).monospace()).clicked() { text_to_switch = Some(idx); }
+                            let resp = ui.selectable_label(is_active, tab_title(app, &tab.path, file_name).monospace());
+                            if resp.clicked() { text_to_switch = Some(idx); }
+                            disk_context_menu(app, &tab.path, &resp, &mut disk_action);
                             if ui.small_button("✕").on_hover_text("Close tab").clicked() { text_to_close = Some(idx); }
                         });
                     });
@@ -366,18 +617,46 @@ pub(crate) fn tab_strip(ctx: &egui::Context, app: &mut crate::app::FileViewerApp
                     if is_active { frame = frame.fill(egui::Color32::from_rgb(30, 41, 59)); }
                     frame.show(ui, |ui| {
                         ui.horizontal(|ui| {
-                            if ui.selectable_label(is_active, egui::RichText::new(file_name)).clicked() { img_to_switch = Some(idx); }
+                            let resp = ui.selectable_label(is_active, tab_title(app, path, file_name));
+                            if resp.clicked() { img_to_switch = Some(idx); }
+                            disk_context_menu(app, path, &resp, &mut disk_action);
                             if ui.small_button("✕").on_hover_text("Close image tab").clicked() { img_to_close = Some(idx); }
                         });
                     });
                 }
+                // Placeholders for files still loading; closing one cancels its load
+                for pending in app.pending_loads() {
+                    let crate::loader::LoadPurpose::Open { activate, .. } = pending.purpose else { continue; };
+                    if app.open_text_tabs.iter().any(|t| t.path == pending.path) || app.open_image_tabs.contains(&pending.path) { continue; }
+                    let file_name = pending.path.file_name().and_then(|s| s.to_str()).unwrap_or("(untitled)");
+                    egui::Frame::group(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.add(egui::Spinner::new().size(12.0));
+                            let caption = format!("{} {:.0}%", file_name, pending.progress * 100.0);
+                            let label = ui.label(RichText::new(caption).weak());
+                            if activate { label.on_hover_text("Loading; opens when ready"); }
+                            if ui.small_button("✕").on_hover_text("Cancel loading").clicked() { load_to_cancel = Some(pending.path.clone()); }
+                        });
+                    });
+                }
             });
+            if let Some(path) = load_to_cancel { app.cancel_load(&path); }
+            match disk_action {
+                Some((path, DiskAction::ToggleAutoReload)) => app.toggle_auto_reload(path, ctx),
+                Some((path, DiskAction::Reload)) => app.reload_path(&path, ctx),
+                Some((path, DiskAction::CompareWith(other))) => app.compare_files(path, other, ctx),
+                None => {}
+            }
             if let Some(idx) = text_to_switch { app.switch_to_text_tab(idx); }
             if let Some(idx) = text_to_close {
                 // Remove the tab and update active/content
                 let was_active = app.active_text_tab == Some(idx);
                 if idx < app.open_text_tabs.len() {
-                    app.open_text_tabs.remove(idx);
+                    let closed = app.open_text_tabs.remove(idx);
+                    app.following.remove(&closed.path);
+                    app.forced_text.remove(&closed.path);
+                    app.pending_jumps.remove(&closed.path);
+                    app.cancel_load(&closed.path);
                 }
                 if app.open_text_tabs.is_empty() {
                     app.active_text_tab = None;
@@ -397,8 +676,10 @@ pub(crate) fn tab_strip(ctx: &egui::Context, app: &mut crate::app::FileViewerApp
                     }
                 }
             }
-            if let Some(idx) = img_to_switch {
-                if let Some(p) = app.open_image_tabs.get(idx).cloned() { app.active_image_tab = Some(idx); ctx.memory_mut(|m| m.request_focus(egui::Id::new("central"))); }
+            if let Some(idx) = img_to_switch
+                && idx < app.open_image_tabs.len() {
+                app.active_image_tab = Some(idx);
+                ctx.memory_mut(|m| m.request_focus(egui::Id::new("central")));
             }
             if let Some(idx) = img_to_close {
                 if idx < app.open_image_tabs.len() { app.open_image_tabs.remove(idx); }
@@ -406,12 +687,89 @@ pub(crate) fn tab_strip(ctx: &egui::Context, app: &mut crate::app::FileViewerApp
                     app.content = None;
                     app.current_path = None;
                     app.active_image_tab = None;
-                } else if let Some(a) = app.active_image_tab { if a > idx { app.active_image_tab = Some(a - 1); } }
+                } else if let Some(a) = app.active_image_tab && a > idx { app.active_image_tab = Some(a - 1); }
             }
         });
     });
 }
 
+enum DiskAction {
+    ToggleAutoReload,
+    Reload,
+    CompareWith(PathBuf),
+}
+
+// Menu entries picking the file to compare `path` with: another open tab
+// of the same kind, or any file through the file dialog
+fn compare_menu(ui: &mut egui::Ui, app: &crate::app::FileViewerApp, path: &std::path::Path) -> Option<PathBuf> {
+    let mut picked = None;
+    let candidates: Vec<&PathBuf> = if app.open_image_tabs.iter().any(|p| p == path) {
+        app.open_image_tabs.iter().filter(|p| *p != path).collect()
+    } else {
+        app.open_text_tabs.iter().filter(|t| t.path != path && t.paged.is_none()).map(|t| &t.path).collect()
+    };
+    for candidate in candidates {
+        let name = candidate.file_name().and_then(|s| s.to_str()).unwrap_or("(untitled)");
+        if ui.button(RichText::new(name).monospace()).on_hover_text(candidate.to_string_lossy()).clicked() {
+            picked = Some(candidate.clone());
+            ui.close_menu();
+        }
+    }
+    if ui.button("📂 File on disk…").clicked() {
+        picked = crate::filetype::file_dialog().pick_file();
+        ui.close_menu();
+    }
+    picked
+}
+
+// .svg files, whose bytes are readable markup (unlike gzipped .svgz)
+fn is_plain_svg(path: &std::path::Path) -> bool {
+    path.extension().and_then(|s| s.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("svg"))
+}
+
+// Tab caption with a marker for files deleted or changed on disk
+fn tab_title(app: &crate::app::FileViewerApp, path: &std::path::Path, file_name: &str) -> RichText {
+    if app.missing_on_disk.contains(path) {
+        RichText::new(format!("⚠ {}", file_name)).strikethrough().color(egui::Color32::from_rgb(239, 68, 68))
+    } else if app.changed_on_disk.contains(path) {
+        RichText::new(format!("● {}", file_name)).color(egui::Color32::from_rgb(245, 158, 11))
+    } else if app.following.contains_key(path) {
+        RichText::new(format!("⏬ {}", file_name))
+    } else {
+        RichText::new(file_name)
+    }
+}
+
+fn disk_context_menu(app: &crate::app::FileViewerApp, path: &std::path::Path, resp: &egui::Response, action: &mut Option<(PathBuf, DiskAction)>) {
+    let resp = if app.missing_on_disk.contains(path) {
+        resp.clone().on_hover_text("Deleted on disk")
+    } else if app.changed_on_disk.contains(path) {
+        resp.clone().on_hover_text("Changed on disk (auto-reload off)")
+    } else {
+        resp.clone()
+    };
+    resp.context_menu(|ui| {
+        let mut auto = !app.no_auto_reload.contains(path);
+        if ui.checkbox(&mut auto, "Auto-reload when changed on disk").changed() {
+            *action = Some((path.to_path_buf(), DiskAction::ToggleAutoReload));
+        }
+        if ui.add_enabled(path.exists(), egui::Button::new("⟳ Reload from disk")).clicked() {
+            *action = Some((path.to_path_buf(), DiskAction::Reload));
+            ui.close_menu();
+        }
+        if app.open_text_tabs.iter().any(|t| t.path == path && t.paged.is_none()) || app.open_image_tabs.iter().any(|p| p == path) {
+            ui.menu_button("⇄ Compare with", |ui| {
+                if let Some(other) = compare_menu(ui, app, path) {
+                    *action = Some((path.to_path_buf(), DiskAction::CompareWith(other)));
+                }
+            });
+        }
+        if app.watcher.as_ref().is_some_and(|w| w.polling) {
+            ui.label(RichText::new("Watching by polling (no inotify)").weak().small());
+        }
+    });
+}
+
 pub(crate) fn recent_files_window(ctx: &egui::Context, app: &mut crate::app::FileViewerApp, file_to_load: &mut Option<PathBuf>) {
     if !app.show_recent_window { return; }
     let mut open_flag = app.show_recent_window;
@@ -445,13 +803,8 @@ pub(crate) fn recent_files_window(ctx: &egui::Context, app: &mut crate::app::Fil
                     if p.exists() {
                         if idx == active_idx {
                             *file_to_load = Some(p.clone());
-                        } else if crate::io::is_supported_text(&p) {
-                            if let Ok((text, lossy, lines)) = crate::io::load_text(&p) {
-                                let exists = app.open_text_tabs.iter().any(|t| t.path == p);
-                                if !exists {
-                                    app.open_text_tabs.push(crate::app::TextTab { path: p.clone(), text, is_lossy: lossy, line_count: lines });
-                                }
-                            }
+                        } else if crate::filetype::classify(&p) == crate::filetype::FileKind::Text {
+                            app.request_load(p.clone(), crate::loader::LoadPurpose::Open { activate: false, line: None, column: None }, ui.ctx());
                         }
                     }
                 }
@@ -469,6 +822,20 @@ pub(crate) fn recent_files_window(ctx: &egui::Context, app: &mut crate::app::Fil
     app.show_recent_window = open_flag;
 }
 
+// Match case, whole word, regex and diacritic switches for the Find bar
+fn find_option_toggles(ui: &mut egui::Ui, options: &mut crate::search::FindOptions) {
+    for (on, label, hover) in [
+        (&mut options.case_sensitive, "Aa", "Match case"),
+        (&mut options.whole_word, "Ab|", "Whole word"),
+        (&mut options.regex, ".*", "Regular expression"),
+        (&mut options.ignore_diacritics, "é=e", "Ignore diacritics (accents)"),
+    ] {
+        if ui.selectable_label(*on, RichText::new(label).monospace()).on_hover_text(hover).clicked() {
+            *on = !*on;
+        }
+    }
+}
+
 pub(crate) fn global_search_window(ctx: &egui::Context, app: &mut crate::app::FileViewerApp) {
     if !app.show_global_search_window { return; }
     let mut open_flag = app.show_global_search_window;
@@ -481,54 +848,167 @@ pub(crate) fn global_search_window(ctx: &egui::Context, app: &mut crate::app::Fi
             ui.vertical(|ui| {
                 ui.horizontal(|ui| {
                     let resp = ui.text_edit_singleline(&mut app.global_query);
-                    if resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
-                        app.recompute_global_search();
+                    // Taken here so it does not also open the selected result
+                    if resp.lost_focus() && ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Enter)) {
+                        app.recompute_global_search(ctx);
                     }
                     if ui.button(RichText::new("Search").strong()).clicked() {
-                        app.recompute_global_search();
+                        app.recompute_global_search(ctx);
                     }
                 });
                 ui.horizontal(|ui| {
-                    ui.checkbox(&mut app.global_case_sensitive, "Case sensitive");
-                    let ww = ui.checkbox(&mut app.global_whole_word, "Whole word");
-                    if app.global_regex {
-                        // Visually disable by graying out when regex mode is on
-                        // and force it off to avoid confusion
-                        app.global_whole_word = false;
-                        ww.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Checkbox, false, "Whole word (disabled in regex)"));
-                    }
-                    ui.checkbox(&mut app.global_regex, "Regex");
+                    ui.label("Search in:");
+                    ui.radio_value(&mut app.global_in_folder, false, "Open tabs");
+                    ui.radio_value(&mut app.global_in_folder, true, "Folder");
+                });
+                if app.global_in_folder { folder_search_options(ui, app); }
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut app.global_options.case_sensitive, "Case sensitive");
+                    ui.checkbox(&mut app.global_options.whole_word, "Whole word");
+                    ui.checkbox(&mut app.global_options.regex, "Regex");
+                    ui.checkbox(&mut app.global_options.ignore_diacritics, "Ignore diacritics");
                 });
                 if let Some(err) = &app.global_error { ui.colored_label(egui::Color32::RED, err); }
-                ui.separator();
-                egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
-                    for (idx, res) in app.global_results.clone().into_iter().enumerate() {
-                        let label = format!("{}:{} — {}", res.path.to_string_lossy(), res.line_index + 1, res.snippet);
-                        if ui.selectable_label(false, egui::RichText::new(label).monospace()).clicked() {
-                            // Switch to tab
-                            app.switch_to_text_tab(res.tab_index);
-                            // Set local search state and jump
-                            app.search_query = app.global_query.clone();
-                            if let Some(active) = app.active_text_tab
-                                && let Some(tab) = app.open_text_tabs.get(active) {
-                                app.search_count = crate::search::recompute_count(&app.search_query, &tab.text);
-                            }
-                            app.search_current = res.match_index_in_tab.min(app.search_count.saturating_sub(1));
-                            app.scroll_target_line = Some(res.line_index);
-                            // Close window
-                            app.show_global_search_window = false;
+                if let Some(search) = &mut app.folder_search {
+                    ui.horizontal(|ui| {
+                        let counts = format!("{} matches in {} files scanned", app.global_results.len(), search.files_scanned());
+                        if search.done {
+                            ui.label(RichText::new(counts).weak());
+                        } else {
+                            ui.add(egui::Spinner::new());
+                            ui.label(format!("Searching… {}", counts));
+                            if ui.button("⏹ Stop").clicked() { search.stop(); }
                         }
-                        if idx < app.global_results.len().saturating_sub(1) { ui.separator(); }
-                    }
-                    if app.global_results.is_empty() && !app.global_query.is_empty() {
-                        ui.label(RichText::new("No results").weak());
-                    }
-                });
+                        if search.truncated {
+                            ui.colored_label(egui::Color32::from_rgb(245, 158, 11), format!("Stopped at {} matches", crate::folder_search::MAX_RESULTS)); // Orange
+                        }
+                    });
+                } else if !app.global_results.is_empty() {
+                    ui.label(RichText::new(format!("{} matches in {} files", app.global_results.len(), app.global_results.file_count())).weak());
+                }
+                ui.separator();
+                let searching = app.folder_search.as_ref().is_some_and(|s| !s.done);
+                if app.global_results.is_empty() && !app.global_query.is_empty() && !searching {
+                    ui.label(RichText::new("No results").weak());
+                }
+                global_search_results(ui, app);
             });
         });
     app.show_global_search_window = open_flag;
 }
 
+// Results list, drawn only for the rows in view: a collapsible header per
+// file with its match count, then one line per match with the match marked.
+// Up/Down step through the matches and Enter opens the selected one.
+fn global_search_results(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) {
+    let font = egui::TextStyle::Monospace.resolve(ui.style());
+    let row_height = ui.fonts(|f| f.row_height(&font)) + 6.0;
+    let row_step = row_height + ui.spacing().item_spacing.y;
+
+    let mut area = egui::ScrollArea::vertical().auto_shrink([false, false]);
+    let mut open = None;
+    if !ui.ctx().wants_keyboard_input() {
+        let (down, up, enter) = ui.input_mut(|i| (
+            i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+        ));
+        if (down || up) && let Some(row) = app.global_results.select_step(down) {
+            // Scroll just enough to bring the selected row into view
+            let (offset, height) = app.global_results.view;
+            let top = row as f32 * row_step;
+            if top < offset {
+                area = area.vertical_scroll_offset(top);
+            } else if top + row_height > offset + height {
+                area = area.vertical_scroll_offset(top + row_height - height);
+            }
+        }
+        if enter { open = app.global_results.selected.and_then(|s| app.global_results.get(s)).cloned(); }
+    }
+
+    let mut toggle = None;
+    let results = &app.global_results;
+    let highlight = app.code_theme.search_highlight();
+    let output = area.show_rows(ui, row_height, results.rows().len(), |ui, range| {
+        for &row in &results.rows()[range] {
+            let (rect, resp) = ui.allocate_exact_size(egui::vec2(ui.available_width(), row_height), egui::Sense::click());
+            let selected = matches!(row, crate::search::ResultRow::Match(i) if results.selected == Some(i));
+            if selected {
+                ui.painter().rect_filled(rect, 2.0, ui.visuals().selection.bg_fill);
+            } else if resp.hovered() {
+                ui.painter().rect_filled(rect, 2.0, ui.visuals().widgets.hovered.weak_bg_fill);
+            }
+            let text_color = ui.visuals().text_color();
+            let weak = ui.visuals().weak_text_color();
+            let mut job = egui::text::LayoutJob::default();
+            let plain = |color| egui::TextFormat { font_id: font.clone(), color, ..Default::default() };
+            let indent = match row {
+                crate::search::ResultRow::File(group) => {
+                    let (path, count) = results.group(group);
+                    let arrow = if results.is_collapsed(group) { "▶" } else { "▼" };
+                    job.append(&format!("{} {}", arrow, path.display()), 0.0, plain(ui.visuals().strong_text_color()));
+                    job.append(&format!("  ({})", count), 0.0, plain(weak));
+                    if resp.clicked() { toggle = Some(group); }
+                    4.0
+                }
+                crate::search::ResultRow::Match(index) => {
+                    let Some(res) = results.get(index) else { continue; };
+                    let range = res.match_range.clone();
+                    job.append(&format!("{:>6}: ", res.line_index + 1), 0.0, plain(weak));
+                    job.append(&res.snippet[..range.start], 0.0, plain(text_color));
+                    job.append(&res.snippet[range.clone()], 0.0, egui::TextFormat { background: highlight, ..plain(text_color) });
+                    job.append(&res.snippet[range.end..], 0.0, plain(text_color));
+                    if resp.clicked() { open = Some(res.clone()); }
+                    24.0
+                }
+            };
+            job.wrap = egui::text::TextWrapping::truncate_at_width(rect.width() - indent);
+            let galley = ui.fonts(|f| f.layout_job(job));
+            let pos = egui::pos2(rect.left() + indent, rect.center().y - galley.size().y / 2.0);
+            ui.painter().galley(pos, galley, text_color);
+        }
+    });
+    app.global_results.view = (output.state.offset.y, output.inner_rect.height());
+
+    if let Some(group) = toggle { app.global_results.toggle_collapsed(group); }
+    if let Some(res) = open {
+        app.open_global_result(&res, ui.ctx());
+        // Close window
+        app.show_global_search_window = false;
+    }
+}
+
+// Folder, include/exclude globs and ignore rules for a folder search
+fn folder_search_options(ui: &mut egui::Ui, app: &mut crate::app::FileViewerApp) {
+    let options = &mut app.global_folder;
+    ui.horizontal(|ui| {
+        ui.label("Folder:");
+        match &options.root {
+            Some(root) => ui.label(RichText::new(root.display().to_string()).monospace()),
+            None => ui.label(RichText::new("none chosen").weak()),
+        };
+        if ui.button("📁 Choose…").clicked() {
+            // Start from the folder of the file on screen
+            let start = options.root.clone().or_else(|| app.current_path.as_deref().and_then(|p| p.parent()).map(PathBuf::from));
+            let mut dialog = rfd::FileDialog::new();
+            if let Some(start) = start { dialog = dialog.set_directory(start); }
+            if let Some(dir) = dialog.pick_folder() { options.root = Some(dir); }
+        }
+    });
+    egui::Grid::new("folder_search_globs").num_columns(2).show(ui, |ui| {
+        ui.label("Include:");
+        ui.add(egui::TextEdit::singleline(&mut options.include).hint_text("*.rs, src/**").desired_width(280.0));
+        ui.end_row();
+        ui.label("Exclude:");
+        ui.add(egui::TextEdit::singleline(&mut options.exclude).hint_text("target, *.min.js").desired_width(280.0));
+        ui.end_row();
+    });
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut options.respect_gitignore, "Respect .gitignore");
+        ui.checkbox(&mut options.include_hidden, "Hidden files");
+    });
+}
+
 pub(crate) fn settings_window(ctx: &egui::Context, app: &mut crate::app::FileViewerApp) {
     if !app.show_settings_window { return; }
     let mut open = app.show_settings_window;
@@ -548,6 +1028,11 @@ pub(crate) fn settings_window(ctx: &egui::Context, app: &mut crate::app::FileVie
             ui.add_space(8.0);
             ui.label(RichText::new("💾 Session").strong());
             ui.checkbox(&mut app.restore_session, "Restore previous session on startup");
+            if ui.checkbox(&mut app.single_instance, "Single instance: open files from new launches in this window")
+                .on_hover_text("Later `gfv FILE` launches add tabs here instead of opening a new window")
+                .changed() {
+                app.sync_instance_server(ctx);
+            }
         });
     app.show_settings_window = open;
 }
@@ -0,0 +1,125 @@
+// File watching for open tabs. Parent directories are watched (not the files
+// themselves) so editors that save via write-to-temp + rename are still seen.
+// Uses the platform watcher (inotify on Linux) and falls back to polling.
+
+use eframe::egui;
+use notify::event::{AccessKind, AccessMode};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+pub(crate) enum FileChange {
+    Modified(PathBuf),
+    Removed(PathBuf),
+}
+
+pub(crate) struct FileWatcher {
+    watcher: Box<dyn Watcher + Send>,
+    rx: Receiver<notify::Result<notify::Event>>,
+    // Watched directory -> number of tracked files inside it
+    dirs: HashMap<PathBuf, usize>,
+    // Absolute path (as notify reports it) -> path as the tab knows it
+    files: HashMap<PathBuf, PathBuf>,
+    // Tab paths of the last sync, so an unchanged tab set costs no path lookups
+    synced: Vec<PathBuf>,
+    pub(crate) polling: bool,
+}
+
+impl FileWatcher {
+    pub(crate) fn new(ctx: egui::Context) -> Option<Self> {
+        let (tx, rx) = channel();
+        let tx_poll = tx.clone();
+        let ctx_poll = ctx.clone();
+        let native = notify::recommended_watcher(move |res| {
+            let _ = tx.send(res);
+            ctx.request_repaint();
+        });
+        match native {
+            Ok(w) => Some(Self { watcher: Box::new(w), rx, dirs: HashMap::new(), files: HashMap::new(), synced: Vec::new(), polling: false }),
+            Err(_) => {
+                let config = notify::Config::default().with_poll_interval(POLL_INTERVAL);
+                let w = notify::PollWatcher::new(move |res| {
+                    let _ = tx_poll.send(res);
+                    ctx_poll.request_repaint();
+                }, config).ok()?;
+                Some(Self { watcher: Box::new(w), rx, dirs: HashMap::new(), files: HashMap::new(), synced: Vec::new(), polling: true })
+            }
+        }
+    }
+
+    /// Make the watched set match `paths`, adding and dropping directory
+    /// watches as files are opened and closed. Called every frame; returns
+    /// at once while the tabs stay the same.
+    pub(crate) fn sync<'a>(&mut self, paths: impl IntoIterator<Item = &'a PathBuf>) {
+        let paths: Vec<&PathBuf> = paths.into_iter().collect();
+        if paths.len() == self.synced.len() && paths.iter().zip(&self.synced).all(|(a, b)| *a == b) { return; }
+        self.synced = paths.iter().map(|p| (*p).clone()).collect();
+        let wanted: HashMap<PathBuf, PathBuf> = paths
+            .into_iter()
+            .map(|p| (std::path::absolute(p).unwrap_or_else(|_| p.clone()), p.clone()))
+            .collect();
+        if wanted.len() == self.files.len() && wanted.keys().all(|k| self.files.contains_key(k)) { return; }
+
+        let removed: Vec<PathBuf> = self.files.keys().filter(|k| !wanted.contains_key(*k)).cloned().collect();
+        let added: Vec<PathBuf> = wanted.keys().filter(|k| !self.files.contains_key(*k)).cloned().collect();
+        for path in removed {
+            if let Some(dir) = watch_dir(&path)
+                && let Some(count) = self.dirs.get_mut(&dir) {
+                *count -= 1;
+                if *count == 0 {
+                    self.dirs.remove(&dir);
+                    let _ = self.watcher.unwatch(&dir);
+                }
+            }
+        }
+        for path in added {
+            if let Some(dir) = watch_dir(&path) {
+                let count = self.dirs.entry(dir.clone()).or_insert(0);
+                if *count == 0 {
+                    let _ = self.watcher.watch(&dir, RecursiveMode::NonRecursive);
+                }
+                *count += 1;
+            }
+        }
+        self.files = wanted;
+    }
+
+    /// Drain pending events into one change per tracked path, deciding between
+    /// modified and removed by whether the file still exists.
+    pub(crate) fn poll_changes(&self) -> Vec<FileChange> {
+        let mut touched: Vec<PathBuf> = Vec::new();
+        for res in self.rx.try_iter() {
+            let Ok(event) = res else { continue; };
+            if is_read_only_access(&event.kind) { continue; }
+            for p in event.paths {
+                if let Some(orig) = self.files.get(&p)
+                    && !touched.contains(orig) {
+                    touched.push(orig.clone());
+                }
+            }
+        }
+        touched
+            .into_iter()
+            .map(|p| if p.exists() { FileChange::Modified(p) } else { FileChange::Removed(p) })
+            .collect()
+    }
+}
+
+fn watch_dir(path: &Path) -> Option<PathBuf> {
+    path.parent().map(Path::to_path_buf)
+}
+
+// Our own reads (and anyone else's) must not trigger reloads
+fn is_read_only_access(kind: &EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Access(AccessKind::Read)
+            | EventKind::Access(AccessKind::Open(_))
+            | EventKind::Access(AccessKind::Close(AccessMode::Read))
+            | EventKind::Access(AccessKind::Close(AccessMode::Execute))
+    )
+}